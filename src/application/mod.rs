@@ -1,3 +1,4 @@
 pub mod use_cases;
 pub mod services;
+pub mod providers;
 
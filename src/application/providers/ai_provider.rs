@@ -0,0 +1,70 @@
+use crate::application::providers::file_provider::format_age;
+use crate::domain::model::{Action, ChatSession, SearchResult};
+use crate::domain::ports::{IChatHistory, ISearchProvider};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Most saved chats listed under the "Ask AI" row.
+const MAX_CHATS: usize = 5;
+
+/// Produces the "Ask AI" row and the saved chats matching the query. The
+/// query itself runs in the UI, which owns the progress display, so this
+/// provider never activates anything.
+pub struct AiProvider {
+    chats: Arc<dyn IChatHistory + Send + Sync>,
+}
+
+impl AiProvider {
+    pub fn new(chats: Arc<dyn IChatHistory + Send + Sync>) -> Self {
+        Self { chats }
+    }
+}
+
+/// Row reopening a saved chat.
+pub fn chat_result(session: &ChatSession) -> SearchResult {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    SearchResult::new(session.title.clone(), "internet-chat", Action::ResumeChat(session.id.clone()))
+        .with_detail(format!("{} messages · {}", session.messages.len(), format_age(session.updated, now)))
+}
+
+impl ISearchProvider for AiProvider {
+    fn id(&self) -> &str { "ai" }
+
+    fn default_prefix(&self) -> &str { "? " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let prompt = query.trim();
+        if prompt.is_empty() { return vec![]; }
+
+        // Or a brain icon if available
+        let mut results = vec![SearchResult::new(format!("Ask AI: {}", prompt), "system-search", Action::AskAi(prompt.to_string()))];
+        let needle = prompt.to_lowercase();
+        results.extend(self.chats.sessions().iter()
+            .filter(|session| {
+                session.title.to_lowercase().contains(&needle)
+                    || session.messages.iter().any(|m| m.content.to_lowercase().contains(&needle))
+            })
+            .take(MAX_CHATS)
+            .map(chat_result));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockChats;
+
+    #[test]
+    fn test_lists_matching_chats_after_ask_row() {
+        let provider = AiProvider::new(Arc::new(MockChats::with_session("Plan the garden", "Where do tomatoes grow best?")));
+
+        let results = provider.search("tomatoes");
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].action, Action::AskAi("tomatoes".to_string()));
+        assert_eq!(results[1].name, "Plan the garden");
+        assert_eq!(results[1].action, Action::ResumeChat("1".to_string()));
+
+        assert_eq!(provider.search("taxes").len(), 1);
+    }
+}
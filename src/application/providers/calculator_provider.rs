@@ -0,0 +1,27 @@
+use crate::domain::model::{Action, SearchResult};
+use crate::domain::ports::{ICalculator, ISearchProvider};
+use std::sync::Arc;
+
+pub struct CalculatorProvider {
+    calculator: Arc<dyn ICalculator + Send + Sync>,
+}
+
+impl CalculatorProvider {
+    pub fn new(calculator: Arc<dyn ICalculator + Send + Sync>) -> Self {
+        Self { calculator }
+    }
+}
+
+impl ISearchProvider for CalculatorProvider {
+    fn id(&self) -> &str { "calculator" }
+
+    fn default_prefix(&self) -> &str { "c " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let expr = query.trim();
+        match self.calculator.calculate(expr) {
+            Some(result) => vec![SearchResult::new(format!("= {}", result), "accessories-calculator", Action::CopyText(result))],
+            None => vec![],
+        }
+    }
+}
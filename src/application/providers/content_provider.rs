@@ -0,0 +1,72 @@
+use crate::domain::model::{Action, SearchResult};
+use crate::domain::ports::{IFileIndexer, IMimeDatabase, ISearchProvider};
+use std::sync::Arc;
+
+/// Most matching files listed.
+const MAX_MATCHES: usize = 30;
+/// Longer matching lines are cut to this many characters.
+const MAX_LINE_CHARS: usize = 100;
+
+/// Full-text search over the indexed files, like grep.
+pub struct ContentSearchProvider {
+    indexer: Arc<dyn IFileIndexer + Send + Sync>,
+    mime: Arc<dyn IMimeDatabase + Send + Sync>,
+}
+
+impl ContentSearchProvider {
+    pub fn new(indexer: Arc<dyn IFileIndexer + Send + Sync>, mime: Arc<dyn IMimeDatabase + Send + Sync>) -> Self {
+        Self { indexer, mime }
+    }
+}
+
+impl ISearchProvider for ContentSearchProvider {
+    fn id(&self) -> &str { "content" }
+
+    fn default_prefix(&self) -> &str { "g " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let term = query.trim();
+        if term.is_empty() { return vec![]; }
+        // The index is built from 3-character sequences
+        if term.chars().count() < 3 {
+            return vec![SearchResult::new("Type at least 3 characters to search file contents", "system-search", Action::None)];
+        }
+
+        let home = std::env::var("HOME").unwrap_or_default();
+        self.indexer.search_content(term, MAX_MATCHES).into_iter()
+            .map(|found| {
+                let mut line: String = found.line.chars().take(MAX_LINE_CHARS).collect();
+                if line.len() < found.line.len() {
+                    line.push('…');
+                }
+                let shown_path = match found.path.strip_prefix(&home) {
+                    Some(rest) if !home.is_empty() => format!("~{}", rest),
+                    _ => found.path.clone(),
+                };
+                let icon = self.mime.icon_name(&self.mime.mime_type(&found.path));
+                SearchResult::new(line, &icon, Action::OpenPath(found.path))
+                    .with_detail(format!("{}:{}", shown_path, found.line_number))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockIndexer, MockMime};
+
+    #[test]
+    fn test_lists_matching_lines() {
+        let provider = ContentSearchProvider::new(Arc::new(MockIndexer), Arc::new(MockMime));
+
+        let results = provider.search("oat milk");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "- [ ] buy oat milk");
+        assert_eq!(results[0].action, Action::OpenPath("/home/user/notes/todo.md".to_string()));
+        assert!(results[0].detail.as_deref().unwrap().ends_with("todo.md:3"));
+
+        assert_eq!(provider.search("oa")[0].action, Action::None);
+        assert!(provider.search("kale").is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+use crate::domain::model::{Action, SearchResult};
+use crate::domain::ports::{IDictionaryService, ISearchProvider};
+use std::sync::Arc;
+
+pub struct DictionaryProvider {
+    dictionary: Arc<dyn IDictionaryService + Send + Sync>,
+}
+
+impl DictionaryProvider {
+    pub fn new(dictionary: Arc<dyn IDictionaryService + Send + Sync>) -> Self {
+        Self { dictionary }
+    }
+}
+
+impl ISearchProvider for DictionaryProvider {
+    fn id(&self) -> &str { "dictionary" }
+
+    fn default_prefix(&self) -> &str { "d " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let term = query.trim();
+        if term.is_empty() { return vec![]; }
+
+        let name = match self.dictionary.lookup(term) {
+            Some(def) => format!("{}: {}", term, def),
+            None => format!("Define '{}' on Google", term),
+        };
+        let url = format!("https://google.com/search?q=define+{}", encode_query(term));
+
+        vec![SearchResult::new(name, "accessories-dictionary", Action::OpenUrl(url))]
+    }
+}
+
+/// Percent-encodes a search term for use in a URL query string.
+fn encode_query(term: &str) -> String {
+    let mut encoded = String::new();
+    for byte in term.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
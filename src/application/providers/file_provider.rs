@@ -0,0 +1,354 @@
+use crate::domain::model::{Action, FileEntry, FileSort, InternalCommand, SearchResult};
+use crate::domain::ports::{IActionProvider, IFileSystem, IMimeDatabase, ISearchProvider};
+use crate::infrastructure::services::settings_store::SettingsStore;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Most entries listed for one directory; the rest are summed up in a last row.
+const MAX_ENTRIES: usize = 500;
+
+/// How a directory is listed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BrowseOptions {
+    pub show_hidden: bool,
+    pub sort: FileSort,
+}
+
+pub struct FileBrowserProvider {
+    fs: Arc<dyn IFileSystem + Send + Sync>,
+    mime: Arc<dyn IMimeDatabase + Send + Sync>,
+    settings: Arc<SettingsStore>,
+}
+
+impl FileBrowserProvider {
+    pub fn new(
+        fs: Arc<dyn IFileSystem + Send + Sync>,
+        mime: Arc<dyn IMimeDatabase + Send + Sync>,
+        settings: Arc<SettingsStore>,
+    ) -> Self {
+        Self { fs, mime, settings }
+    }
+
+    /// Renames `path` to `new_name` within its directory.
+    pub fn rename_action(path: &str, new_name: &str) -> Action {
+        // NUL can't appear in paths, so it safely separates the two
+        Action::Provider { id: "files".to_string(), payload: format!("rename:{}\0{}", path, new_name) }
+    }
+
+    /// Creates the folder `name` in `dir`.
+    pub fn new_folder_action(dir: &str, name: &str) -> Action {
+        Action::Provider { id: "files".to_string(), payload: format!("mkdir:{}\0{}", dir, name) }
+    }
+
+    /// Splits the query into the directory to list and the text its entries
+    /// are matched against. `~` and relative paths start at the home directory.
+    fn resolve(&self, query: &str) -> (String, String) {
+        let input = query.trim();
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
+        let path = if input.is_empty() {
+            home
+        } else if let Some(rest) = input.strip_prefix('~') {
+            format!("{}{}", home, rest)
+        } else if !input.starts_with('/') {
+            format!("{}/{}", home.trim_end_matches('/'), input)
+        } else {
+            input.to_string()
+        };
+
+        if path.ends_with('/') || self.fs.is_dir(&path) {
+            return (path, String::new());
+        }
+        match path.rfind('/') {
+            Some(0) => ("/".to_string(), path[1..].to_string()),
+            Some(i) => (path[..i].to_string(), path[i + 1..].to_string()),
+            None => (path, String::new()),
+        }
+    }
+
+    /// Lists the directory in `query`, fuzzy-filtered by the name being typed.
+    pub fn browse(&self, query: &str, options: BrowseOptions) -> Vec<SearchResult> {
+        let (dir, fragment) = self.resolve(query);
+        // Typing a dot asks for hidden files
+        let show_hidden = options.show_hidden || fragment.starts_with('.');
+        let matcher = SkimMatcherV2::default();
+
+        let mut entries: Vec<(i64, FileEntry)> = self.fs.read_dir(&dir).into_iter()
+            .filter(|entry| show_hidden || !entry.name.starts_with('.'))
+            .filter_map(|entry| {
+                if fragment.is_empty() {
+                    return Some((0, entry));
+                }
+                matcher.fuzzy_match(&entry.name, &fragment).map(|score| (score, entry))
+            })
+            .collect();
+        // Best match first; otherwise folders first, then the chosen order
+        entries.sort_by(|(score_a, a), (score_b, b)| {
+            score_b.cmp(score_a)
+                .then(b.is_dir.cmp(&a.is_dir))
+                .then_with(|| compare(a, b, options.sort))
+        });
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let total = entries.len();
+        let mut results: Vec<SearchResult> = entries.into_iter()
+            .take(MAX_ENTRIES)
+            .map(|(_, entry)| self.row(&dir, entry, now))
+            .collect();
+        if total > MAX_ENTRIES {
+            let more = format!("{} more, type to filter", total - MAX_ENTRIES);
+            results.push(SearchResult::new(more, "view-more", Action::None));
+        }
+        results
+    }
+
+    fn row(&self, dir: &str, entry: FileEntry, now: u64) -> SearchResult {
+        let path = format!("{}/{}", dir.trim_end_matches('/'), entry.name);
+        let age = entry.modified.map(|modified| format_age(modified, now));
+        if entry.is_dir {
+            let row = SearchResult::new(format!("{}/", entry.name), "folder", Action::OpenPath(path));
+            return match age {
+                Some(age) => row.with_detail(age),
+                None => row,
+            };
+        }
+        let icon = self.mime.icon_name(&self.mime.mime_type(&path));
+        let detail = match age {
+            Some(age) => format!("{} · {}", format_size(entry.size), age),
+            None => format_size(entry.size),
+        };
+        SearchResult::new(entry.name, &icon, Action::OpenPath(path)).with_detail(detail)
+    }
+
+    fn rename(&self, path: &str, new_name: &str) -> Result<(), String> {
+        let new_name = valid_name(new_name)?;
+        let parent = std::path::Path::new(path).parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| "/".to_string());
+        self.fs.rename(path, &format!("{}/{}", parent.trim_end_matches('/'), new_name))
+    }
+
+    fn new_folder(&self, dir: &str, name: &str) -> Result<(), String> {
+        let name = valid_name(name)?;
+        self.fs.create_dir(&format!("{}/{}", dir.trim_end_matches('/'), name))
+    }
+}
+
+fn compare(a: &FileEntry, b: &FileEntry, sort: FileSort) -> Ordering {
+    let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+    match sort {
+        FileSort::Name => by_name(),
+        FileSort::Modified => b.modified.cmp(&a.modified).then_with(by_name),
+        FileSort::Size => b.size.cmp(&a.size).then_with(by_name),
+    }
+}
+
+fn valid_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." {
+        return Err("Enter a name".to_string());
+    }
+    if name.contains('/') {
+        return Err("A name cannot contain \"/\"".to_string());
+    }
+    Ok(name)
+}
+
+/// Size in SI units, like GNOME Files (`1.5 MB`).
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1000.0 {
+            break;
+        }
+        value /= 1000.0;
+        unit = next;
+    }
+    format!("{:.1} {}", value, unit)
+}
+
+pub(crate) fn format_age(modified: u64, now: u64) -> String {
+    let secs = now.saturating_sub(modified);
+    let (minute, hour, day) = (60, 3600, 86_400);
+    match secs {
+        s if s < minute => "just now".to_string(),
+        s if s < hour => format!("{} min ago", s / minute),
+        s if s < day => format!("{} h ago", s / hour),
+        s if s < 2 * day => "yesterday".to_string(),
+        s if s < 60 * day => format!("{} days ago", s / day),
+        s if s < 730 * day => format!("{} months ago", s / (30 * day)),
+        s => format!("{} years ago", s / (365 * day)),
+    }
+}
+
+impl ISearchProvider for FileBrowserProvider {
+    fn id(&self) -> &str { "files" }
+
+    fn default_prefix(&self) -> &str { "f " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let options = BrowseOptions {
+            show_hidden: self.settings.get_show_hidden_files(),
+            sort: self.settings.get_file_sort(),
+        };
+        self.browse(query, options)
+    }
+
+    fn activate(&self, action: &Action) -> Option<Result<(), String>> {
+        let Action::Provider { id, payload } = action else { return None };
+        if id != self.id() {
+            return None;
+        }
+        let result = match payload.split_once(':') {
+            Some(("trash", path)) => self.fs.trash(path),
+            Some(("rename", rest)) => {
+                let (path, name) = rest.split_once('\0')?;
+                self.rename(path, name)
+            },
+            Some(("mkdir", rest)) => {
+                let (dir, name) = rest.split_once('\0')?;
+                self.new_folder(dir, name)
+            },
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+impl IActionProvider for FileBrowserProvider {
+    fn actions_for(&self, result: &SearchResult) -> Vec<SearchResult> {
+        let Action::OpenPath(path) = &result.action else { return vec![] };
+        let dir = if self.fs.is_dir(path) {
+            path.clone()
+        } else {
+            std::path::Path::new(path).parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| "/".to_string())
+        };
+        let mut actions = vec![SearchResult::new("Open", "document-open", Action::OpenPath(path.clone()))];
+        // "Open with" for every other registered application
+        let handlers = self.mime.handlers(&self.mime.mime_type(path));
+        actions.extend(handlers.into_iter().skip(1).map(|handler| {
+            let icon = handler.icon.clone().unwrap_or_else(|| "application-x-executable".to_string());
+            let action = Action::OpenWith { path: path.clone(), desktop_id: handler.desktop_id };
+            SearchResult::new(format!("Open with {}", handler.name), &icon, action)
+        }));
+        let provider = |payload: String| Action::Provider { id: self.id().to_string(), payload };
+        actions.extend([
+            SearchResult::new("Show in Folder", "folder", Action::ShowInFolder(path.clone())),
+            SearchResult::new("Copy Path", "edit-copy", Action::CopyText(path.clone())),
+            SearchResult::new("Open Terminal Here", "utilities-terminal", Action::OpenTerminal(dir.clone())),
+            SearchResult::new("Rename…", "document-edit", Action::Internal(InternalCommand::RenameFile(path.clone()))),
+            SearchResult::new("Move to Trash", "user-trash", provider(format!("trash:{}", path))),
+            SearchResult::new("New Folder…", "folder-new", Action::Internal(InternalCommand::NewFolder(dir))),
+        ]);
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockMime;
+    use std::sync::Mutex;
+
+    /// `/home/u` with a few entries; records file operations.
+    #[derive(Default)]
+    struct HomeFS {
+        ops: Mutex<Vec<String>>,
+    }
+
+    impl IFileSystem for HomeFS {
+        fn list_dir(&self, _path: &str) -> Vec<String> { unimplemented!() }
+        fn is_dir(&self, path: &str) -> bool { path == "/home/u" || path == "/home/u/Documents" }
+        fn exists(&self, _path: &str) -> bool { true }
+        fn read_dir(&self, path: &str) -> Vec<FileEntry> {
+            if path.trim_end_matches('/') != "/home/u" {
+                return vec![];
+            }
+            let entry = |name: &str, is_dir, size, modified| FileEntry { name: name.to_string(), is_dir, size, modified: Some(modified) };
+            vec![
+                entry("notes.txt", false, 1_500, 300),
+                entry("Documents", true, 0, 100),
+                entry(".bashrc", false, 20, 200),
+                entry("big.iso", false, 4_000_000_000, 50),
+                entry("new-todo.md", false, 10, 400),
+            ]
+        }
+        fn trash(&self, path: &str) -> Result<(), String> {
+            self.ops.lock().unwrap().push(format!("trash {}", path));
+            Ok(())
+        }
+        fn rename(&self, path: &str, new_path: &str) -> Result<(), String> {
+            self.ops.lock().unwrap().push(format!("rename {} {}", path, new_path));
+            Ok(())
+        }
+        fn create_dir(&self, path: &str) -> Result<(), String> {
+            self.ops.lock().unwrap().push(format!("mkdir {}", path));
+            Ok(())
+        }
+    }
+
+    fn provider(fs: Arc<HomeFS>) -> FileBrowserProvider {
+        FileBrowserProvider::new(fs, Arc::new(MockMime), Arc::new(SettingsStore::new()))
+    }
+
+    fn names(results: Vec<SearchResult>) -> Vec<String> {
+        results.into_iter().map(|r| r.name).collect()
+    }
+
+    #[test]
+    fn test_sorting_and_hidden_files() {
+        let files = provider(Arc::new(HomeFS::default()));
+        let by = |sort, show_hidden| names(files.browse("/home/u/", BrowseOptions { show_hidden, sort }));
+
+        assert_eq!(by(FileSort::Name, false), ["Documents/", "big.iso", "new-todo.md", "notes.txt"]);
+        assert_eq!(by(FileSort::Modified, false), ["Documents/", "new-todo.md", "notes.txt", "big.iso"]);
+        assert_eq!(by(FileSort::Size, true), ["Documents/", "big.iso", "notes.txt", ".bashrc", "new-todo.md"]);
+        // A leading dot shows hidden files without the toggle
+        assert_eq!(names(files.browse("/home/u/.b", BrowseOptions::default())), [".bashrc"]);
+    }
+
+    #[test]
+    fn test_fuzzy_match_and_metadata() {
+        let files = provider(Arc::new(HomeFS::default()));
+        let results = files.browse("/home/u/ntd", BrowseOptions::default());
+        assert_eq!(names(results.clone()), ["new-todo.md"]);
+        assert_eq!(results[0].icon.as_deref(), Some("text-plain"));
+
+        let notes = &files.browse("/home/u/notes", BrowseOptions::default())[0];
+        assert!(notes.detail.as_deref().unwrap().starts_with("1.5 kB · "));
+        assert_eq!(format_size(4_000_000_000), "4.0 GB");
+        assert_eq!(format_age(1_000, 1_000 + 90), "1 min ago");
+        assert_eq!(format_age(0, 3 * 86_400), "3 days ago");
+    }
+
+    #[test]
+    fn test_file_operations() {
+        let fs = Arc::new(HomeFS::default());
+        let files = provider(fs.clone());
+
+        let trash = files.actions_for(&SearchResult::new("notes.txt", "", Action::OpenPath("/home/u/notes.txt".to_string())))
+            .into_iter()
+            .find(|a| a.name == "Move to Trash")
+            .unwrap();
+        files.activate(&trash.action).unwrap().unwrap();
+        files.activate(&FileBrowserProvider::rename_action("/home/u/notes.txt", " todo.txt ")).unwrap().unwrap();
+        files.activate(&FileBrowserProvider::new_folder_action("/home/u", "Projects")).unwrap().unwrap();
+        assert!(files.activate(&FileBrowserProvider::rename_action("/home/u/notes.txt", "a/b")).unwrap().is_err());
+        assert!(files.activate(&FileBrowserProvider::new_folder_action("/home/u", "..")).unwrap().is_err());
+
+        assert_eq!(fs.ops.lock().unwrap().as_slice(), [
+            "trash /home/u/notes.txt",
+            "rename /home/u/notes.txt /home/u/todo.txt",
+            "mkdir /home/u/Projects",
+        ]);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::domain::model::{Action, InternalCommand, SearchResult};
+use crate::domain::ports::ISearchProvider;
+
+/// Internal Launch commands (About, Quit, Settings). These open dialogs or
+/// stop the app, so the UI handles them rather than the provider.
+#[derive(Default)]
+pub struct LaunchProvider;
+
+impl LaunchProvider {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ISearchProvider for LaunchProvider {
+    fn id(&self) -> &str { "launch" }
+
+    fn default_prefix(&self) -> &str { "l " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        // internal settings
+        let items = vec![
+            SearchResult::new("About Launch", "help-about", Action::Internal(InternalCommand::About)),
+            SearchResult::new("Quit", "application-exit", Action::Internal(InternalCommand::Quit)),
+            SearchResult::new("Settings", "preferences-system", Action::Internal(InternalCommand::Settings)),
+        ];
+        items.into_iter()
+            .filter(|app| app.name.to_lowercase().contains(&query.trim().to_lowercase()))
+            .collect()
+    }
+}
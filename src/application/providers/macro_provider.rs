@@ -0,0 +1,83 @@
+use crate::domain::model::{Action, InternalCommand, Macro, SearchResult};
+use crate::domain::ports::{IActionProvider, IMacroRepository, ISearchProvider};
+use std::sync::Arc;
+
+/// Lists macros and handles their duplicate/delete sub-actions. Running them
+/// needs the executor and app search, so `Action::RunMacro` is left to `ExecuteCommand`.
+/// Deleting is confirmed in the UI first, which then runs `delete_action`.
+pub struct MacroProvider {
+    macros: Arc<dyn IMacroRepository + Send + Sync>,
+}
+
+impl MacroProvider {
+    pub fn new(macros: Arc<dyn IMacroRepository + Send + Sync>) -> Self {
+        Self { macros }
+    }
+
+    /// Deletes the macro `name`, once the user confirmed it.
+    pub fn delete_action(name: &str) -> Action {
+        Action::Provider { id: "macros".to_string(), payload: format!("delete:{}", name) }
+    }
+}
+
+impl ISearchProvider for MacroProvider {
+    fn id(&self) -> &str { "macros" }
+
+    fn default_prefix(&self) -> &str { "m " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let name = query.trim();
+        if self.macros.get(name).is_some() {
+            return vec![SearchResult::new(format!("Macro: {}", name), "system-run", Action::RunMacro(name.to_string()))];
+        }
+        // Show all macros matching
+        let all = self.macros.get_all();
+        all.into_iter()
+            .filter(|m| m.name.contains(name))
+            .map(|m| SearchResult::new(format!("Macro: {}", m.name), "system-run", Action::RunMacro(m.name)))
+            .collect()
+    }
+
+    fn activate(&self, action: &Action) -> Option<Result<(), String>> {
+        let Action::Provider { id, payload } = action else { return None };
+        if id != self.id() {
+            return None;
+        }
+        let result = match payload.split_once(':') {
+            Some(("duplicate", name)) => match self.macros.get(name) {
+                Some(mac) => {
+                    let name = copy_name(name, |n| self.macros.get(n).is_some());
+                    self.macros.add(Macro { name, actions: mac.actions })
+                },
+                None => Err(format!("No macro named {}", name)),
+            },
+            Some(("delete", name)) => self.macros.remove(name),
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+impl IActionProvider for MacroProvider {
+    fn actions_for(&self, result: &SearchResult) -> Vec<SearchResult> {
+        let Action::RunMacro(name) = &result.action else { return vec![] };
+        let provider = |payload: String| Action::Provider { id: self.id().to_string(), payload };
+        vec![
+            SearchResult::new("Run", "system-run", Action::RunMacro(name.clone())),
+            SearchResult::new("Edit", "document-edit", Action::Internal(InternalCommand::EditMacro(name.clone()))),
+            SearchResult::new("Duplicate", "edit-copy", provider(format!("duplicate:{}", name))),
+            SearchResult::new("Delete…", "edit-delete", Action::Internal(InternalCommand::DeleteMacro(name.clone()))),
+        ]
+    }
+}
+
+/// First free "<name> copy", "<name> copy 2", ... name.
+pub(crate) fn copy_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut candidate = format!("{} copy", name);
+    let mut n = 2;
+    while taken(&candidate) {
+        candidate = format!("{} copy {}", name, n);
+        n += 1;
+    }
+    candidate
+}
@@ -0,0 +1,13 @@
+pub mod registry;
+pub mod window_provider;
+pub mod terminal_provider;
+pub mod file_provider;
+pub mod content_provider;
+pub mod shortcut_provider;
+pub mod calculator_provider;
+pub mod dictionary_provider;
+pub mod ai_provider;
+pub mod macro_provider;
+pub mod system_provider;
+pub mod time_provider;
+pub mod launch_provider;
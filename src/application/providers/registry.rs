@@ -0,0 +1,163 @@
+use crate::domain::model::{Action, SearchResult};
+use crate::domain::ports::{IActionProvider, ISearchProvider};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+#[derive(Default)]
+pub struct ProviderRegistry {
+    providers: RwLock<Vec<Arc<dyn ISearchProvider + Send + Sync>>>,
+    action_providers: RwLock<Vec<Arc<dyn IActionProvider + Send + Sync>>>,
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        Self {
+            providers: RwLock::new(Vec::new()),
+            action_providers: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Adds a provider. A provider registered with an existing id replaces the old one,
+    /// so built-in modes can be overridden as well as extended.
+    pub fn register(&self, provider: Arc<dyn ISearchProvider + Send + Sync>) {
+        let mut providers = self.providers.write().unwrap();
+        providers.retain(|p| p.id() != provider.id());
+        providers.push(provider);
+    }
+
+    pub fn unregister(&self, id: &str) {
+        self.providers.write().unwrap().retain(|p| p.id() != id);
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<dyn ISearchProvider + Send + Sync>> {
+        self.providers.read().unwrap().iter().find(|p| p.id() == id).cloned()
+    }
+
+    /// Returns (id, effective prefix) for every registered provider.
+    pub fn prefixes(&self, overrides: &HashMap<String, String>) -> Vec<(String, String)> {
+        self.providers.read().unwrap().iter()
+            .map(|p| (p.id().to_string(), Self::prefix_for(p.as_ref(), overrides)))
+            .collect()
+    }
+
+    /// Finds the provider whose prefix matches the query and returns it along with
+    /// the query stripped of that prefix. The longest matching prefix wins so that
+    /// e.g. `ss ` is not shadowed by a user-defined `s `.
+    pub fn resolve<'a>(
+        &self,
+        query: &'a str,
+        overrides: &HashMap<String, String>,
+    ) -> Option<(Arc<dyn ISearchProvider + Send + Sync>, &'a str)> {
+        let providers = self.providers.read().unwrap();
+        let mut best: Option<(Arc<dyn ISearchProvider + Send + Sync>, usize)> = None;
+
+        for provider in providers.iter() {
+            let prefix = Self::prefix_for(provider.as_ref(), overrides);
+            if prefix.is_empty() || !query.starts_with(&prefix) {
+                continue;
+            }
+            if best.as_ref().is_none_or(|(_, len)| prefix.len() > *len) {
+                best = Some((provider.clone(), prefix.len()));
+            }
+        }
+
+        best.map(|(provider, len)| (provider, &query[len..]))
+    }
+
+    /// Offers an action to each provider until one claims it, returning its outcome.
+    pub fn activate(&self, action: &Action) -> Option<Result<(), String>> {
+        let providers = self.providers.read().unwrap().clone();
+        providers.iter().find_map(|p| p.activate(action))
+    }
+
+    /// Adds a source of Ctrl+Enter sub-actions.
+    pub fn register_actions(&self, provider: Arc<dyn IActionProvider + Send + Sync>) {
+        self.action_providers.write().unwrap().push(provider);
+    }
+
+    /// Sub-actions from every action provider that recognises the result.
+    pub fn actions_for(&self, result: &SearchResult) -> Vec<SearchResult> {
+        let providers = self.action_providers.read().unwrap().clone();
+        providers.iter().flat_map(|p| p.actions_for(result)).collect()
+    }
+
+    fn prefix_for(provider: &(dyn ISearchProvider + Send + Sync), overrides: &HashMap<String, String>) -> String {
+        overrides.get(provider.id())
+            .cloned()
+            .unwrap_or_else(|| provider.default_prefix().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::SearchResult;
+
+    struct EchoProvider {
+        id: &'static str,
+        prefix: &'static str,
+    }
+
+    impl ISearchProvider for EchoProvider {
+        fn id(&self) -> &str { self.id }
+        fn default_prefix(&self) -> &str { self.prefix }
+        fn search(&self, query: &str) -> Vec<SearchResult> {
+            let action = Action::Provider { id: self.id.to_string(), payload: query.to_string() };
+            vec![SearchResult::new(format!("{}: {}", self.id, query), "system-run", action)]
+        }
+        fn activate(&self, action: &Action) -> Option<Result<(), String>> {
+            matches!(action, Action::Provider { id, .. } if id == self.id).then_some(Ok(()))
+        }
+    }
+
+    fn registry() -> ProviderRegistry {
+        let registry = ProviderRegistry::new();
+        registry.register(Arc::new(EchoProvider { id: "short", prefix: "s " }));
+        registry.register(Arc::new(EchoProvider { id: "shortcuts", prefix: "ss " }));
+        registry
+    }
+
+    #[test]
+    fn test_resolve_prefers_longest_prefix() {
+        let registry = registry();
+        let (provider, rest) = registry.resolve("ss term", &HashMap::new()).unwrap();
+        assert_eq!(provider.id(), "shortcuts");
+        assert_eq!(rest, "term");
+
+        let (provider, rest) = registry.resolve("s term", &HashMap::new()).unwrap();
+        assert_eq!(provider.id(), "short");
+        assert_eq!(rest, "term");
+
+        assert!(registry.resolve("firefox", &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_resolve_uses_overrides() {
+        let registry = registry();
+        let overrides = HashMap::from([("shortcuts".to_string(), "; ".to_string())]);
+
+        let (provider, rest) = registry.resolve("; term", &overrides).unwrap();
+        assert_eq!(provider.id(), "shortcuts");
+        assert_eq!(rest, "term");
+
+        // The default prefix no longer routes to the remapped provider
+        assert!(registry.resolve("ss term", &overrides).is_none_or(|(p, _)| p.id() != "shortcuts"));
+    }
+
+    #[test]
+    fn test_register_replaces_same_id() {
+        let registry = registry();
+        registry.register(Arc::new(EchoProvider { id: "short", prefix: "q " }));
+        assert_eq!(registry.prefixes(&HashMap::new()).len(), 2);
+        assert!(registry.resolve("q x", &HashMap::new()).is_some());
+        assert!(registry.resolve("s x", &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_activate_dispatches_to_owner() {
+        let registry = registry();
+        let action = registry.resolve("ss term", &HashMap::new()).unwrap().0.search("term")[0].action.clone();
+        assert_eq!(registry.activate(&action), Some(Ok(())));
+        assert_eq!(registry.activate(&Action::Launch("firefox".to_string())), None);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::domain::model::{Action, InternalCommand, SearchResult};
+use crate::domain::ports::{IActionProvider, ISearchProvider, IShortcutRepository};
+use crate::application::providers::macro_provider::copy_name;
+use std::sync::Arc;
+
+pub struct ShortcutProvider {
+    shortcuts: Arc<dyn IShortcutRepository + Send + Sync>,
+}
+
+impl ShortcutProvider {
+    pub fn new(shortcuts: Arc<dyn IShortcutRepository + Send + Sync>) -> Self {
+        Self { shortcuts }
+    }
+
+    /// Deletes the shortcut `key`, once the user confirmed it.
+    pub fn delete_action(key: &str) -> Action {
+        Action::Provider { id: "shortcuts".to_string(), payload: format!("delete:{}", key) }
+    }
+}
+
+impl ISearchProvider for ShortcutProvider {
+    fn id(&self) -> &str { "shortcuts" }
+
+    fn default_prefix(&self) -> &str { "ss " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let key = query.trim();
+        if let Some(cmd) = self.shortcuts.get(key) {
+            return vec![SearchResult::new(format!("Shortcut: {}", key), "emblem-symbolic-link", Action::Shell(cmd))];
+        }
+        // Show all shortcuts matching?
+        let all = self.shortcuts.get_all();
+        all.into_iter()
+            .filter(|(k, _)| k.contains(key))
+            .map(|(k, v)| SearchResult::new(format!("Shortcut: {}", k), "emblem-symbolic-link", Action::Shell(v)))
+            .collect()
+    }
+
+    fn activate(&self, action: &Action) -> Option<Result<(), String>> {
+        let Action::Provider { id, payload } = action else { return None };
+        if id != self.id() {
+            return None;
+        }
+        let result = match payload.split_once(':') {
+            Some(("duplicate", key)) => match self.shortcuts.get(key) {
+                Some(cmd) => {
+                    let key = copy_name(key, |k| self.shortcuts.get(k).is_some());
+                    self.shortcuts.add(key, cmd)
+                },
+                None => Err(format!("No shortcut named {}", key)),
+            },
+            Some(("delete", key)) => self.shortcuts.remove(key),
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+impl IActionProvider for ShortcutProvider {
+    fn actions_for(&self, result: &SearchResult) -> Vec<SearchResult> {
+        // Shortcut rows run a plain shell command, so recognise them by key and command
+        let Action::Shell(cmd) = &result.action else { return vec![] };
+        let key = result.name.strip_prefix("Shortcut: ").unwrap_or(&result.name);
+        if self.shortcuts.get(key).as_ref() != Some(cmd) {
+            return vec![];
+        }
+        let provider = |payload: String| Action::Provider { id: self.id().to_string(), payload };
+        vec![
+            SearchResult::new("Run", "system-run", Action::Shell(cmd.clone())),
+            SearchResult::new("Edit", "document-edit", Action::Internal(InternalCommand::EditShortcut(key.to_string()))),
+            SearchResult::new("Duplicate", "edit-copy", provider(format!("duplicate:{}", key))),
+            SearchResult::new("Delete…", "edit-delete", Action::Internal(InternalCommand::DeleteShortcut(key.to_string()))),
+        ]
+    }
+}
@@ -0,0 +1,38 @@
+use crate::domain::model::{Action, SearchResult};
+use crate::domain::ports::{ISearchProvider, ISystemPower};
+use std::sync::Arc;
+
+pub struct SystemProvider {
+    power: Arc<dyn ISystemPower + Send + Sync>,
+}
+
+impl SystemProvider {
+    pub fn new(power: Arc<dyn ISystemPower + Send + Sync>) -> Self {
+        Self { power }
+    }
+}
+
+impl ISearchProvider for SystemProvider {
+    fn id(&self) -> &str { "system" }
+
+    fn default_prefix(&self) -> &str { "! " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let action = query.trim();
+        // predefined actions
+        let actions = vec![
+            "suspend", "reboot", "poweroff", "lock", "hibernate",
+            "mute", "mute_mic", "mute_all",
+            "toggle_night_light", "toggle_dark_mode", "toggle_dnd"
+        ];
+        actions.into_iter()
+            .filter(|a| a.starts_with(action))
+            .map(|a| SearchResult::new(format!("System: {}", a), "system-shutdown", Action::System(a.to_string())))
+            .collect()
+    }
+
+    fn activate(&self, action: &Action) -> Option<Result<(), String>> {
+        let Action::System(sys_action) = action else { return None };
+        Some(self.power.execute(sys_action))
+    }
+}
@@ -0,0 +1,285 @@
+use crate::domain::model::{shell_quote, Action, SearchResult};
+use crate::domain::ports::{IFileSystem, ISearchProvider, IUsageHistory};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::collections::{BTreeSet, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Most completion rows shown at once.
+const MAX_COMPLETIONS: usize = 8;
+/// Most history rows shown at once.
+const MAX_HISTORY: usize = 8;
+/// How long the list of `$PATH` executables is reused before rescanning.
+const EXECUTABLES_TTL: Duration = Duration::from_secs(30);
+const HISTORY_ICON: &str = "document-open-recent";
+
+/// Command mode: runs what was typed in a terminal or in the background,
+/// recalls earlier commands and completes program names and paths.
+pub struct TerminalProvider {
+    fs: Arc<dyn IFileSystem + Send + Sync>,
+    history: Arc<dyn IUsageHistory + Send + Sync>,
+    /// `$PATH` entries searched for program names.
+    path_dirs: Vec<String>,
+    home: String,
+    /// Where commands run, for completing relative paths.
+    cwd: String,
+    executables: Mutex<Option<(Instant, Vec<String>)>>,
+}
+
+impl TerminalProvider {
+    pub fn new(fs: Arc<dyn IFileSystem + Send + Sync>, history: Arc<dyn IUsageHistory + Send + Sync>) -> Self {
+        let path = std::env::var("PATH").unwrap_or_default();
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
+        let cwd = std::env::current_dir()
+            .map(|d| d.to_string_lossy().to_string())
+            .unwrap_or_else(|_| home.clone());
+        Self {
+            fs,
+            history,
+            path_dirs: path.split(':').filter(|d| !d.is_empty()).map(str::to_string).collect(),
+            home,
+            cwd,
+            executables: Mutex::new(None),
+        }
+    }
+
+    /// Names of the programs on `$PATH`, sorted and without duplicates.
+    fn executables(&self) -> Vec<String> {
+        let mut cached = self.executables.lock().unwrap();
+        if let Some((scanned, names)) = cached.as_ref()
+            && scanned.elapsed() < EXECUTABLES_TTL
+        {
+            return names.clone();
+        }
+        let names: BTreeSet<String> = self.path_dirs.iter()
+            .flat_map(|dir| self.fs.list_dir(dir))
+            .collect();
+        let names: Vec<String> = names.into_iter().collect();
+        *cached = Some((Instant::now(), names.clone()));
+        names
+    }
+
+    /// Earlier commands, most recent first, each with how it was run.
+    fn previous_commands(&self) -> Vec<Action> {
+        let mut seen = HashSet::new();
+        self.history.records().into_iter().rev()
+            .filter_map(|record| {
+                if let Some(cmd) = record.key.strip_prefix("terminal:") {
+                    Some(Action::RunInTerminal(cmd.to_string()))
+                } else {
+                    record.key.strip_prefix("shell:").map(|cmd| Action::Shell(cmd.to_string()))
+                }
+            })
+            .filter(|action| seen.insert(command_of(action).to_string()))
+            .collect()
+    }
+
+    /// Previous commands matching `query` fuzzily, like a shell's Ctrl+R.
+    fn history_results(&self, query: &str) -> Vec<SearchResult> {
+        let matcher = SkimMatcherV2::default();
+        let mut matches: Vec<(i64, Action)> = self.previous_commands().into_iter()
+            .filter(|action| command_of(action) != query)
+            .filter_map(|action| {
+                if query.is_empty() {
+                    return Some((0, action));
+                }
+                matcher.fuzzy_match(command_of(&action), query).map(|score| (score, action))
+            })
+            .collect();
+        // Stable, so equal scores stay in recency order
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        matches.into_iter()
+            .take(MAX_HISTORY)
+            .map(|(_, action)| SearchResult::new(command_of(&action), HISTORY_ICON, action.clone()))
+            .collect()
+    }
+
+    /// Completions of the word being typed: a program name for the first
+    /// word, a path for the others.
+    fn completions(&self, query: &str) -> Vec<SearchResult> {
+        let (head, word) = match query.rfind(char::is_whitespace) {
+            Some(i) => query.split_at(i + 1),
+            None => ("", query),
+        };
+        if word.is_empty() {
+            return vec![];
+        }
+
+        let candidates = if head.trim().is_empty() && !word.contains('/') && !word.starts_with('~') {
+            self.complete_program(word)
+        } else {
+            self.complete_path(word)
+        };
+
+        candidates.into_iter()
+            .take(MAX_COMPLETIONS)
+            .map(|(completed, icon)| {
+                let text = format!("{}{}", head, completed);
+                SearchResult::new(completed.trim_end(), icon, Action::Complete(text))
+            })
+            .collect()
+    }
+
+    fn complete_program(&self, word: &str) -> Vec<(String, &'static str)> {
+        let mut names: Vec<String> = self.executables().into_iter()
+            .filter(|name| name.starts_with(word) && name != word)
+            .collect();
+        names.sort_by_key(|name| name.len());
+        names.into_iter().map(|name| (format!("{} ", name), "application-x-executable")).collect()
+    }
+
+    fn complete_path(&self, word: &str) -> Vec<(String, &'static str)> {
+        let (dir_part, name_prefix) = match word.rfind('/') {
+            Some(i) => word.split_at(i + 1),
+            None => ("", word),
+        };
+        let dir = if let Some(rest) = dir_part.strip_prefix('~') {
+            format!("{}{}", self.home, rest)
+        } else if dir_part.starts_with('/') {
+            dir_part.to_string()
+        } else {
+            format!("{}/{}", self.cwd.trim_end_matches('/'), dir_part)
+        };
+        let dir = if dir.is_empty() { "/".to_string() } else { dir };
+
+        let mut names: Vec<String> = self.fs.list_dir(&dir).into_iter()
+            // Hidden files only when asked for
+            .filter(|name| name.starts_with(name_prefix) && (name_prefix.starts_with('.') || !name.starts_with('.')))
+            .collect();
+        names.sort();
+
+        names.into_iter()
+            .map(|name| {
+                let is_dir = self.fs.is_dir(&format!("{}/{}", dir.trim_end_matches('/'), name));
+                let completed = format!("{}{}", dir_part, name);
+                // Quoting turns `~` into a literal, so spell the home directory out
+                let unquoted = completed.strip_prefix('~').unwrap_or(&completed);
+                let completed = if shell_quote(unquoted) == unquoted {
+                    completed
+                } else {
+                    shell_quote(&format!("{}/{}", dir.trim_end_matches('/'), name))
+                };
+                if is_dir {
+                    (format!("{}/", completed), "folder")
+                } else {
+                    (format!("{} ", completed), "text-x-generic")
+                }
+            })
+            .collect()
+    }
+}
+
+fn command_of(action: &Action) -> &str {
+    match action {
+        Action::RunInTerminal(cmd) | Action::Shell(cmd) => cmd,
+        _ => "",
+    }
+}
+
+impl ISearchProvider for TerminalProvider {
+    fn id(&self) -> &str { "terminal" }
+
+    fn default_prefix(&self) -> &str { "x " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let cmd = query.trim_start();
+        let mut results = Vec::new();
+
+        if !cmd.trim().is_empty() {
+            let trimmed = cmd.trim_end().to_string();
+            results.push(SearchResult::new(format!("Run in Terminal: {}", trimmed), "utilities-terminal", Action::RunInTerminal(trimmed.clone())));
+            results.push(SearchResult::new(format!("Run in Background: {}", trimmed), "system-run", Action::Shell(trimmed)));
+        }
+        results.extend(self.completions(cmd));
+        results.extend(self.history_results(cmd.trim()));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockHistory;
+    use std::collections::HashMap;
+
+    struct TreeFS(HashMap<&'static str, Vec<&'static str>>);
+
+    impl IFileSystem for TreeFS {
+        fn list_dir(&self, path: &str) -> Vec<String> {
+            self.0.get(path.trim_end_matches('/')).map(|names| names.iter().map(|n| n.to_string()).collect()).unwrap_or_default()
+        }
+        fn is_dir(&self, path: &str) -> bool { self.0.contains_key(path) }
+        fn exists(&self, path: &str) -> bool { self.0.contains_key(path) }
+        fn trash(&self, _path: &str) -> Result<(), String> { unimplemented!() }
+        fn rename(&self, _path: &str, _new_path: &str) -> Result<(), String> { unimplemented!() }
+        fn create_dir(&self, _path: &str) -> Result<(), String> { unimplemented!() }
+    }
+
+    fn provider(history: Arc<MockHistory>) -> TerminalProvider {
+        let fs = TreeFS(HashMap::from([
+            ("/usr/bin", vec!["firefox", "find", "fio", "ls"]),
+            ("/bin", vec!["find", "sh"]),
+            ("/home/me", vec!["Documents", "My Notes", ".bashrc", "notes.txt"]),
+            ("/home/me/Documents", vec![]),
+        ]));
+        TerminalProvider {
+            fs: Arc::new(fs),
+            history,
+            path_dirs: vec!["/usr/bin".to_string(), "/bin".to_string()],
+            home: "/home/me".to_string(),
+            cwd: "/home/me".to_string(),
+            executables: Mutex::new(None),
+        }
+    }
+
+    fn completions(provider: &TerminalProvider, query: &str) -> Vec<String> {
+        provider.search(query).into_iter()
+            .filter_map(|r| match r.action { Action::Complete(text) => Some(text), _ => None })
+            .collect()
+    }
+
+    #[test]
+    fn test_terminal_and_background_variants() {
+        let results = provider(Arc::new(MockHistory::default())).search("make -j4 ");
+        assert_eq!(results[0].action, Action::RunInTerminal("make -j4".to_string()));
+        assert_eq!(results[1].action, Action::Shell("make -j4".to_string()));
+    }
+
+    #[test]
+    fn test_completes_programs_then_paths() {
+        let provider = provider(Arc::new(MockHistory::default()));
+        assert_eq!(completions(&provider, "fi"), ["fio ", "find ", "firefox "]);
+        assert_eq!(completions(&provider, "vim ~/Doc"), ["vim ~/Documents/"]);
+        assert_eq!(completions(&provider, "vim no"), ["vim notes.txt "]);
+        assert_eq!(completions(&provider, "vim .b"), ["vim .bashrc "]);
+        assert_eq!(completions(&provider, "cat ~/My"), ["cat '/home/me/My Notes' "]);
+        // Nothing to complete after a space
+        assert!(completions(&provider, "ls ").is_empty());
+    }
+
+    #[test]
+    fn test_history_is_fuzzy_deduplicated_and_recent_first() {
+        let history = Arc::new(MockHistory::default());
+        history.record("terminal:htop", "x htop");
+        history.record("shell:rsync -a ~/src backup:", "x rs");
+        history.record("launch:firefox", "fire");
+        history.record("terminal:htop", "x ht");
+        history.record("terminal:git status", "x git");
+        let provider = provider(history);
+
+        let recent: Vec<Action> = provider.search("").into_iter().map(|r| r.action).collect();
+        assert_eq!(recent, [
+            Action::RunInTerminal("git status".to_string()),
+            Action::RunInTerminal("htop".to_string()),
+            Action::Shell("rsync -a ~/src backup:".to_string()),
+        ]);
+
+        let found: Vec<String> = provider.search("rsbk").into_iter()
+            .filter(|r| r.icon.as_deref() == Some(HISTORY_ICON))
+            .map(|r| r.name)
+            .collect();
+        assert_eq!(found, ["rsync -a ~/src backup:"]);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::domain::model::{Action, SearchResult, TimerAction};
+use crate::domain::ports::{ISearchProvider, ITimeService};
+use std::sync::Arc;
+
+pub struct TimeProvider {
+    time: Arc<dyn ITimeService + Send + Sync>,
+}
+
+impl TimeProvider {
+    pub fn new(time: Arc<dyn ITimeService + Send + Sync>) -> Self {
+        Self { time }
+    }
+}
+
+impl ISearchProvider for TimeProvider {
+    fn id(&self) -> &str { "time" }
+
+    fn default_prefix(&self) -> &str { "t " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let action = query.trim();
+        let mut results = vec![];
+
+        // Check status for controls
+        let (_, is_active) = self.time.get_status();
+        if is_active && !action.is_empty() {
+            if action == "stop" || "stop".starts_with(action) {
+                 results.push(SearchResult::new("Stop Timer", "media-playback-stop", Action::Timer(TimerAction::Stop)));
+            }
+            if action == "pause" || "pause".starts_with(action) || action == "resume" || "resume".starts_with(action) {
+                 results.push(SearchResult::new("Pause/Resume", "media-playback-pause", Action::Timer(TimerAction::TogglePause)));
+            }
+            if action == "restart" || "restart".starts_with(action) {
+                 results.push(SearchResult::new("Restart", "view-refresh", Action::Timer(TimerAction::Restart)));
+            }
+        }
+
+        // t timer 10 (10 mins)
+        if action.starts_with("timer ") {
+             let duration_str = action.strip_prefix("timer ").unwrap_or("").trim();
+             let duration = if let Ok(val) = duration_str.parse::<u64>() {
+                 val
+             } else {
+                 // Parse 10m, 1h? Simplified to minutes for now if number
+                 if let Some(mins) = duration_str.strip_suffix("m") {
+                     mins.trim().parse::<u64>().unwrap_or(0)
+                 } else {
+                     duration_str.parse::<u64>().unwrap_or(0)
+                 }
+             };
+
+             if duration > 0 {
+                 results.push(SearchResult::new(
+                     format!("Start Timer: {} minutes", duration),
+                     "alarm-timer",
+                     Action::Timer(TimerAction::Start { secs: duration * 60 }),
+                 ));
+             }
+        } else if action == "pomodoro" || "pomodoro".starts_with(action) {
+             results.push(SearchResult::new("Start Pomodoro (25m)", "alarm-timer", Action::Timer(TimerAction::Pomodoro)));
+        } else if action == "stopwatch" || "stopwatch".starts_with(action) {
+             results.push(SearchResult::new("Start Stopwatch", "alarm-timer", Action::Timer(TimerAction::Stopwatch)));
+        }
+
+        // Allow just typing "t" to see options
+        if action.is_empty() {
+             if is_active {
+                 results.push(SearchResult::new("Pause/Resume", "media-playback-pause", Action::Timer(TimerAction::TogglePause)));
+                 results.push(SearchResult::new("Stop", "media-playback-stop", Action::Timer(TimerAction::Stop)));
+                 results.push(SearchResult::new("Restart", "view-refresh", Action::Timer(TimerAction::Restart)));
+             }
+             results.push(SearchResult::new("Pomodoro", "alarm-timer", Action::Timer(TimerAction::Pomodoro)));
+             results.push(SearchResult::new("Stopwatch", "alarm-timer", Action::Timer(TimerAction::Stopwatch)));
+             results.push(SearchResult::new("Timer (e.g. 't timer 10')", "alarm-timer", Action::None));
+        }
+
+        results
+    }
+
+    fn activate(&self, action: &Action) -> Option<Result<(), String>> {
+        let Action::Timer(timer_action) = action else { return None };
+        match *timer_action {
+            TimerAction::Start { secs } => self.time.start_timer(secs),
+            TimerAction::Pomodoro => self.time.start_pomodoro(),
+            TimerAction::Stopwatch => self.time.start_stopwatch(),
+            TimerAction::TogglePause => self.time.toggle_pause(),
+            TimerAction::Stop => self.time.stop(),
+            TimerAction::Restart => self.time.restart(),
+        }
+        Some(Ok(()))
+    }
+}
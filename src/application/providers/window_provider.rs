@@ -0,0 +1,90 @@
+use crate::domain::model::{Action, SearchResult};
+use crate::domain::ports::{IActionProvider, ISearchProvider, IWindowRepository};
+use std::sync::Arc;
+
+pub struct WindowProvider {
+    window_repo: Arc<dyn IWindowRepository + Send + Sync>,
+}
+
+impl WindowProvider {
+    pub fn new(window_repo: Arc<dyn IWindowRepository + Send + Sync>) -> Self {
+        Self { window_repo }
+    }
+}
+
+impl ISearchProvider for WindowProvider {
+    fn id(&self) -> &str { "window" }
+
+    fn default_prefix(&self) -> &str { "w " }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let keyword = query.trim().to_lowercase();
+        let windows = self.window_repo.get_open_windows();
+
+        windows.into_iter()
+            .filter(|w| {
+                keyword.is_empty()
+                || w.title.to_lowercase().contains(&keyword)
+                || w.app_name.to_lowercase().contains(&keyword)
+            })
+            .map(|w| {
+                let ws_label = if w.workspace >= 0 { format!("[WS {}]", w.workspace + 1) } else { "[WS ?]".to_string() };
+                let screen_label = if w.screen >= 0 { format!("[SCR {}]", w.screen + 1) } else { "[SCR ?]".to_string() };
+                SearchResult {
+                    name: format!("{} {} {} - {}", ws_label, screen_label, w.app_name, w.title),
+                    icon: Some("preferences-system-windows".to_string()),
+                    action: Action::FocusWindow(w.id),
+                    is_running: true,
+                    is_favorite: false,
+                    category: None,
+                    sub_actions: Vec::new(),
+                    detail: None,
+                }
+            })
+            .collect()
+    }
+
+    fn activate(&self, action: &Action) -> Option<Result<(), String>> {
+        let result = match action {
+            Action::FocusWindow(win_id) => self.window_repo.focus_window(win_id),
+            Action::Provider { id, payload } if id == self.id() => {
+                match payload.split_once(':') {
+                    Some(("close", win_id)) => self.window_repo.close_window(win_id),
+                    Some(("move", rest)) => {
+                        let (workspace, win_id) = rest.split_once(':')?;
+                        let workspace = workspace.parse().ok()?;
+                        self.window_repo.move_window_to_workspace(win_id, workspace)
+                    },
+                    _ => return None,
+                }
+            },
+            _ => return None,
+        };
+        Some(result.map_err(|e| format!("Window action failed: {}", e)))
+    }
+}
+
+impl IActionProvider for WindowProvider {
+    fn actions_for(&self, result: &SearchResult) -> Vec<SearchResult> {
+        let Action::FocusWindow(win_id) = &result.action else { return vec![] };
+        let provider = |payload: String| Action::Provider { id: self.id().to_string(), payload };
+
+        let mut actions = vec![
+            SearchResult::new("Focus", "preferences-system-windows", Action::FocusWindow(win_id.clone())),
+            SearchResult::new("Close", "window-close", provider(format!("close:{}", win_id))),
+        ];
+
+        // Offer every workspace in use plus a fresh one, except the window's own
+        let windows = self.window_repo.get_open_windows();
+        let current = windows.iter().find(|w| &w.id == win_id).map(|w| w.workspace);
+        let last = windows.iter().map(|w| w.workspace).max().unwrap_or(0).max(0);
+        for workspace in (0..=last + 1).filter(|ws| Some(*ws) != current) {
+            actions.push(SearchResult::new(
+                format!("Move to Workspace {}", workspace + 1),
+                "go-jump",
+                provider(format!("move:{}:{}", workspace, win_id)),
+            ));
+        }
+        actions
+    }
+}
@@ -1,14 +1,33 @@
 use crate::domain::model::App;
 use crate::domain::ports::{IAppRepository, IProcessMonitor};
-use std::sync::{Arc, RwLock};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::Duration;
 
+/// How often running status is refreshed while the launcher is visible.
+const VISIBLE_REFRESH: Duration = Duration::from_secs(5);
+/// Installs touch many files at once; wait for the burst to settle before rescanning.
+const RESCAN_DEBOUNCE: Duration = Duration::from_millis(300);
+
+enum CacheMessage {
+    /// Something changed in a watched applications directory.
+    AppsChanged,
+    /// The launcher was shown (true) or hidden (false).
+    Visible(bool),
+    Stop,
+}
+
+/// Keeps the app list in memory. Desktop files are re-read only when inotify
+/// reports a change in an applications directory; running status is refreshed
+/// when the launcher is shown and at a low rate while it stays visible.
 pub struct AppCacheService {
     cache: Arc<RwLock<Vec<App>>>,
-    // We hold strong references to the real adapters to use them in the background thread
-    // But we don't expose them directly.
-    _handle: Option<thread::JoinHandle<()>>, 
+    sender: Sender<CacheMessage>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
 }
 
 impl AppCacheService {
@@ -17,44 +36,200 @@ impl AppCacheService {
         real_monitor: Arc<dyn IProcessMonitor + Send + Sync>,
     ) -> Self {
         let cache = Arc::new(RwLock::new(Vec::new()));
-        let cache_clone = cache.clone();
 
         // Initial fetch (blocking, so we have data immediately on startup)
         {
             let mut apps = real_repo.find_apps();
             real_monitor.update_app_status(&mut apps);
-            let mut writer = cache.write().unwrap();
-            *writer = apps;
+            *cache.write().unwrap() = apps;
         }
 
-        // Background update task
+        let (sender, receiver) = mpsc::channel();
+
+        // The watcher must live as long as the worker, so it is moved into it
+        let watcher = AppDirWatcher::new(real_repo.watch_paths().iter().map(PathBuf::from).collect(), sender.clone());
+
+        let cache_clone = cache.clone();
         let handle = thread::spawn(move || {
-            loop {
-                thread::sleep(Duration::from_secs(2)); // Refresh rate
-                
-                let mut apps = real_repo.find_apps();
-                real_monitor.update_app_status(&mut apps);
-                
-                // Sort by running logic here or let Omnibar do it?
-                // Omnibar sorts it anyway. But caching "ready-to-go" data is good.
-                // Let's just store the raw updated list.
-                
-                if let Ok(mut writer) = cache_clone.write() {
-                    *writer = apps;
-                } else {
-                    // Lock poisoned
-                    break;
-                }
-            }
+            Self::run(receiver, cache_clone, real_repo, real_monitor, watcher);
         });
 
         Self {
             cache,
-            _handle: Some(handle),
+            sender,
+            handle: Mutex::new(Some(handle)),
+        }
+    }
+
+    /// Tells the cache whether the launcher is on screen. Showing it triggers
+    /// an immediate status refresh.
+    pub fn set_visible(&self, visible: bool) {
+        let _ = self.sender.send(CacheMessage::Visible(visible));
+    }
+
+    /// Stops watching and waits for the background thread to finish.
+    pub fn stop(&self) {
+        let _ = self.sender.send(CacheMessage::Stop);
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+
+    fn run(
+        receiver: Receiver<CacheMessage>,
+        cache: Arc<RwLock<Vec<App>>>,
+        repo: Arc<dyn IAppRepository + Send + Sync>,
+        monitor: Arc<dyn IProcessMonitor + Send + Sync>,
+        mut watcher: Option<AppDirWatcher>,
+    ) {
+        let mut visible = false;
+
+        loop {
+            // Hidden: sleep until something happens. Visible: wake up to refresh status.
+            let message = if visible {
+                match receiver.recv_timeout(VISIBLE_REFRESH) {
+                    Ok(message) => Some(message),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            } else {
+                match receiver.recv() {
+                    Ok(message) => Some(message),
+                    Err(_) => break,
+                }
+            };
+
+            match message {
+                Some(CacheMessage::Stop) => break,
+                Some(CacheMessage::AppsChanged) => {
+                    // Swallow the rest of the burst, but still honour a stop request
+                    loop {
+                        match receiver.recv_timeout(RESCAN_DEBOUNCE) {
+                            Ok(CacheMessage::AppsChanged) => continue,
+                            Ok(CacheMessage::Visible(v)) => visible = v,
+                            Ok(CacheMessage::Stop) | Err(RecvTimeoutError::Disconnected) => return,
+                            Err(RecvTimeoutError::Timeout) => break,
+                        }
+                    }
+                    // A missing directory may have been created
+                    if let Some(watcher) = watcher.as_mut() {
+                        watcher.update();
+                    }
+                    let mut apps = repo.find_apps();
+                    monitor.update_app_status(&mut apps);
+                    if let Ok(mut writer) = cache.write() {
+                        *writer = apps;
+                    }
+                },
+                Some(CacheMessage::Visible(v)) => {
+                    visible = v;
+                    if visible {
+                        Self::refresh_status(&cache, monitor.as_ref());
+                    }
+                },
+                None => Self::refresh_status(&cache, monitor.as_ref()),
+            }
+        }
+    }
+
+    fn refresh_status(cache: &RwLock<Vec<App>>, monitor: &(dyn IProcessMonitor + Send + Sync)) {
+        // Work on a copy so searches aren't blocked while /proc is read
+        let mut apps = match cache.read() {
+            Ok(reader) => reader.clone(),
+            Err(_) => return,
+        };
+        monitor.update_app_status(&mut apps);
+        if let Ok(mut writer) = cache.write() {
+            // Keep whatever a concurrent rescan produced, only copying status over
+            for app in writer.iter_mut() {
+                if let Some(updated) = apps.iter().find(|a| a.exec_path == app.exec_path) {
+                    app.is_running = updated.is_running;
+                }
+            }
         }
     }
 }
 
+/// Watches the applications directories. One that doesn't exist yet (e.g.
+/// Flatpak's before the first install) is covered by watching its nearest
+/// existing ancestor, and watched itself once it appears.
+struct AppDirWatcher {
+    watcher: RecommendedWatcher,
+    dirs: Vec<PathBuf>,
+    /// Watched path -> whether it is watched recursively, i.e. is one of `dirs`.
+    watched: HashMap<PathBuf, bool>,
+}
+
+impl AppDirWatcher {
+    fn new(dirs: Vec<PathBuf>, sender: Sender<CacheMessage>) -> Option<Self> {
+        let relevant = dirs.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else { return };
+            // Ancestors are watched for the missing directories only, not
+            // for everything else happening in them
+            let changed = !event.kind.is_access() && event.paths.iter()
+                .any(|path| relevant.iter().any(|dir| path.starts_with(dir) || dir.starts_with(path)));
+            if changed {
+                let _ = sender.send(CacheMessage::AppsChanged);
+            }
+        });
+        match watcher {
+            Ok(watcher) => {
+                let mut this = Self { watcher, dirs, watched: HashMap::new() };
+                this.update();
+                Some(this)
+            },
+            Err(e) => {
+                println!("Failed to start app directory watcher: {}", e);
+                None
+            },
+        }
+    }
+
+    /// Moves the watches to the directories as they exist now.
+    fn update(&mut self) {
+        // Repeat in case a directory appeared while the watches were changed
+        loop {
+            let wanted = self.wanted();
+            if wanted == self.watched {
+                return;
+            }
+            for (path, recursive) in &self.watched {
+                if wanted.get(path) != Some(recursive) {
+                    let _ = self.watcher.unwatch(path);
+                }
+            }
+            for (path, &recursive) in &wanted {
+                if self.watched.get(path) != Some(&recursive) {
+                    let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+                    if let Err(e) = self.watcher.watch(path, mode) {
+                        println!("Failed to watch {}: {}", path.display(), e);
+                    }
+                }
+            }
+            self.watched = wanted;
+        }
+    }
+
+    fn wanted(&self) -> HashMap<PathBuf, bool> {
+        let mut wanted = HashMap::new();
+        for dir in &self.dirs {
+            if dir.is_dir() {
+                wanted.insert(dir.clone(), true);
+            } else if let Some(ancestor) = dir.ancestors().skip(1).find(|a| a.is_dir()) {
+                wanted.entry(ancestor.to_path_buf()).or_insert(false);
+            }
+        }
+        wanted
+    }
+}
+
+impl Drop for AppCacheService {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
 impl IAppRepository for AppCacheService {
     fn find_apps(&self) -> Vec<App> {
         if let Ok(reader) = self.cache.read() {
@@ -68,30 +243,79 @@ impl IAppRepository for AppCacheService {
 impl IProcessMonitor for AppCacheService {
     fn get_running_pids(&self) -> Vec<u32> {
         // We aren't caching raw PIDs, only the effect on Apps.
-        // If something needs raw PIDs, we might need to cache them too.
         // Checking usage: Omnibar only calls update_app_status, never get_running_pids directly.
         vec![]
     }
 
-    fn update_app_status(&self, apps: &mut [App]) {
-        // The apps in our cache ALREADY have status updated.
-        // But if this method is called with *external* apps (e.g. from a fresh search if we bypassed cache),
-        // we might not be able to help much without caching PIDs.
-        // HOWEVER: Omnibar gets apps FROM us (find_apps), then calls update_app_status on them.
-        // So they are already updated.
-        // We can just do nothing here, OR we can re-sync with our cache if matching names.
-        
-        // Optimization: logic has moved to background. 
-        // If the passed apps match our cache, they are already up to date.
-        // If Omnibar calls find_apps() -> gets cached structs (running=true/false) -> calls update_app_status,
-        // we can essentially no-op or re-apply from cache.
-        
-        // Let's implement a cheap "re-apply from cache" just in case apps came from elsewhere?
-        // Actually, Omnibar implementation:
-        // let mut apps = self.app_repo.find_apps();
-        // self.process_monitor.update_app_status(&mut apps);
-        
-        // If `self.app_repo` IS `AppCacheService`, then `find_apps` returns fully updated apps.
-        // Then `update_app_status` is called. We can just return.
+    fn update_app_status(&self, _apps: &mut [App]) {
+        // Omnibar gets apps FROM us (find_apps), then calls update_app_status on them.
+        // Their status was refreshed by the background thread already, so this is a no-op.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockProcessMonitor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct DirRepo {
+        dir: String,
+        scans: AtomicUsize,
+    }
+
+    impl IAppRepository for DirRepo {
+        fn find_apps(&self) -> Vec<App> {
+            self.scans.fetch_add(1, Ordering::SeqCst);
+            std::fs::read_dir(&self.dir).into_iter()
+                .flatten()
+                .flatten()
+                .map(|e| App { name: e.file_name().to_string_lossy().to_string(), ..Default::default() })
+                .collect()
+        }
+
+        fn watch_paths(&self) -> Vec<String> {
+            vec![self.dir.clone()]
+        }
+    }
+
+    #[test]
+    fn test_rescans_only_on_change_and_stops() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(DirRepo { dir: dir.path().to_string_lossy().to_string(), scans: AtomicUsize::new(0) });
+        let cache = AppCacheService::new(repo.clone(), Arc::new(MockProcessMonitor));
+        assert!(cache.find_apps().is_empty());
+
+        // No change, no rescan
+        thread::sleep(RESCAN_DEBOUNCE * 2);
+        assert_eq!(repo.scans.load(Ordering::SeqCst), 1);
+
+        std::fs::write(dir.path().join("foo.desktop"), "").unwrap();
+        for _ in 0..50 {
+            if !cache.find_apps().is_empty() { break; }
+            thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(cache.find_apps()[0].name, "foo.desktop");
+
+        cache.stop();
+        assert!(cache.handle.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_picks_up_directory_created_later() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("flatpak/exports/share/applications");
+        let repo = Arc::new(DirRepo { dir: dir.to_string_lossy().to_string(), scans: AtomicUsize::new(0) });
+        let cache = AppCacheService::new(repo, Arc::new(MockProcessMonitor));
+        assert!(cache.find_apps().is_empty());
+
+        std::fs::create_dir_all(&dir).unwrap();
+        thread::sleep(RESCAN_DEBOUNCE * 2);
+        std::fs::write(dir.join("foo.desktop"), "").unwrap();
+        for _ in 0..50 {
+            if !cache.find_apps().is_empty() { break; }
+            thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(cache.find_apps().len(), 1);
     }
 }
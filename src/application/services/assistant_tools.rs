@@ -0,0 +1,278 @@
+use crate::domain::model::{Action, App, SearchResult, TimerAction, ToolCall, ToolSpec};
+use crate::domain::ports::{IAppRepository, ICalculator, IDictionaryService, IFileSystem, IMacroRepository, IWindowRepository};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// Most apps listed by one search.
+const MAX_APP_MATCHES: usize = 10;
+
+/// What a tool call came to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolOutcome {
+    /// Output of a tool that only looks things up, handed back to the model.
+    Answer(String),
+    /// An action for the user to confirm. Nothing has happened yet.
+    Proposal(SearchResult),
+}
+
+/// Launcher capabilities the assistant can call. Lookups run right away;
+/// anything that changes something becomes a result row the user has to
+/// pick, so the model never runs a command on its own.
+pub struct AssistantTools {
+    apps: Arc<dyn IAppRepository + Send + Sync>,
+    fs: Arc<dyn IFileSystem + Send + Sync>,
+    windows: Arc<dyn IWindowRepository + Send + Sync>,
+    macros: Arc<dyn IMacroRepository + Send + Sync>,
+    calculator: Arc<dyn ICalculator + Send + Sync>,
+    dictionary: Arc<dyn IDictionaryService + Send + Sync>,
+}
+
+impl AssistantTools {
+    pub fn new(
+        apps: Arc<dyn IAppRepository + Send + Sync>,
+        fs: Arc<dyn IFileSystem + Send + Sync>,
+        windows: Arc<dyn IWindowRepository + Send + Sync>,
+        macros: Arc<dyn IMacroRepository + Send + Sync>,
+        calculator: Arc<dyn ICalculator + Send + Sync>,
+        dictionary: Arc<dyn IDictionaryService + Send + Sync>,
+    ) -> Self {
+        Self { apps, fs, windows, macros, calculator, dictionary }
+    }
+
+    pub fn specs(&self) -> Vec<ToolSpec> {
+        let spec = |name: &str, description: &str, param: &str, kind: &str, param_description: &str| ToolSpec {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": { param: { "type": kind, "description": param_description } },
+                "required": [param],
+            }),
+        };
+        vec![
+            spec("search_apps", "Lists installed applications whose name matches the query.", "query", "string", "Part of an application name"),
+            spec("calculate", "Evaluates an arithmetic expression or unit conversion exactly.", "expression", "string", "e.g. 17% of 240 or 5 km in miles"),
+            spec("define", "Looks up the definition of a word in the dictionary.", "term", "string", "The word to define"),
+            spec("launch_app", "Suggests starting an installed application.", "name", "string", "Name of the application"),
+            spec("open_file", "Suggests opening a file or folder with its default application.", "path", "string", "Absolute path, or one starting with ~"),
+            spec("focus_window", "Suggests switching to an open window.", "title", "string", "Part of the window title or application name"),
+            spec("start_timer", "Suggests starting a countdown timer.", "minutes", "number", "Length of the timer in minutes"),
+            spec("run_macro", "Suggests running one of the user's saved macros.", "name", "string", "Name of the macro"),
+            spec("run_command", "Suggests running a shell command. Prefer the other tools when they fit.", "command", "string", "The command line"),
+        ]
+    }
+
+    /// Runs a lookup or turns an action into a proposal. Errors are meant
+    /// for the model, so it can correct the call.
+    pub fn call(&self, call: &ToolCall) -> Result<ToolOutcome, String> {
+        let args = &call.arguments;
+        match call.name.as_str() {
+            "search_apps" => Ok(ToolOutcome::Answer(self.search_apps(text_arg(args, "query")?))),
+            "calculate" => {
+                let expression = text_arg(args, "expression")?;
+                self.calculator.calculate(expression)
+                    .map(ToolOutcome::Answer)
+                    .ok_or_else(|| format!("Could not evaluate {}", expression))
+            },
+            "define" => {
+                let term = text_arg(args, "term")?;
+                self.dictionary.lookup(term)
+                    .map(ToolOutcome::Answer)
+                    .ok_or_else(|| format!("No definition found for {}", term))
+            },
+            "launch_app" => self.launch_app(text_arg(args, "name")?).map(ToolOutcome::Proposal),
+            "open_file" => self.open_file(text_arg(args, "path")?).map(ToolOutcome::Proposal),
+            "focus_window" => self.focus_window(text_arg(args, "title")?).map(ToolOutcome::Proposal),
+            "start_timer" => {
+                let minutes = args["minutes"].as_f64()
+                    .or_else(|| args["minutes"].as_str().and_then(|m| m.trim().parse().ok()))
+                    .ok_or("Missing argument minutes")?;
+                let secs = (minutes * 60.0).round();
+                if secs < 1.0 {
+                    return Err("The timer needs a positive length".to_string());
+                }
+                let secs = secs as u64;
+                let length = if secs.is_multiple_of(60) { format!("{} minutes", secs / 60) } else { format!("{} seconds", secs) };
+                Ok(ToolOutcome::Proposal(SearchResult::new(
+                    format!("Start Timer: {}", length),
+                    "alarm-timer",
+                    Action::Timer(TimerAction::Start { secs }),
+                )))
+            },
+            "run_macro" => {
+                let name = text_arg(args, "name")?;
+                match self.macros.get(name) {
+                    Some(found) => Ok(ToolOutcome::Proposal(SearchResult::new(
+                        format!("Macro: {}", found.name),
+                        "system-run",
+                        Action::RunMacro(found.name),
+                    ))),
+                    None => {
+                        let names: Vec<String> = self.macros.get_all().into_iter().map(|m| m.name).collect();
+                        Err(format!("No macro named {}. Saved macros: {}", name, list_or_none(&names)))
+                    },
+                }
+            },
+            "run_command" => {
+                let command = text_arg(args, "command")?.trim();
+                if command.is_empty() {
+                    return Err("The command is empty".to_string());
+                }
+                let mut result = SearchResult::new(format!("Run: {}", command), "utilities-terminal", Action::Shell(command.to_string()));
+                result.detail = Some("Shell command suggested by the assistant, check it before running".to_string());
+                Ok(ToolOutcome::Proposal(result))
+            },
+            other => Err(format!("Unknown tool {}", other)),
+        }
+    }
+
+    fn search_apps(&self, query: &str) -> String {
+        let mut matches = self.matching_apps(query);
+        matches.truncate(MAX_APP_MATCHES);
+        if matches.is_empty() {
+            return format!("No installed application matches {}", query);
+        }
+        matches.iter()
+            .map(|app| match &app.generic_name {
+                Some(generic) => format!("{} ({})", app.name, generic),
+                None => app.name.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Installed apps matching `query`, best first.
+    fn matching_apps(&self, query: &str) -> Vec<App> {
+        let matcher = SkimMatcherV2::default();
+        let mut scored: Vec<_> = self.apps.find_apps().into_iter()
+            .filter_map(|app| Some((matcher.fuzzy_match(&app.name, query)?, app)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+        scored.into_iter().map(|(_, app)| app).collect()
+    }
+
+    fn launch_app(&self, name: &str) -> Result<SearchResult, String> {
+        let app = self.matching_apps(name).into_iter().next()
+            .ok_or_else(|| format!("No installed application matches {}", name))?;
+        let mut result = SearchResult::new(
+            format!("Launch {}", app.name),
+            app.icon.as_deref().unwrap_or("application-x-executable"),
+            Action::Launch(app.exec_path),
+        );
+        result.detail = app.generic_name;
+        Ok(result)
+    }
+
+    fn open_file(&self, path: &str) -> Result<SearchResult, String> {
+        let home = std::env::var("HOME").unwrap_or_default();
+        let path = match path.trim().strip_prefix('~') {
+            Some(rest) => format!("{}{}", home, rest),
+            None => path.trim().to_string(),
+        };
+        if !path.starts_with('/') {
+            return Err(format!("{} is not an absolute path", path));
+        }
+        if !self.fs.exists(&path) {
+            return Err(format!("{} does not exist", path));
+        }
+        let name = path.trim_end_matches('/').rsplit('/').next().filter(|name| !name.is_empty()).unwrap_or("/");
+        let icon = if self.fs.is_dir(&path) { "folder" } else { "document-open" };
+        let mut result = SearchResult::new(format!("Open {}", name), icon, Action::OpenPath(path.clone()));
+        result.detail = Some(path);
+        Ok(result)
+    }
+
+    fn focus_window(&self, title: &str) -> Result<SearchResult, String> {
+        let windows = self.windows.get_open_windows();
+        let needle = title.to_lowercase();
+        let found = windows.iter()
+            .find(|w| w.title.to_lowercase().contains(&needle) || w.app_name.to_lowercase().contains(&needle));
+        match found {
+            Some(window) => {
+                let mut result = SearchResult::new(
+                    format!("Focus {}", window.title),
+                    "preferences-system-windows",
+                    Action::FocusWindow(window.id.clone()),
+                );
+                result.detail = Some(window.app_name.clone());
+                Ok(result)
+            },
+            None => {
+                let titles: Vec<String> = windows.into_iter().map(|w| w.title).collect();
+                Err(format!("No open window matches {}. Open windows: {}", title, list_or_none(&titles)))
+            },
+        }
+    }
+}
+
+fn text_arg<'a>(args: &'a Value, name: &str) -> Result<&'a str, String> {
+    args[name].as_str().ok_or_else(|| format!("Missing argument {}", name))
+}
+
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() { "none".to_string() } else { items.join(", ") }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Only `/home/user/notes` and the files in it exist.
+    struct NotesFS;
+    impl IFileSystem for NotesFS {
+        fn list_dir(&self, _path: &str) -> Vec<String> { vec![] }
+        fn is_dir(&self, path: &str) -> bool { path == "/home/user/notes" }
+        fn exists(&self, path: &str) -> bool { path.starts_with("/home/user/notes") }
+        fn trash(&self, _path: &str) -> Result<(), String> { Ok(()) }
+        fn rename(&self, _path: &str, _new_path: &str) -> Result<(), String> { Ok(()) }
+        fn create_dir(&self, _path: &str) -> Result<(), String> { Ok(()) }
+    }
+
+    fn tools() -> AssistantTools {
+        AssistantTools::new(
+            Arc::new(MockAppRepo),
+            Arc::new(NotesFS),
+            Arc::new(MockWindowRepo),
+            Arc::new(MockMacro),
+            Arc::new(MockCalculator),
+            Arc::new(MockDictionary),
+        )
+    }
+
+    fn call(name: &str, arguments: Value) -> Result<ToolOutcome, String> {
+        tools().call(&ToolCall { id: "call_0".to_string(), name: name.to_string(), arguments })
+    }
+
+    fn proposed_action(outcome: Result<ToolOutcome, String>) -> Action {
+        match outcome {
+            Ok(ToolOutcome::Proposal(result)) => result.action,
+            other => panic!("expected a proposal, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lookups_answer_the_model() {
+        assert_eq!(call("calculate", json!({ "expression": "1+1" })), Ok(ToolOutcome::Answer("2".to_string())));
+        assert_eq!(call("define", json!({ "term": "rust" })), Ok(ToolOutcome::Answer("Awesome language".to_string())));
+        assert_eq!(call("search_apps", json!({ "query": "fire" })), Ok(ToolOutcome::Answer("Firefox".to_string())));
+        assert!(call("calculate", json!({})).unwrap_err().contains("expression"));
+        assert!(call("format_disk", json!({})).is_err());
+    }
+
+    #[test]
+    fn test_actions_become_proposals() {
+        assert_eq!(proposed_action(call("launch_app", json!({ "name": "firefox" }))), Action::Launch("firefox".to_string()));
+        assert_eq!(proposed_action(call("open_file", json!({ "path": "/home/user/notes/todo.md" }))), Action::OpenPath("/home/user/notes/todo.md".to_string()));
+        assert_eq!(proposed_action(call("focus_window", json!({ "title": "terminal" }))), Action::FocusWindow("0x2".to_string()));
+        assert_eq!(proposed_action(call("start_timer", json!({ "minutes": 5 }))), Action::Timer(TimerAction::Start { secs: 300 }));
+        assert_eq!(proposed_action(call("run_macro", json!({ "name": "test" }))), Action::RunMacro("test".to_string()));
+        assert_eq!(proposed_action(call("run_command", json!({ "command": "rm -rf ~/tmp" }))), Action::Shell("rm -rf ~/tmp".to_string()));
+
+        assert!(call("open_file", json!({ "path": "/etc/missing" })).unwrap_err().contains("does not exist"));
+        assert!(call("focus_window", json!({ "title": "Slack" })).unwrap_err().contains("Google Chrome, Terminal"));
+        assert!(call("start_timer", json!({ "minutes": 0 })).is_err());
+    }
+}
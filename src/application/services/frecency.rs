@@ -0,0 +1,108 @@
+use crate::domain::model::UsageRecord;
+use std::collections::HashMap;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Converts a frecency total into points comparable to a fuzzy score.
+/// Logarithmic so hundreds of launches help without drowning the match quality.
+const FRECENCY_SCALE: f64 = 40.0;
+/// Bonus per earlier pick of a result for exactly this query.
+const QUERY_PICK_BONUS: f64 = 50.0;
+const MAX_QUERY_PICKS: usize = 4;
+/// Extra bonus for the result picked the last time this query was typed.
+const LAST_PICK_BONUS: f64 = 200.0;
+
+/// Weight of a single launch, decaying with age (Firefox-style buckets).
+fn visit_weight(age_secs: u64) -> f64 {
+    match age_secs / DAY {
+        0..=3 => 100.0,
+        4..=13 => 70.0,
+        14..=30 => 50.0,
+        31..=89 => 30.0,
+        _ => 10.0,
+    }
+}
+
+/// Usage-based ranking signals for one search, keyed by `Action::usage_key`.
+#[derive(Debug, Default)]
+pub struct UsageScores {
+    frecency: HashMap<String, f64>,
+    query_picks: HashMap<String, usize>,
+    last_pick: Option<String>,
+}
+
+impl UsageScores {
+    pub fn from_records(records: &[UsageRecord], query: &str, now: u64) -> Self {
+        let query = normalize(query);
+        let mut scores = Self::default();
+
+        for record in records {
+            let age = now.saturating_sub(record.timestamp);
+            *scores.frecency.entry(record.key.clone()).or_default() += visit_weight(age);
+
+            if !query.is_empty() && normalize(&record.query) == query {
+                *scores.query_picks.entry(record.key.clone()).or_default() += 1;
+                // Records are oldest first, so the last match wins
+                scores.last_pick = Some(record.key.clone());
+            }
+        }
+        scores
+    }
+
+    /// Accumulated, age-decayed launch weight.
+    pub fn frecency(&self, key: &str) -> f64 {
+        self.frecency.get(key).copied().unwrap_or(0.0)
+    }
+
+    /// Points to add to a fuzzy score for the result with this key.
+    pub fn bonus(&self, key: &str) -> f64 {
+        let frecency = FRECENCY_SCALE * (1.0 + self.frecency(key) / 100.0).ln();
+        let picks = self.query_picks.get(key).copied().unwrap_or(0).min(MAX_QUERY_PICKS);
+        let mut bonus = frecency + picks as f64 * QUERY_PICK_BONUS;
+        if self.last_pick.as_deref() == Some(key) {
+            bonus += LAST_PICK_BONUS;
+        }
+        bonus
+    }
+}
+
+fn normalize(query: &str) -> String {
+    query.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: &str, query: &str, timestamp: u64) -> UsageRecord {
+        UsageRecord { key: key.to_string(), query: query.to_string(), timestamp }
+    }
+
+    #[test]
+    fn test_recent_launches_weigh_more() {
+        let now = 1000 * DAY;
+        let records = vec![
+            record("launch:old", "", now - 200 * DAY),
+            record("launch:old", "", now - 200 * DAY),
+            record("launch:new", "", now - DAY),
+        ];
+        let scores = UsageScores::from_records(&records, "", now);
+        assert!(scores.frecency("launch:new") > scores.frecency("launch:old"));
+        assert_eq!(scores.frecency("launch:unknown"), 0.0);
+    }
+
+    #[test]
+    fn test_last_pick_for_query_wins() {
+        let now = 1000 * DAY;
+        let records = vec![
+            record("launch:firefox", "", now),
+            record("launch:firefox", "", now),
+            record("launch:firefox", "", now),
+            record("launch:gnome-terminal", "te", now),
+            record("launch:telegram", "Te ", now),
+        ];
+        let scores = UsageScores::from_records(&records, "te", now);
+        assert!(scores.bonus("launch:telegram") > scores.bonus("launch:gnome-terminal"));
+        assert!(scores.bonus("launch:telegram") > scores.bonus("launch:firefox"));
+    }
+}
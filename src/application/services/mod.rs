@@ -1 +1,3 @@
 pub mod app_cache;
+pub mod assistant_tools;
+pub mod frecency;
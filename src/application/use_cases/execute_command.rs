@@ -1,22 +1,22 @@
 use crate::domain::ports::ICommandExecutor;
 use crate::domain::ports::IMacroRepository;
 use crate::domain::ports::ISystemPower;
-use crate::domain::ports::IWindowRepository;
-use crate::domain::ports::ITimeService;
+use crate::domain::ports::ITerminalLauncher;
 use crate::application::use_cases::omnibar::Omnibar;
-use crate::domain::model::MacroAction;
-use std::sync::Arc;
+use crate::domain::model::{Action, App, CommandSpec, LaunchFailure, MacroAction};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
-use std::process::Command;
 
 pub struct ExecuteCommand {
     executor: Arc<dyn ICommandExecutor + Send + Sync>,
     macros: Arc<dyn IMacroRepository + Send + Sync>,
     omnibar: Arc<Omnibar>,
     system: Arc<dyn ISystemPower + Send + Sync>,
-    window_repo: Arc<dyn IWindowRepository + Send + Sync>,
-    time: Arc<dyn ITimeService + Send + Sync>,
+    terminal: Arc<dyn ITerminalLauncher + Send + Sync>,
+    /// Launch command -> window focused last, so repeated activation cycles windows.
+    last_focused: Mutex<HashMap<String, String>>,
 }
 
 impl ExecuteCommand {
@@ -25,88 +25,371 @@ impl ExecuteCommand {
         macros: Arc<dyn IMacroRepository + Send + Sync>,
         omnibar: Arc<Omnibar>,
         system: Arc<dyn ISystemPower + Send + Sync>,
-        window_repo: Arc<dyn IWindowRepository + Send + Sync>,
-        time: Arc<dyn ITimeService + Send + Sync>,
+        terminal: Arc<dyn ITerminalLauncher + Send + Sync>,
     ) -> Self {
-        Self { executor, macros, omnibar, system, window_repo, time }
-    }
-
-    pub fn execute(&self, cmd: &str) {
-        if let Some(macro_name) = cmd.strip_prefix("internal:macro:") {
-             self.execute_macro(macro_name);
-             return;
-        }
-
-        if let Some(sys_action) = cmd.strip_prefix("internal:system:") {
-             if let Err(e) = self.system.execute(sys_action) {
-                 println!("System action failed: {}", e);
-             }
-             return;
-        }
-
-        if let Some(win_id) = cmd.strip_prefix("internal:window:") {
-             if let Err(e) = self.window_repo.focus_window(win_id) {
-                 println!("Failed to focus window: {}", e);
-             }
-             return;
-        }
-
-        if let Some(time_cmd) = cmd.strip_prefix("internal:time:") {
-             if time_cmd == "pomodoro" {
-                 self.time.start_pomodoro();
-             } else if time_cmd == "stopwatch" {
-                 self.time.start_stopwatch();
-             } else if time_cmd == "pause" {
-                 self.time.toggle_pause();
-             } else if time_cmd == "stop" {
-                 self.time.stop();
-             } else if time_cmd == "restart" {
-                 self.time.restart();
-             } else if let Some(secs_str) = time_cmd.strip_prefix("timer:") {
-                 if let Ok(secs) = secs_str.parse::<u64>() {
-                     self.time.start_timer(secs);
-                 }
-             }
-             return;
-        }
-
-        self.executor.execute(cmd);
-    }
-    
-    fn execute_macro(&self, name: &str) {
-        if let Some(mac) = self.macros.get(name) {
-            println!("Executing Macro: {}", name);
-            for action in mac.actions {
-                match action {
-                    MacroAction::LaunchApp(app_name) => {
-                         // Search via Omnibar to resolve "Firefox" -> "firefox"
-                         let results = self.omnibar.search(&app_name);
-                         if let Some(top) = results.first() {
-                             self.execute(&top.exec_path);
-                         }
-                    },
-                    MacroAction::Command(cmd) => {
-                         self.executor.execute(&cmd);
-                    },
-                    MacroAction::OpenUrl(url) => {
-                         let cmd = format!("xdg-open \"{}\"", url);
-                         self.executor.execute(&cmd);
-                    },
-                    MacroAction::TypeText(text) => {
-                        // Using xdotool/wtype fallback
-                        // Check for xdotool presence? Or just try?
-                        let _ = Command::new("xdotool").arg("type").arg(&text).spawn();
-                    },
-                    MacroAction::Sleep(ms) => {
-                        thread::sleep(Duration::from_millis(ms));
-                    },
-                    MacroAction::System(sys_action) => {
-                        if let Err(e) = self.system.execute(&sys_action) {
-                            println!("Macro System Action failed: {}", e);
-                        }
-                    }
+        Self { executor, macros, omnibar, system, terminal, last_focused: Mutex::new(HashMap::new()) }
+    }
+
+    /// Runs `action` and records it in the launch history under `query`.
+    /// Fails if the action could not be started; failures after that are
+    /// reported through `take_failures`.
+    pub fn execute(&self, action: &Action, query: &str) -> Result<(), String> {
+        self.omnibar.record_usage(action, query);
+        self.run(action)
+    }
+
+    /// Like `execute`, but always starts a new instance instead of focusing
+    /// an open window of the app.
+    pub fn execute_new_instance(&self, action: &Action, query: &str) -> Result<(), String> {
+        self.omnibar.record_usage(action, query);
+        match action {
+            Action::Launch(cmd) => self.launch(cmd, self.omnibar.find_app(cmd).as_ref()),
+            _ => self.run(action),
+        }
+    }
+
+    /// Launched commands that exited with an error shortly after starting.
+    pub fn take_failures(&self) -> Vec<LaunchFailure> {
+        self.executor.take_failures()
+    }
+
+    fn run(&self, action: &Action) -> Result<(), String> {
+        match action {
+            Action::Launch(cmd) => {
+                let app = self.omnibar.find_app(cmd);
+                // Desktop actions ("New Window") always start something new
+                if app.as_ref().is_some_and(|app| app.exec_path == *cmd && self.focus_existing_window(cmd, app)) {
+                    return Ok(());
+                }
+                self.launch(cmd, app.as_ref())
+            },
+            Action::Shell(cmd) => self.executor.execute(cmd),
+            Action::RunInTerminal(cmd) => {
+                self.executor.spawn(&self.terminal.terminal_command(Some(&CommandSpec::shell(cmd)), None))
+            },
+            Action::OpenPath(path) => self.open_file(path, None),
+            Action::OpenWith { path, desktop_id } => self.open_file(path, Some(desktop_id)),
+            Action::ShowInFolder(path) => {
+                // FileManager1 selects the item; fall back to opening its folder
+                let uri = file_uri(path);
+                let parent = std::path::Path::new(path).parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| "/".to_string());
+                let show_item = CommandSpec::new("dbus-send", [
+                    "--session".to_string(),
+                    "--print-reply".to_string(),
+                    "--dest=org.freedesktop.FileManager1".to_string(),
+                    "/org/freedesktop/FileManager1".to_string(),
+                    "org.freedesktop.FileManager1.ShowItems".to_string(),
+                    format!("array:string:{}", uri),
+                    "string:".to_string(),
+                ]);
+                self.executor.spawn_or(&show_item, &CommandSpec::new("xdg-open", [parent]))
+            },
+            Action::OpenTerminal(dir) => self.executor.spawn(&self.terminal.terminal_command(None, Some(dir))),
+            Action::OpenUrl(url) => self.executor.spawn(&CommandSpec::new("xdg-open", [url.as_str()])),
+            Action::CopyText(text) => {
+                self.executor.spawn(&CommandSpec::new("xclip", ["-selection", "clipboard"]).with_stdin(text))
+            },
+            Action::RunMacro(name) => self.execute_macro(name),
+            // Window, system, timer and custom results are handled by the provider that produced them
+            _ => self.omnibar.activate(action)
+                .unwrap_or_else(|| Err(format!("No handler for action: {:?}", action))),
+        }
+    }
+
+    fn launch(&self, cmd: &str, app: Option<&App>) -> Result<(), String> {
+        let (app, mut spec) = app.and_then(|app| Some((app, app.command_for(cmd)?.clone())))
+            .ok_or_else(|| format!("'{}' is no longer installed", cmd))?;
+        if app.terminal {
+            spec = self.terminal.terminal_command(Some(&spec), None);
+        }
+        self.executor.launch_app(&spec, app.app_id())
+    }
+
+    /// Opens `path` with the given application, or the default one for its type.
+    fn open_file(&self, path: &str, desktop_id: Option<&str>) -> Result<(), String> {
+        let mime = &self.omnibar.mime;
+        let mime_type = mime.mime_type(path);
+        let handlers = mime.handlers(&mime_type);
+        let handler = match desktop_id {
+            Some(id) => handlers.iter().find(|h| h.desktop_id == id),
+            None => handlers.first(),
+        }.ok_or_else(|| format!("No application is registered to open {}", mime_type))?;
+
+        let mut spec = mime.open_command(handler, path)
+            .ok_or_else(|| format!("{} cannot open files", handler.name))?;
+        if handler.terminal {
+            spec = self.terminal.terminal_command(Some(&spec), None);
+        }
+        self.executor.launch_app(&spec, Some(handler.app_id()))
+    }
+
+    /// Focuses a window of the app behind `cmd`, moving on to its next window
+    /// each time. Returns false when the app has no open window.
+    fn focus_existing_window(&self, cmd: &str, app: &App) -> bool {
+        let mut windows: Vec<_> = self.omnibar.window_repo.get_open_windows().into_iter()
+            .filter(|w| app.matches_window_class(&w.app_name))
+            .collect();
+        if windows.is_empty() {
+            return false;
+        }
+        windows.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut last_focused = self.last_focused.lock().unwrap();
+        let next = last_focused.get(cmd)
+            .and_then(|last| windows.iter().position(|w| &w.id == last))
+            .map_or(0, |i| (i + 1) % windows.len());
+        let target = &windows[next];
+        if let Err(e) = self.omnibar.window_repo.focus_window(&target.id) {
+            println!("Failed to focus window: {}", e);
+            return false;
+        }
+        last_focused.insert(cmd.to_string(), target.id.clone());
+        true
+    }
+
+    /// Runs the macro's steps in order, stopping at the first one that fails.
+    fn execute_macro(&self, name: &str) -> Result<(), String> {
+        let mac = self.macros.get(name).ok_or_else(|| format!("No macro named {}", name))?;
+        println!("Executing Macro: {}", name);
+        for action in mac.actions {
+            match action {
+                MacroAction::LaunchApp(app_name) => {
+                     // Only installed apps, so "Firefox" can't resolve to a window or file
+                     let app = self.omnibar.match_app(&app_name).ok_or_else(|| format!("No app matches '{}'", app_name))?;
+                     self.run(&Action::Launch(app.exec_path))?;
+                },
+                MacroAction::Command(cmd) => {
+                     self.executor.execute(&cmd)?;
+                },
+                MacroAction::OpenUrl(url) => {
+                     self.run(&Action::OpenUrl(url))?;
+                },
+                MacroAction::TypeText(text) => {
+                    self.executor.spawn(&CommandSpec::new("xdotool", ["type", "--", text.as_str()]))?;
+                },
+                MacroAction::Sleep(ms) => {
+                    thread::sleep(Duration::from_millis(ms));
+                },
+                MacroAction::System(sys_action) => {
+                    self.system.execute(&sys_action)
+                        .map_err(|e| format!("Macro System Action failed: {}", e))?;
                 }
             }
         }
+        Ok(())
+    }
+}
+
+/// `file://` URI for `path`, with every byte but unreserved characters and
+/// `/` percent-encoded. That includes `,`, which dbus-send would otherwise
+/// take as an array separator.
+fn file_uri(path: &str) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::domain::model::{Macro, Window};
+    use crate::domain::ports::IWindowRepository;
+
+    #[derive(Default)]
+    struct RecordingExecutor {
+        commands: Mutex<Vec<String>>,
+        specs: Mutex<Vec<CommandSpec>>,
+        app_ids: Mutex<Vec<Option<String>>>,
+    }
+
+    impl ICommandExecutor for RecordingExecutor {
+        fn execute(&self, cmd: &str) -> Result<(), String> {
+            self.commands.lock().unwrap().push(cmd.to_string());
+            Ok(())
+        }
+
+        fn spawn(&self, spec: &CommandSpec) -> Result<(), String> {
+            self.specs.lock().unwrap().push(spec.clone());
+            Ok(())
+        }
+
+        fn launch_app(&self, spec: &CommandSpec, app_id: Option<&str>) -> Result<(), String> {
+            self.app_ids.lock().unwrap().push(app_id.map(str::to_string));
+            self.spawn(spec)
+        }
+    }
+
+    fn execute_command(executor: Arc<RecordingExecutor>) -> ExecuteCommand {
+        ExecuteCommand::new(executor, Arc::new(MockMacro), Arc::new(create_omnibar()), Arc::new(MockPower), Arc::new(MockTerminal))
+    }
+
+    /// Two Firefox windows; remembers which one was focused.
+    #[derive(Default)]
+    struct FirefoxWindows {
+        focused: Mutex<Vec<String>>,
+    }
+
+    impl IWindowRepository for FirefoxWindows {
+        fn get_open_windows(&self) -> Vec<Window> {
+            ["0xb", "0xa"].iter()
+                .map(|id| Window { id: id.to_string(), title: "Mozilla Firefox".to_string(), app_name: "firefox".to_string(), workspace: 0, screen: 0 })
+                .collect()
+        }
+        fn focus_window(&self, id: &str) -> Result<(), String> {
+            self.focused.lock().unwrap().push(id.to_string());
+            Ok(())
+        }
+        fn close_window(&self, _id: &str) -> Result<(), String> { Ok(()) }
+        fn move_window_to_workspace(&self, _id: &str, _workspace: i32) -> Result<(), String> { Ok(()) }
+    }
+
+    #[test]
+    fn test_paths_and_text_bypass_the_shell() {
+        let executor = Arc::new(RecordingExecutor::default());
+        let cmd = execute_command(executor.clone());
+        let hostile = "/tmp/it's \"here\" $(rm -rf ~).txt";
+
+        cmd.execute(&Action::OpenPath(hostile.to_string()), "").unwrap();
+        cmd.execute(&Action::CopyText(hostile.to_string()), "").unwrap();
+        cmd.execute(&Action::OpenTerminal("/tmp".to_string()), "").unwrap();
+        cmd.execute(&Action::RunInTerminal("echo \"$HOME\"".to_string()), "").unwrap();
+
+        assert!(executor.commands.lock().unwrap().is_empty());
+        let specs = executor.specs.lock().unwrap();
+        assert_eq!(specs[0], CommandSpec::new("gnome-text-editor", [hostile]));
+        // Copied text only ever reaches xclip's stdin
+        assert_eq!(specs[1], CommandSpec::new("xclip", ["-selection", "clipboard"]).with_stdin(hostile));
+        assert_eq!(specs[2].cwd.as_deref(), Some("/tmp"));
+        assert_eq!(specs[3].argv(), ["term", "-e", "sh", "-c", "echo \"$HOME\""]);
+    }
+
+    #[test]
+    fn test_show_in_folder_encodes_the_uri() {
+        let executor = Arc::new(RecordingExecutor::default());
+        let cmd = execute_command(executor.clone());
+
+        cmd.execute(&Action::ShowInFolder("/tmp/a b,c#%é.txt".to_string()), "").unwrap();
+
+        let specs = executor.specs.lock().unwrap();
+        assert_eq!(specs[0].program, "dbus-send");
+        assert!(specs[0].args.contains(&"array:string:file:///tmp/a%20b%2Cc%23%25%C3%A9.txt".to_string()));
+    }
+
+    #[test]
+    fn test_files_open_with_their_registered_applications() {
+        let executor = Arc::new(RecordingExecutor::default());
+        let cmd = execute_command(executor.clone());
+
+        cmd.execute(&Action::OpenPath("/home/user/todo.md".to_string()), "").unwrap();
+        let vim = Action::OpenWith { path: "/home/user/todo.md".to_string(), desktop_id: "vim.desktop".to_string() };
+        cmd.execute(&vim, "").unwrap();
+        let err = cmd.execute(&Action::OpenPath("/home/user/core.dump".to_string()), "").unwrap_err();
+        assert!(err.contains("application/octet-stream"));
+
+        let specs = executor.specs.lock().unwrap();
+        assert_eq!(specs[0].argv(), ["gnome-text-editor", "/home/user/todo.md"]);
+        // Terminal=true handlers run in the terminal
+        assert_eq!(specs[1].argv(), ["term", "-e", "vim", "/home/user/todo.md"]);
+        assert_eq!(executor.app_ids.lock().unwrap().as_slice(), [Some("org.gnome.TextEditor".to_string()), Some("vim".to_string())]);
+    }
+
+    #[test]
+    fn test_macro_runs_commands() {
+        let executor = Arc::new(RecordingExecutor::default());
+        let cmd = execute_command(executor.clone());
+
+        cmd.execute(&Action::RunMacro("test".to_string()), "m test").unwrap();
+
+        assert_eq!(executor.commands.lock().unwrap().as_slice(), ["x echo hi"]);
+    }
+
+    /// Macros launching Firefox, and "todo", which is a file but no app.
+    struct LaunchMacros;
+    impl IMacroRepository for LaunchMacros {
+        fn get(&self, name: &str) -> Option<Macro> {
+            let app = match name {
+                "web" => "Firefox",
+                "notes" => "todo",
+                _ => return None,
+            };
+            Some(Macro { name: name.to_string(), actions: vec![MacroAction::LaunchApp(app.to_string())] })
+        }
+        fn get_all(&self) -> Vec<Macro> { vec![] }
+        fn add(&self, _mac: Macro) -> Result<(), String> { Ok(()) }
+        fn remove(&self, _name: &str) -> Result<(), String> { Ok(()) }
+    }
+
+    #[test]
+    fn test_macro_launches_apps_not_other_results() {
+        let executor = Arc::new(RecordingExecutor::default());
+        let omnibar = Arc::new(create_omnibar());
+        assert_eq!(omnibar.search("todo")[0].action, Action::OpenPath("/home/user/notes/todo.md".to_string()));
+        let cmd = ExecuteCommand::new(executor.clone(), Arc::new(LaunchMacros), omnibar, Arc::new(MockPower), Arc::new(MockTerminal));
+
+        cmd.execute(&Action::RunMacro("web".to_string()), "").unwrap();
+        assert_eq!(executor.app_ids.lock().unwrap().as_slice(), [Some("firefox".to_string())]);
+
+        // The top search result is a file, which a launch step doesn't open
+        let err = cmd.execute(&Action::RunMacro("notes".to_string()), "").unwrap_err();
+        assert!(err.contains("No app matches"), "{}", err);
+        assert_eq!(executor.specs.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_running_app_is_focused_and_cycled() {
+        let executor = Arc::new(RecordingExecutor::default());
+        let windows = Arc::new(FirefoxWindows::default());
+        let omnibar = Arc::new(create_omnibar_with_windows(windows.clone()));
+        let cmd = ExecuteCommand::new(executor.clone(), Arc::new(MockMacro), omnibar, Arc::new(MockPower), Arc::new(MockTerminal));
+
+        let firefox = Action::Launch("firefox".to_string());
+        cmd.execute(&firefox, "").unwrap();
+        cmd.execute(&firefox, "").unwrap();
+        cmd.execute(&firefox, "").unwrap();
+        assert_eq!(windows.focused.lock().unwrap().as_slice(), ["0xa", "0xb", "0xa"]);
+        assert!(executor.specs.lock().unwrap().is_empty());
+
+        // Forcing a new instance skips the windows
+        cmd.execute_new_instance(&firefox, "").unwrap();
+        assert_eq!(executor.specs.lock().unwrap().as_slice(), [CommandSpec::new("firefox", Vec::<String>::new())]);
+        assert_eq!(executor.app_ids.lock().unwrap().as_slice(), [Some("firefox".to_string())]);
+
+        // Desktop actions always launch, in the app's scope
+        cmd.execute(&Action::Launch("firefox --private-window".to_string()), "").unwrap();
+        assert_eq!(executor.specs.lock().unwrap()[1].argv(), ["firefox", "--private-window"]);
+        assert_eq!(executor.app_ids.lock().unwrap()[1].as_deref(), Some("firefox"));
+
+        // Commands that no installed app runs never reach a shell
+        assert!(cmd.execute(&Action::Launch("firefox; rm -rf ~".to_string()), "").is_err());
+        assert_eq!(executor.specs.lock().unwrap().len(), 2);
+    }
+
+    struct FailingExecutor;
+
+    impl ICommandExecutor for FailingExecutor {
+        fn execute(&self, cmd: &str) -> Result<(), String> {
+            Err(format!("{}: not found", cmd))
+        }
+
+        fn spawn(&self, spec: &CommandSpec) -> Result<(), String> {
+            Err(format!("{} is not installed", spec.program))
+        }
+    }
+
+    #[test]
+    fn test_failures_are_returned() {
+        let cmd = ExecuteCommand::new(Arc::new(FailingExecutor), Arc::new(MockMacro), Arc::new(create_omnibar()), Arc::new(MockPower), Arc::new(MockTerminal));
+
+        assert_eq!(cmd.execute(&Action::RunMacro("test".to_string()), ""), Err("x echo hi: not found".to_string()));
+        assert!(cmd.execute(&Action::RunMacro("missing".to_string()), "").is_err());
+        let unclaimed = Action::Provider { id: "nope".to_string(), payload: String::new() };
+        assert!(cmd.execute(&unclaimed, "").is_err());
     }
 }
@@ -1,95 +1,463 @@
-use crate::domain::model::App;
-use crate::domain::ports::{IAppRepository, IProcessMonitor, IFileSystem, ISystemPower, ICalculator, IShortcutRepository, IMacroRepository, IWindowRepository, IDictionaryService, ILLMService, IFileIndexer, ITimeService};
-use std::sync::Arc;
+use crate::domain::model::{Action, AiAnswer, App, Category, ChatError, ChatMessage, ChatRole, ChatSession, Citation, FileQuery, InternalCommand, SearchResult};
+use crate::domain::ports::{IAppRepository, IProcessMonitor, IFileSystem, IMimeDatabase, ISystemPower, ICalculator, IShortcutRepository, IMacroRepository, IWindowRepository, IDictionaryService, ILLMService, IFileIndexer, IPassageIndex, IChatHistory, ITimeService, ISearchProvider, IUsageHistory, IActionProvider};
+use crate::application::services::assistant_tools::{AssistantTools, ToolOutcome};
+use crate::application::services::frecency::UsageScores;
+use crate::application::providers::registry::ProviderRegistry;
+use crate::application::providers::{
+    window_provider::WindowProvider, terminal_provider::TerminalProvider, file_provider::FileBrowserProvider, content_provider::ContentSearchProvider,
+    shortcut_provider::ShortcutProvider, calculator_provider::CalculatorProvider, dictionary_provider::DictionaryProvider,
+    ai_provider::{AiProvider, chat_result}, macro_provider::MacroProvider, system_provider::SystemProvider,
+    time_provider::TimeProvider, launch_provider::LaunchProvider,
+};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
 
 #[derive(Debug, Clone)]
 pub struct OverviewData {
-    pub apps: Vec<App>,
-    pub folders: Vec<App>,
-    pub shortcuts: Vec<App>,
-    pub macros: Vec<App>,
-    pub ai: Vec<App>,
-    pub settings: Vec<App>,
-    pub system: Vec<App>,
+    pub apps: Vec<SearchResult>,
+    pub folders: Vec<SearchResult>,
+    pub shortcuts: Vec<SearchResult>,
+    pub macros: Vec<SearchResult>,
+    pub ai: Vec<SearchResult>,
+    pub settings: Vec<SearchResult>,
+    pub system: Vec<SearchResult>,
     pub ai_ready: bool,
 }
 
+/// What Enter does while a chat is open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatEnter {
+    /// Run the selected source or suggested action.
+    Activate(Action),
+    /// Send the typed follow-up question.
+    Ask(String),
+    Nothing,
+}
+
+/// What the assistant (`?`) works with.
+pub struct AiServices {
+    pub llm: Arc<dyn ILLMService + Send + Sync>,
+    /// Passages of the user's files, given to the model as context.
+    pub passages: Arc<dyn IPassageIndex + Send + Sync>,
+    pub chats: Arc<dyn IChatHistory + Send + Sync>,
+}
+
 pub struct Omnibar {
     app_repo: Arc<dyn IAppRepository + Send + Sync>,
     process_monitor: Arc<dyn IProcessMonitor + Send + Sync>,
     fs: Arc<dyn IFileSystem + Send + Sync>,
+    pub mime: Arc<dyn IMimeDatabase + Send + Sync>,
     pub shortcuts: Arc<dyn IShortcutRepository + Send + Sync>,
     pub macros: Arc<dyn IMacroRepository + Send + Sync>,
     pub window_repo: Arc<dyn IWindowRepository + Send + Sync>,
-    power: Arc<dyn ISystemPower + Send + Sync>,
-    calculator: Arc<dyn ICalculator + Send + Sync>,
-    dictionary: Arc<dyn IDictionaryService + Send + Sync>,
     pub llm: Arc<dyn ILLMService + Send + Sync>,
     pub indexer: Arc<dyn IFileIndexer + Send + Sync>,
+    passages: Arc<dyn IPassageIndex + Send + Sync>,
     pub time: Arc<dyn ITimeService + Send + Sync>,
     pub settings: Arc<crate::infrastructure::services::settings_store::SettingsStore>,
+    calculator: Arc<dyn ICalculator + Send + Sync>,
+    history: Arc<dyn IUsageHistory + Send + Sync>,
+    providers: ProviderRegistry,
+    /// Cancel flag of the answer being generated, if any.
+    ai_generation: Mutex<Option<Arc<AtomicBool>>>,
+    /// Held while a question is answered and saved, so a follow-up sees the
+    /// turn it cancelled.
+    ai_turn: Mutex<()>,
+    chats: Arc<dyn IChatHistory + Send + Sync>,
+    /// The conversation follow-up questions go to.
+    chat: Mutex<Option<ChatSession>>,
+    tools: AssistantTools,
 }
 
+/// Base score of an inline calculator result, before weighting. Fuzzy scores
+/// rarely get this high, so a valid expression lands on top by default.
+const CALCULATOR_SCORE: f64 = 1000.0;
+
+const GENERIC_NAME_FACTOR: f64 = 0.8;
+const KEYWORD_FACTOR: f64 = 0.6;
+const DESKTOP_ACTION_FACTOR: f64 = 0.9;
+
+/// Shortest query that is also looked up in the file index.
+const MIN_FILE_QUERY_LEN: usize = 3;
+/// Most indexed files blended into the results.
+const MAX_FILE_RESULTS: usize = 20;
+/// Files taken from the index before opened ones are boosted and the list
+/// is cut to `MAX_FILE_RESULTS`, so the boost can lift a file the index
+/// alone ranks lower.
+const FILE_CANDIDATES: usize = 100;
+/// Recent chats offered in the overview's AI section.
+const MAX_OVERVIEW_CHATS: usize = 8;
+/// Chats are titled with the start of their first question.
+const MAX_CHAT_TITLE_CHARS: usize = 60;
+/// Model replies per question; the last one gets no tools, so it has to answer.
+const MAX_TOOL_ROUNDS: usize = 4;
+
 impl Omnibar {
     pub fn new(
         app_repo: Arc<dyn IAppRepository + Send + Sync>,
         process_monitor: Arc<dyn IProcessMonitor + Send + Sync>,
         fs: Arc<dyn IFileSystem + Send + Sync>,
+        mime: Arc<dyn IMimeDatabase + Send + Sync>,
         shortcuts: Arc<dyn IShortcutRepository + Send + Sync>,
         macros: Arc<dyn IMacroRepository + Send + Sync>,
         window_repo: Arc<dyn IWindowRepository + Send + Sync>,
         power: Arc<dyn ISystemPower + Send + Sync>,
         calculator: Arc<dyn ICalculator + Send + Sync>,
         dictionary: Arc<dyn IDictionaryService + Send + Sync>,
-        llm: Arc<dyn ILLMService + Send + Sync>,
+        ai: AiServices,
         indexer: Arc<dyn IFileIndexer + Send + Sync>,
         time: Arc<dyn ITimeService + Send + Sync>,
         settings: Arc<crate::infrastructure::services::settings_store::SettingsStore>,
+        history: Arc<dyn IUsageHistory + Send + Sync>,
     ) -> Self {
+        let AiServices { llm, passages, chats } = ai;
+        let window_provider = Arc::new(WindowProvider::new(window_repo.clone()));
+        let file_provider = Arc::new(FileBrowserProvider::new(fs.clone(), mime.clone(), settings.clone()));
+        let shortcut_provider = Arc::new(ShortcutProvider::new(shortcuts.clone()));
+        let macro_provider = Arc::new(MacroProvider::new(macros.clone()));
+        let tools = AssistantTools::new(
+            app_repo.clone(),
+            fs.clone(),
+            window_repo.clone(),
+            macros.clone(),
+            calculator.clone(),
+            dictionary.clone(),
+        );
+
+        let providers = ProviderRegistry::new();
+        providers.register(window_provider.clone());
+        providers.register(Arc::new(TerminalProvider::new(fs.clone(), history.clone())));
+        providers.register(file_provider.clone());
+        providers.register(Arc::new(ContentSearchProvider::new(indexer.clone(), mime.clone())));
+        providers.register(shortcut_provider.clone());
+        providers.register(Arc::new(CalculatorProvider::new(calculator.clone())));
+        providers.register(Arc::new(DictionaryProvider::new(dictionary)));
+        providers.register(Arc::new(AiProvider::new(chats.clone())));
+        providers.register(macro_provider.clone());
+        providers.register(Arc::new(SystemProvider::new(power)));
+        providers.register(Arc::new(TimeProvider::new(time.clone())));
+        providers.register(Arc::new(LaunchProvider::new()));
+
+        providers.register_actions(window_provider);
+        providers.register_actions(file_provider);
+        providers.register_actions(shortcut_provider);
+        providers.register_actions(macro_provider);
+
         Self {
             app_repo,
             process_monitor,
             fs,
+            mime,
             shortcuts,
             macros,
             window_repo,
-            power,
-            calculator,
-            dictionary,
             llm,
             indexer,
+            passages,
+            ai_generation: Mutex::new(None),
+            ai_turn: Mutex::new(()),
+            chats,
+            chat: Mutex::new(None),
+            tools,
             time,
             settings,
+            calculator,
+            history,
+            providers,
+        }
+    }
+
+    /// Adds a search mode, or replaces the built-in one with the same id.
+    pub fn register_provider(&self, provider: Arc<dyn ISearchProvider + Send + Sync>) {
+        self.providers.register(provider);
+    }
+
+    /// Adds a source of Ctrl+Enter sub-actions.
+    pub fn register_action_provider(&self, provider: Arc<dyn IActionProvider + Send + Sync>) {
+        self.providers.register_actions(provider);
+    }
+
+    /// Everything listed for a result on Ctrl+Enter: its own sub-actions
+    /// (e.g. desktop actions) followed by those of the action providers.
+    pub fn sub_actions(&self, result: &SearchResult) -> Vec<SearchResult> {
+        let mut actions = result.sub_actions.clone();
+        actions.extend(self.providers.actions_for(result));
+        actions
+    }
+
+    /// Returns (provider id, effective prefix) for every registered mode.
+    pub fn provider_prefixes(&self) -> Vec<(String, String)> {
+        self.providers.prefixes(&self.settings.get_prefix_overrides())
+    }
+
+    /// The mode prefix `query` starts with, or "" for a plain search.
+    pub fn mode_prefix<'a>(&self, query: &'a str) -> &'a str {
+        let overrides = self.settings.get_prefix_overrides();
+        match self.providers.resolve(query, &overrides) {
+            Some((_, rest)) => &query[..query.len() - rest.len()],
+            None => "",
+        }
+    }
+
+    /// Effective prefix of a provider, taking settings overrides into account.
+    pub fn prefix_for(&self, provider_id: &str) -> Option<String> {
+        self.provider_prefixes().into_iter()
+            .find(|(id, _)| id == provider_id)
+            .map(|(_, prefix)| prefix)
+    }
+
+    /// Lets the provider that produced `action` run it. Returns `None` if none claimed it.
+    pub fn activate(&self, action: &Action) -> Option<Result<(), String>> {
+        self.providers.activate(action)
+    }
+
+    /// The installed app launched by this command line, as its main command
+    /// or one of its desktop actions.
+    pub fn find_app(&self, exec_path: &str) -> Option<App> {
+        self.app_repo.find_apps().into_iter()
+            .find(|app| app.exec_path == exec_path || app.actions.iter().any(|a| a.exec_path == exec_path))
+    }
+
+    /// The installed app best matching `name`, by name, generic name or keywords.
+    pub fn match_app(&self, name: &str) -> Option<App> {
+        let matcher = SkimMatcherV2::default();
+        self.app_repo.find_apps().into_iter()
+            .filter_map(|app| Some((app_match_score(&matcher, &app, name)?, app)))
+            .max_by(|a, b| a.0.total_cmp(&b.0).then_with(|| b.1.name.cmp(&a.1.name)))
+            .map(|(_, app)| app)
+    }
+
+    /// Records that `action` was picked while `query` was typed, for ranking.
+    pub fn record_usage(&self, action: &Action, query: &str) {
+        if let Some(key) = action.usage_key() {
+            self.history.record(&key, query.trim());
+        }
+    }
+
+    fn usage_scores(&self, query: &str) -> UsageScores {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        UsageScores::from_records(&self.history.records(), query, now)
+    }
+
+    /// Favorites first, then most frecent, then running, then by name.
+    fn sort_by_usage(&self, results: &mut [SearchResult]) {
+        let usage = self.usage_scores("");
+        let frecency = |r: &SearchResult| r.action.usage_key().map_or(0.0, |key| usage.frecency(&key));
+        results.sort_by(|a, b| {
+            b.is_favorite.cmp(&a.is_favorite)
+                .then_with(|| frecency(b).total_cmp(&frecency(a)))
+                .then(b.is_running.cmp(&a.is_running))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+    }
+
+    /// Whether a chat is open, i.e. questions continue a conversation.
+    pub fn chat_open(&self) -> bool {
+        self.chat.lock().unwrap().is_some()
+    }
+
+    /// Enter picks the selected row if it does something (a cited file, a
+    /// suggested action); otherwise it sends the question typed after the
+    /// AI prefix.
+    pub fn chat_enter(&self, query: &str, selected: Option<&SearchResult>) -> ChatEnter {
+        if let Some(result) = selected.filter(|r| r.action != Action::None) {
+            return ChatEnter::Activate(result.action.clone());
+        }
+        let prefix = self.prefix_for("ai").unwrap_or_else(|| "? ".to_string());
+        match query.strip_prefix(prefix.as_str()).map(str::trim) {
+            Some(question) if !question.is_empty() => ChatEnter::Ask(question.to_string()),
+            _ => ChatEnter::Nothing,
         }
     }
 
-    pub fn query_ai(&self, prompt: &str) -> Result<String, String> {
-        // Simple keyword search for context
-        // We look for nouns/words in the prompt that might be folder names
-        let context = if prompt.len() > 3 {
-             let keywords: Vec<&str> = prompt.split_whitespace()
-                 .filter(|w| w.len() > 3)
-                 .collect();
-             
-             let mut found_paths = Vec::new();
-             for kw in keywords {
-                 found_paths.extend(self.indexer.search(kw));
-             }
-             
-             if !found_paths.is_empty() {
-                 found_paths.sort();
-                 found_paths.dedup();
-                 Some(found_paths.join("\n"))
-             } else {
-                 None
-             }
-        } else {
-            None
+    /// Opens a new chat unless one is open already.
+    pub fn open_chat(&self) {
+        self.chat.lock().unwrap().get_or_insert_with(|| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            ChatSession { id: now.as_millis().to_string(), title: String::new(), updated: now.as_secs(), messages: vec![] }
+        });
+    }
+
+    /// Makes a saved chat the open one and returns it.
+    pub fn resume_chat(&self, id: &str) -> Option<ChatSession> {
+        self.cancel_ai();
+        let session = self.chats.get(id)?;
+        *self.chat.lock().unwrap() = Some(session.clone());
+        Some(session)
+    }
+
+    /// Closes the open chat, stopping an answer being written. The next
+    /// question starts a new chat.
+    pub fn end_chat(&self) {
+        self.cancel_ai();
+        self.chat.lock().unwrap().take();
+    }
+
+    /// Answers the next question of the open chat (opening one if needed),
+    /// with passages of the user's files as context, citing the passages the
+    /// answer refers to. The model can use the launcher's tools; actions it
+    /// suggests come back as proposals for the user to pick. The answer is
+    /// streamed to `on_token`; a question still being answered is cancelled,
+    /// and kept with what it got so far before this one is asked. The chat is
+    /// saved afterwards.
+    pub fn query_ai(&self, prompt: &str, on_token: &mut dyn FnMut(&str)) -> Result<AiAnswer, String> {
+        let cancel = Arc::new(AtomicBool::new(false));
+        if let Some(previous) = self.ai_generation.lock().unwrap().replace(cancel.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+        let _turn = self.ai_turn.lock().unwrap();
+        self.open_chat();
+        let (chat_id, mut messages) = {
+            let chat = self.chat.lock().unwrap();
+            let session = chat.as_ref().expect("chat was just opened");
+            (session.id.clone(), session.messages.clone())
         };
 
-        self.llm.query(prompt, context)
+        let passages = self.passages.retrieve(prompt, self.settings.get_rag_settings().passages)
+            .unwrap_or_else(|e| {
+                println!("No file context for the question: {}", e);
+                vec![]
+            });
+
+        let context = (!passages.is_empty()).then(|| {
+            let excerpts: Vec<String> = passages.iter().enumerate()
+                .map(|(i, p)| format!("[{}] {} (lines {}–{}):\n{}", i + 1, p.path, p.start_line, p.end_line, p.text))
+                .collect();
+            format!(
+                "Excerpts from the user's files:\n\n{}\n\nUse the excerpts if they are relevant and cite them as [1], [2] and so on.",
+                excerpts.join("\n\n")
+            )
+        });
+
+        // Excerpts only go with the question they were found for
+        let question = match context {
+            Some(context) => format!("{}\n\nUser Question: {}", context, prompt),
+            None => prompt.to_string(),
+        };
+        messages.push(ChatMessage::new(ChatRole::User, question));
+        let reply = self.converse(messages, on_token, &cancel);
+        {
+            let mut generation = self.ai_generation.lock().unwrap();
+            if generation.as_ref().is_some_and(|current| Arc::ptr_eq(current, &cancel)) {
+                *generation = None;
+            }
+        }
+        let (text, proposals) = reply?;
+        let cited = cited_numbers(&text, passages.len());
+        let citations = passages.into_iter().enumerate()
+            .map(|(i, p)| Citation { number: i + 1, path: p.path, start_line: p.start_line, end_line: p.end_line })
+            // Without citations in the answer, all excerpts are listed as sources
+            .filter(|c| cited.is_empty() || cited.contains(&c.number))
+            .collect();
+        let answer = AiAnswer { text, citations, proposals };
+        self.save_turn(&chat_id, prompt, &answer);
+        Ok(answer)
+    }
+
+    /// Lets the model answer, running the tools it calls until it replies
+    /// without calls. Returns the text of its replies and the actions it
+    /// proposed.
+    fn converse(&self, mut messages: Vec<ChatMessage>, on_token: &mut dyn FnMut(&str), cancel: &AtomicBool) -> Result<(String, Vec<SearchResult>), String> {
+        let mut tools = self.tools.specs();
+        let mut text = String::new();
+        let mut proposals = Vec::new();
+        for round in 1..=MAX_TOOL_ROUNDS {
+            let offered = if round < MAX_TOOL_ROUNDS { tools.as_slice() } else { &[] };
+            // A blank line separates the text of each round from the earlier ones
+            let mut pending_break = !text.is_empty();
+            let mut stream = |token: &str| {
+                if pending_break {
+                    if token.trim().is_empty() {
+                        return;
+                    }
+                    on_token("\n\n");
+                    pending_break = false;
+                }
+                on_token(token);
+            };
+            let reply = match self.llm.chat(&messages, offered, &mut stream, cancel) {
+                // Models without function calling still get to answer
+                Err(ChatError::ToolsUnsupported(e)) => {
+                    println!("Asking without tools: {}", e);
+                    tools.clear();
+                    self.llm.chat(&messages, &[], &mut stream, cancel)
+                },
+                reply => reply,
+            }.map_err(|e| e.to_string())?;
+            if !reply.content.trim().is_empty() {
+                if !text.is_empty() {
+                    text.push_str("\n\n");
+                }
+                text.push_str(reply.content.trim_start());
+            }
+            if reply.tool_calls.is_empty() || cancel.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let calls = reply.tool_calls.clone();
+            messages.push(reply);
+            for call in calls {
+                let result = match self.tools.call(&call) {
+                    Ok(ToolOutcome::Answer(answer)) => answer,
+                    Ok(ToolOutcome::Proposal(proposal)) => {
+                        let result = format!("Shown to the user as \"{}\". It happens only if they pick it; don't say it was done.", proposal.name);
+                        proposals.push(proposal);
+                        result
+                    },
+                    Err(e) => format!("Error: {}", e),
+                };
+                messages.push(ChatMessage { tool_call_id: Some(call.id), ..ChatMessage::new(ChatRole::Tool, result) });
+            }
+        }
+        Ok((text, proposals))
+    }
+
+    /// Adds a question and its answer to the chat, unless it was closed meanwhile.
+    fn save_turn(&self, chat_id: &str, prompt: &str, answer: &AiAnswer) {
+        let mut chat = self.chat.lock().unwrap();
+        let Some(session) = chat.as_mut().filter(|session| session.id == chat_id) else { return };
+        if session.title.is_empty() {
+            session.title = prompt.chars().take(MAX_CHAT_TITLE_CHARS).collect();
+        }
+        session.messages.push(ChatMessage::new(ChatRole::User, prompt));
+        session.messages.push(ChatMessage { citations: answer.citations.clone(), ..ChatMessage::new(ChatRole::Assistant, answer.text.clone()) });
+        session.updated = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        if let Err(e) = self.chats.save(session) {
+            println!("Failed to save chat: {}", e);
+        }
+    }
+
+    /// Stops the answer being generated. Returns false if there is none.
+    pub fn cancel_ai(&self) -> bool {
+        match self.ai_generation.lock().unwrap().take() {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Result rows opening the cited files.
+    pub fn citation_results(&self, citations: &[Citation]) -> Vec<SearchResult> {
+        let home = std::env::var("HOME").unwrap_or_default();
+        citations.iter()
+            .map(|citation| {
+                let shown_path = match citation.path.strip_prefix(&home) {
+                    Some(rest) if !home.is_empty() => format!("~{}", rest),
+                    _ => citation.path.clone(),
+                };
+                let icon = self.mime.icon_name(&self.mime.mime_type(&citation.path));
+                SearchResult::new(citation.label(), &icon, Action::OpenPath(citation.path.clone()))
+                    .with_detail(shown_path)
+            })
+            .collect()
     }
 
     pub fn get_overview(&self) -> OverviewData {
@@ -102,15 +470,9 @@ impl Omnibar {
             app.is_favorite = self.settings.is_favorite(&app.name);
         }
 
-        // Sort by Favorite -> Running -> Name
-        apps.sort_by(|a, b| {
-            b.is_favorite.cmp(&a.is_favorite)
-                .then(b.is_running.cmp(&a.is_running))
-                .then_with(|| a.name.cmp(&b.name))
-        });
-        
         // Take all apps now that we scroll
-        let top_apps = apps;
+        let mut top_apps: Vec<SearchResult> = apps.into_iter().map(SearchResult::from).collect();
+        self.sort_by_usage(&mut top_apps);
 
         // 2. Folders (Home Dir)
         let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
@@ -121,87 +483,57 @@ impl Omnibar {
             let full_path = format!("{}/{}", home.trim_end_matches('/'), name);
             if self.fs.is_dir(&full_path) {
                 let is_fav = self.settings.is_favorite(&name);
-                folders.push(App {
+                folders.push(SearchResult {
                     name: name.clone(),
-                    exec_path: format!("nautilus \"{}\"", full_path),
                     icon: Some("folder".to_string()),
+                    action: Action::OpenPath(full_path),
                     is_running: false,
                     is_favorite: is_fav,
+                    category: None,
+                    sub_actions: Vec::new(),
+                    detail: None,
                 });
             }
         }
         
-        folders.sort_by(|a, b| {
-            b.is_favorite.cmp(&a.is_favorite)
-                .then_with(|| a.name.cmp(&b.name))
-        });
+        self.sort_by_usage(&mut folders);
         let top_folders = folders.into_iter().take(8).collect();
 
         // 3. Shortcuts
         let all_shortcuts = self.shortcuts.get_all();
-        let mut shortcut_apps: Vec<App> = all_shortcuts.into_iter().map(|(k, v)| App {
-            name: format!("Shortcut: {}", k),
-            exec_path: v,
-            icon: Some("emblem-symbolic-link".to_string()),
-            is_running: false,
-            is_favorite: false,
-        }).collect();
+        let mut shortcut_apps: Vec<SearchResult> = all_shortcuts.into_iter()
+            .map(|(k, v)| SearchResult::new(format!("Shortcut: {}", k), "emblem-symbolic-link", Action::Shell(v)))
+            .collect();
         shortcut_apps.sort_by(|a, b| a.name.cmp(&b.name));
 
         // 4. Macros
         let all_macros = self.macros.get_all();
-        let mut macro_apps: Vec<App> = all_macros.into_iter().map(|m| App {
-            name: format!("Macro: {}", m.name),
-            exec_path: format!("internal:macro:{}", m.name),
-            icon: Some("system-run".to_string()),
-            is_running: false,
-            is_favorite: false,
-        }).collect();
+        let mut macro_apps: Vec<SearchResult> = all_macros.into_iter()
+            .map(|m| SearchResult::new(format!("Macro: {}", m.name), "system-run", Action::RunMacro(m.name)))
+            .collect();
         macro_apps.sort_by(|a, b| a.name.cmp(&b.name));
 
         // 5. AI Items
-        let ai = vec![
-            App {
-                 name: "Ask AI".to_string(),
-                 exec_path: "internal:ai:".to_string(), // Empty prompt triggers input mode? Or just placeholder
-                 icon: Some("system-search".to_string()),
-                 is_running: false,
-                 is_favorite: false,
-            }
+        // An empty prompt switches the entry to AI mode; recent chats follow
+        let mut ai = vec![
+            SearchResult::new("Ask AI", "system-search", Action::AskAi(String::new())),
         ];
+        ai.extend(self.chats.sessions().iter().take(MAX_OVERVIEW_CHATS).map(chat_result));
         let ai_ready = true;
 
         // 6. Settings Items
         let settings = vec![
-            App { 
-                name: "Settings".to_string(), 
-                exec_path: "internal:settings".to_string(), 
-                icon: Some("preferences-system".to_string()), 
-                is_running: false,
-                is_favorite: false,
-            },
-            App { 
-                name: "About".to_string(), 
-                exec_path: "internal:about".to_string(), 
-                icon: Some("help-about".to_string()), 
-                is_running: false,
-                is_favorite: false,
-            },
-            App { 
-                name: "Quit".to_string(), 
-                exec_path: "internal:quit".to_string(), 
-                icon: Some("application-exit".to_string()), 
-                is_running: false,
-                is_favorite: false, 
-            },
+            SearchResult::new("Settings", "preferences-system", Action::Internal(InternalCommand::Settings)),
+            SearchResult::new("About", "help-about", Action::Internal(InternalCommand::About)),
+            SearchResult::new("Quit", "application-exit", Action::Internal(InternalCommand::Quit)),
         ];
 
         // 7. System Items
         let system = vec![
-            App { name: "Lock".to_string(), exec_path: "internal:system:lock".to_string(), icon: Some("system-lock-screen".to_string()), is_running: false, is_favorite: false },
-            App { name: "Suspend".to_string(), exec_path: "internal:system:suspend".to_string(), icon: Some("system-suspend".to_string()), is_running: false, is_favorite: false },
-            App { name: "Reboot".to_string(), exec_path: "internal:system:reboot".to_string(), icon: Some("system-reboot".to_string()), is_running: false, is_favorite: false },
-            App { name: "Power Off".to_string(), exec_path: "internal:system:poweroff".to_string(), icon: Some("system-shutdown".to_string()), is_running: false, is_favorite: false },
+            SearchResult::new("Lock", "system-lock-screen", Action::System("lock".to_string())),
+            SearchResult::new("Suspend", "system-suspend", Action::System("suspend".to_string())),
+            SearchResult::new("Reboot", "system-reboot", Action::System("reboot".to_string())),
+            SearchResult::new("Power Off", "system-shutdown", Action::System("poweroff".to_string())),
         ];
 
         OverviewData {
@@ -216,7 +548,7 @@ impl Omnibar {
         }
     }
 
-    pub fn search(&self, query: &str) -> Vec<App> {
+    pub fn search(&self, query: &str) -> Vec<SearchResult> {
         if query.is_empty() {
             // Default: Show running apps or top apps
             let mut apps = self.app_repo.find_apps();
@@ -227,396 +559,161 @@ impl Omnibar {
                 app.is_favorite = self.settings.is_favorite(&app.name);
             }
 
-            apps.sort_by(|a, b| {
-                b.is_favorite.cmp(&a.is_favorite)
-                .then(b.is_running.cmp(&a.is_running))
-                .then_with(|| a.name.cmp(&b.name))
-            });
-            return apps;
-        }
-
-        if let Some(w_query) = query.strip_prefix("w ") {
-             let keyword = w_query.trim().to_lowercase();
-             let windows = self.window_repo.get_open_windows();
-             
-             return windows.into_iter()
-                 .filter(|w| {
-                     keyword.is_empty() 
-                     || w.title.to_lowercase().contains(&keyword) 
-                     || w.app_name.to_lowercase().contains(&keyword)
-                 })
-                 .map(|w| {
-                     let ws_label = if w.workspace >= 0 { format!("[WS {}]", w.workspace + 1) } else { "[WS ?]".to_string() };
-                     let screen_label = if w.screen >= 0 { format!("[SCR {}]", w.screen + 1) } else { "[SCR ?]".to_string() };
-                     App {
-                         name: format!("{} {} {} - {}", ws_label, screen_label, w.app_name, w.title),
-                         exec_path: format!("internal:window:{}", w.id),
-                         icon: Some("preferences-system-windows".to_string()),
-                         is_running: true,
-                         is_favorite: false,
-                     }
-                 })
-                 .collect();
+            let mut results: Vec<SearchResult> = apps.into_iter().map(SearchResult::from).collect();
+            self.sort_by_usage(&mut results);
+            return results;
         }
 
-        if let Some(term_cmd) = query.strip_prefix("x ") {
-             let cmd = term_cmd.trim();
-             if !cmd.is_empty() {
-                 let wrapped_cmd = format!(
-                     "if command -v gnome-terminal >/dev/null 2>&1; then gnome-terminal -- {}; elif command -v ptyxis >/dev/null 2>&1; then ptyxis --standalone -- {}; else x-terminal-emulator -e {}; fi", 
-                     cmd, cmd, cmd
-                 );
-                 return vec![App {
-                     name: format!("Execute: {}", cmd),
-                     exec_path: wrapped_cmd,
-                     icon: Some("utilities-terminal".to_string()),
-                     is_running: false,
-                     is_favorite: false,
-                 }];
-             }
-             return vec![];
+        let overrides = self.settings.get_prefix_overrides();
+        if let Some((provider, rest)) = self.providers.resolve(query, &overrides) {
+            return provider.search(rest);
         }
 
-        if let Some(fs_query) = query.strip_prefix("f ") {
-            let path_input = fs_query.trim();
-            // Basic ~ expansion
-            let search_path = if path_input.starts_with("~") {
-                if let Ok(home) = std::env::var("HOME") {
-                    path_input.replacen("~", &home, 1)
-                } else {
-                    path_input.to_string()
-                }
-            } else if path_input.is_empty() {
-                std::env::var("HOME").unwrap_or_else(|_| "/".to_string())
-            } else {
-                path_input.to_string()
-            };
+        self.blended_search(query)
+    }
 
+    /// Prefix-less search: fuzzy-matches every source and merges the rows by
+    /// weighted score plus usage bonus, labelling each with the category it came from.
+    fn blended_search(&self, query: &str) -> Vec<SearchResult> {
+        let matcher = SkimMatcherV2::default();
+        let weights = self.settings.get_search_weights();
+        let weight = |category: Category| {
+            weights.get(category.id()).copied().unwrap_or_else(|| category.default_weight())
+        };
 
-            let path = std::path::Path::new(&search_path);
-            let (dir, prefix) = if search_path.ends_with('/') || self.fs.is_dir(&search_path) {
-                if self.fs.is_dir(&search_path) {
-                    (search_path.as_str(), "")
-                } else {
-                     // Dir doesn't exist yet but ends with /, fallback or empty?
-                     // Logic from before: treat parent
-                     if self.fs.exists(&search_path) {
-                          (search_path.as_str(), "")
-                     } else {
-                         // parent
-                         if let Some(parent) = path.parent() {
-                             (parent.to_str().unwrap_or("/"), path.file_name().and_then(|s| s.to_str()).unwrap_or(""))
-                         } else {
-                             ("/", "")
-                         }
-                     }
-                }
-            } else {
-                 if let Some(parent) = path.parent() {
-                     (parent.to_str().unwrap_or("/"), path.file_name().and_then(|s| s.to_str()).unwrap_or(""))
-                 } else {
-                     ("/", "")
-                 }
-            };
+        // Frecency and what was picked for this exact query before
+        let usage = self.usage_scores(query);
 
-            let entries = self.fs.list_dir(dir);
-            let mut results = vec![];
-            for name in entries {
-                if name.starts_with('.') { continue; }
-                if !prefix.is_empty() && !name.to_lowercase().starts_with(&prefix.to_lowercase()) { continue; }
-                
-                let full_path = format!("{}/{}", dir.trim_end_matches('/'), name);
-                let is_dir = self.fs.is_dir(&full_path);
-                let display_name = if is_dir { format!("{}/", name) } else { name.clone() };
-                let icon = if is_dir { "folder" } else { "text-x-generic" };
-                let exec = if is_dir {
-                    format!("nautilus \"{}\"", full_path)
-                } else {
-                    format!("xdg-open \"{}\"", full_path)
-                };
+        let mut scored: Vec<(f64, SearchResult)> = Vec::new();
+        let mut add = |category: Category, score: f64, result: SearchResult| {
+            let bonus = result.action.usage_key().map_or(0.0, |key| usage.bonus(&key));
+            scored.push((score * weight(category) + bonus, result.with_category(category)));
+        };
 
-                results.push(App {
-                    name: display_name,
-                    exec_path: exec,
-                    icon: Some(icon.to_string()),
-                    is_running: false,
-                    is_favorite: false,
-                });
+        // Filters (`ext:md`, `in:~/work`, `modified:<7d`) make it a file search
+        let file_query = FileQuery::parse(query);
+        if !file_query.has_filters() {
+            // 1. Inline calculator
+            if looks_like_math(query) && let Some(result) = self.calculator.calculate(query.trim()) {
+                add(Category::Calculator, CALCULATOR_SCORE,
+                    SearchResult::new(format!("= {}", result), "accessories-calculator", Action::CopyText(result)));
             }
-             // Sort: Directories first, then alphabetical
-            results.sort_by(|a, b| {
-                let a_is_dir = a.name.ends_with('/');
-                let b_is_dir = b.name.ends_with('/');
-                if a_is_dir && !b_is_dir { std::cmp::Ordering::Less }
-                else if !a_is_dir && b_is_dir { std::cmp::Ordering::Greater }
-                else { a.name.cmp(&b.name) }
-            });
-            return results.into_iter().take(20).collect();
-        }
-
-        if let Some(sc_query) = query.strip_prefix("ss ") {
-             let key = sc_query.trim();
-             if let Some(cmd) = self.shortcuts.get(key) {
-                 return vec![App {
-                     name: format!("Shortcut: {}", key),
-                     exec_path: cmd,
-                     icon: Some("emblem-symbolic-link".to_string()),
-                     is_running: false,
-                     is_favorite: false,
-                 }];
-             }
-             // Show all shortcuts matching?
-             let all = self.shortcuts.get_all();
-             return all.into_iter()
-                 .filter(|(k, _)| k.contains(key))
-                 .map(|(k, v)| App {
-                     name: format!("Shortcut: {}", k),
-                     exec_path: v,
-                     icon: Some("emblem-symbolic-link".to_string()),
-                     is_running: false,
-                     is_favorite: false,
-                 })
-                 .collect();
-        }
-        
-        if let Some(calc_query) = query.strip_prefix("c ") {
-             let expr = calc_query.trim();
-             if let Some(result) = self.calculator.calculate(expr) {
-                  return vec![App {
-                      name: format!("= {}", result),
-                      exec_path: format!("echo \"{}\" | xclip -selection clipboard", result), // Copy to clipboard
-                      icon: Some("accessories-calculator".to_string()),
-                      is_running: false,
-                      is_favorite: false,
-                  }];
-             }
-             return vec![];
-        }
-
-        if let Some(dict_query) = query.strip_prefix("d ") {
-             let term = dict_query.trim();
-             if term.is_empty() { return vec![]; }
-             
-             let (name, exec) = match self.dictionary.lookup(term) {
-                  Some(def) => {
-                      (format!("{}: {}", term, def), format!("xdg-open \"https://google.com/search?q=define+{}\"", term))
-                  },
-                 None => {
-                      (format!("Define '{}' on Google", term), format!("xdg-open \"https://google.com/search?q=define+{}\"", term))
-                 }
-             };
-
-             return vec![App {
-                 name,
-                 exec_path: exec,
-                 icon: Some("accessories-dictionary".to_string()),
-                 is_running: false,
-                 is_favorite: false,
-             }];
-        }
-
-        if let Some(ai_query) = query.strip_prefix("? ") {
-             let prompt = ai_query.trim();
-             if prompt.is_empty() { return vec![]; }
-
-             return vec![App {
-                 name: format!("Ask AI: {}", prompt),
-                 exec_path: format!("internal:ai:{}", prompt),
-                 icon: Some("system-search".to_string()), // Or a brain icon if available
-                 is_running: false,
-                 is_favorite: false,
-             }];
-        }
-
-        if let Some(m_query) = query.strip_prefix("m ") {
-             let name = m_query.trim();
-             if let Some(_mac) = self.macros.get(name) {
-                 return vec![App {
-                     name: format!("Macro: {}", name),
-                     // Encode the macro execution as a special internal command
-                     // Or just return it. The executor needs to know how to handle it.
-                     // Let's use internal:macro:name
-                     exec_path: format!("internal:macro:{}", name),
-                     icon: Some("system-run".to_string()),
-                     is_running: false,
-                     is_favorite: false,
-                 }];
-             }
-             // Show all macros matching
-             let all = self.macros.get_all();
-             return all.into_iter()
-                 .filter(|m| m.name.contains(name))
-                 .map(|m| App {
-                     name: format!("Macro: {}", m.name),
-                     exec_path: format!("internal:macro:{}", m.name),
-                     icon: Some("system-run".to_string()),
-                     is_running: false,
-                     is_favorite: false,
-                 })
-                 .collect();
-        }
 
-        if let Some(sys_query) = query.strip_prefix("! ") {
-            let action = sys_query.trim();
-            // predefined actions
-            let actions = vec![
-                "suspend", "reboot", "poweroff", "lock", "hibernate",
-                "mute", "mute_mic", "mute_all",
-                "toggle_night_light", "toggle_dark_mode", "toggle_dnd"
-            ];
-            return actions.into_iter()
-                .filter(|a| a.starts_with(action))
-                .map(|a| App {
-                    name: format!("System: {}", a),
-                    exec_path: format!("internal:system:{}", a),
-                    icon: Some("system-shutdown".to_string()),
-                    is_running: false,
-                    is_favorite: false,
-                }).collect();
-        }
+            // 2. Apps
+            let mut apps = self.app_repo.find_apps();
+            self.process_monitor.update_app_status(&mut apps);
+            for app in apps {
+                let app_score = app_match_score(&matcher, &app, query);
+                let app_result = SearchResult::from(app);
 
-        if let Some(t_query) = query.strip_prefix("t ") {
-            let action = t_query.trim();
-            let mut results = vec![];
-            
-            // Check status for controls
-            let (_, is_active) = self.time.get_status();
-            if is_active && !action.is_empty() {
-                if action == "stop" || "stop".starts_with(action) {
-                     results.push(App {
-                         name: "Stop Timer".to_string(),
-                         exec_path: "internal:time:stop".to_string(),
-                         icon: Some("media-playback-stop".to_string()),
-                         is_running: false,
-                         is_favorite: false,
-                     });
-                }
-                if action == "pause" || "pause".starts_with(action) || action == "resume" || "resume".starts_with(action) {
-                     results.push(App {
-                         name: "Pause/Resume".to_string(),
-                         exec_path: "internal:time:pause".to_string(),
-                         icon: Some("media-playback-pause".to_string()),
-                         is_running: false,
-                         is_favorite: false,
-                     });
+                // Desktop actions ("Firefox › New Private Window") only show up when
+                // the query matches more than the app name alone
+                for sub in &app_result.sub_actions {
+                    let label = format!("{} › {}", app_result.name, sub.name);
+                    let Some(score) = matcher.fuzzy_match(&label, query).map(|s| s as f64) else { continue };
+                    if app_score.is_none_or(|app_score| score > app_score) {
+                        let mut row = sub.clone();
+                        row.name = label;
+                        add(Category::App, score * DESKTOP_ACTION_FACTOR, row);
+                    }
                 }
-                if action == "restart" || "restart".starts_with(action) {
-                     results.push(App {
-                         name: "Restart".to_string(),
-                         exec_path: "internal:time:restart".to_string(),
-                         icon: Some("view-refresh".to_string()),
-                         is_running: false,
-                         is_favorite: false,
-                     });
+
+                if let Some(score) = app_score {
+                    add(Category::App, score, app_result);
                 }
             }
 
-            // t timer 10 (10 mins)
-            if action.starts_with("timer ") {
-                 let duration_str = action.strip_prefix("timer ").unwrap_or("").trim();
-                 let duration = if let Ok(val) = duration_str.parse::<u64>() {
-                     val
-                 } else {
-                     // Parse 10m, 1h? Simplified to minutes for now if number
-                     if let Some(mins) = duration_str.strip_suffix("m") {
-                         mins.trim().parse::<u64>().unwrap_or(0)
-                     } else {
-                         duration_str.parse::<u64>().unwrap_or(0)
-                     }
-                 };
-                 
-                 if duration > 0 {
-                     results.push(App {
-                         name: format!("Start Timer: {} minutes", duration),
-                         exec_path: format!("internal:time:timer:{}", duration * 60), // secs
-                         icon: Some("alarm-timer".to_string()), // icon?
-                         is_running: false,
-                         is_favorite: false,
-                     });
-                 }
-            } else if action == "pomodoro" || "pomodoro".starts_with(action) {
-                 results.push(App {
-                     name: "Start Pomodoro (25m)".to_string(),
-                     exec_path: "internal:time:pomodoro".to_string(),
-                     icon: Some("alarm-timer".to_string()),
-                     is_running: false,
-                     is_favorite: false,
-                 });
-            } else if action == "stopwatch" || "stopwatch".starts_with(action) {
-                 results.push(App {
-                     name: "Start Stopwatch".to_string(),
-                     exec_path: "internal:time:stopwatch".to_string(),
-                     icon: Some("alarm-timer".to_string()),
-                     is_running: false,
-                     is_favorite: false,
-                 });
+            // 3. Open windows
+            for w in self.window_repo.get_open_windows() {
+                let name = format!("{} - {}", w.app_name, w.title);
+                if let Some(score) = matcher.fuzzy_match(&name, query) {
+                    let mut result = SearchResult::new(name, "preferences-system-windows", Action::FocusWindow(w.id));
+                    result.is_running = true;
+                    add(Category::Window, score as f64, result);
+                }
             }
-            
-            // Allow just typing "t" to see options
-            if action.is_empty() {
-                 if is_active {
-                     results.push(App { name: "Pause/Resume".to_string(), exec_path: "internal:time:pause".to_string(), icon: Some("media-playback-pause".to_string()), is_running: false, is_favorite: false });
-                     results.push(App { name: "Stop".to_string(), exec_path: "internal:time:stop".to_string(), icon: Some("media-playback-stop".to_string()), is_running: false, is_favorite: false });
-                     results.push(App { name: "Restart".to_string(), exec_path: "internal:time:restart".to_string(), icon: Some("view-refresh".to_string()), is_running: false, is_favorite: false });
-                 }
-                 results.push(App { name: "Pomodoro".to_string(), exec_path: "internal:time:pomodoro".to_string(), icon: Some("alarm-timer".to_string()), is_running: false, is_favorite: false });
-                 results.push(App { name: "Stopwatch".to_string(), exec_path: "internal:time:stopwatch".to_string(), icon: Some("alarm-timer".to_string()), is_running: false, is_favorite: false });
-                 results.push(App { name: "Timer (e.g. 't timer 10')".to_string(), exec_path: "internal:time:help".to_string(), icon: Some("alarm-timer".to_string()), is_running: false, is_favorite: false });
+
+            // 4. Shortcuts
+            for (key, cmd) in self.shortcuts.get_all() {
+                if let Some(score) = matcher.fuzzy_match(&key, query) {
+                    add(Category::Shortcut, score as f64, SearchResult::new(key, "emblem-symbolic-link", Action::Shell(cmd)));
+                }
             }
 
-            return results;
+            // 5. Macros
+            for m in self.macros.get_all() {
+                if let Some(score) = matcher.fuzzy_match(&m.name, query) {
+                    add(Category::Macro, score as f64, SearchResult::new(m.name.clone(), "system-run", Action::RunMacro(m.name)));
+                }
+            }
         }
-        
-        if let Some(l_query) = query.strip_prefix("l ") {
-             // internal settings
-             let items = vec![
-                App { 
-                    name: "About Launch".to_string(), 
-                    exec_path: "internal:about".to_string(), 
-                    icon: Some("help-about".to_string()), 
-                    is_running: false,
-                    is_favorite: false, 
-                },
-                App { 
-                    name: "Quit".to_string(), 
-                    exec_path: "internal:quit".to_string(), 
-                    icon: Some("application-exit".to_string()), 
-                    is_running: false,
-                    is_favorite: false, 
-                },
-                App { 
-                    name: "Settings".to_string(), 
-                    exec_path: "internal:settings".to_string(), 
-                    icon: Some("preferences-system".to_string()), 
-                    is_running: false,
-                    is_favorite: false, 
-                },
-            ];
-            return items.into_iter()
-                .filter(|app| app.name.to_lowercase().contains(&l_query.trim().to_lowercase()))
+
+        // 6. Indexed files, ranked by the index on name, depth and modification
+        // time, then by how often they were opened
+        if file_query.has_filters() || file_query.text.len() >= MIN_FILE_QUERY_LEN {
+            let mut files: Vec<_> = self.indexer.search(&file_query, FILE_CANDIDATES).into_iter()
+                .map(|found| {
+                    let bonus = Action::OpenPath(found.path.clone()).usage_key().map_or(0.0, |key| usage.bonus(&key));
+                    (found.score * weight(Category::File) + bonus, found)
+                })
                 .collect();
+            files.sort_by(|a, b| b.0.total_cmp(&a.0));
+            files.truncate(MAX_FILE_RESULTS);
+            for (_, found) in files {
+                let file_name = std::path::Path::new(&found.path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| found.path.clone());
+                let icon = self.mime.icon_name(&self.mime.mime_type(&found.path));
+                add(Category::File, found.score, SearchResult::new(file_name, &icon, Action::OpenPath(found.path)));
+            }
         }
 
-        // App Search (Default)
-        let mut apps = self.app_repo.find_apps();
-        self.process_monitor.update_app_status(&mut apps);
-        
-        let matcher = SkimMatcherV2::default();
-        let mut scored_apps: Vec<(i64, App)> = apps.into_iter().filter_map(|app| {
-            let score = matcher.fuzzy_match(&app.name, query)?;
-            Some((score, app))
-        }).collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().map(|(_, result)| result).collect()
+    }
+}
 
+/// Best fuzzy score over an app's name, generic name and keywords. Matches on
+/// the secondary fields count for less, so "browser" finds Firefox without
+/// outranking an app actually called "Browser".
+fn app_match_score(matcher: &SkimMatcherV2, app: &App, query: &str) -> Option<f64> {
+    let name = matcher.fuzzy_match(&app.name, query).map(|s| s as f64);
+    let generic = app.generic_name.as_deref()
+        .and_then(|g| matcher.fuzzy_match(g, query))
+        .map(|s| s as f64 * GENERIC_NAME_FACTOR);
+    let keyword = app.keywords.iter()
+        .filter_map(|k| matcher.fuzzy_match(k, query))
+        .max()
+        .map(|s| s as f64 * KEYWORD_FACTOR);
+    [name, generic, keyword].into_iter().flatten().reduce(f64::max)
+}
 
-        scored_apps.sort_by(|a, b| b.0.cmp(&a.0));
-        scored_apps.into_iter().map(|(_, app)| app).collect()
-    }
+/// Cheap check so plain words are not sent to the calculator.
+fn looks_like_math(query: &str) -> bool {
+    let query = query.trim();
+    query.chars().any(|c| c.is_ascii_digit())
+        && (query.contains(['+', '-', '*', '/', '^', '%', '(']) || query.starts_with('\\'))
+}
+
+/// Excerpt numbers `[n]` referred to in an answer, among the `count` given.
+fn cited_numbers(answer: &str, count: usize) -> Vec<usize> {
+    let mut cited: Vec<usize> = answer.split('[').skip(1)
+        .filter_map(|rest| rest.split_once(']'))
+        .flat_map(|(inside, _)| inside.split(',').map(|n| n.trim().parse::<usize>()).collect::<Vec<_>>())
+        .filter_map(Result::ok)
+        .filter(|n| (1..=count).contains(n))
+        .collect();
+    cited.sort_unstable();
+    cited.dedup();
+    cited
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::test_utils::*;
+    use crate::domain::model::{ContentMatch, FileMatch, IndexedFile, TimerAction, ToolCall, ToolSpec};
 
     #[test]
     fn test_routes_dictionary() {
@@ -626,7 +723,7 @@ mod tests {
         let results = omnibar.search("d rust");
         assert_eq!(results.len(), 1);
         assert!(results[0].name.contains("Awesome language"));
-        assert!(results[0].exec_path.contains("google.com"));
+        assert_eq!(results[0].action, Action::OpenUrl("https://google.com/search?q=define+rust".to_string()));
 
         // Match not found (Fallback)
         let results_fallback = omnibar.search("d unknown");
@@ -638,8 +735,18 @@ mod tests {
     fn test_routes_terminal() {
         let omnibar = create_omnibar();
         let results = omnibar.search("x echo hello");
+        assert_eq!(results[0].action, Action::RunInTerminal("echo hello".to_string()));
+        assert_eq!(results[1].action, Action::Shell("echo hello".to_string()));
+        assert_eq!(omnibar.mode_prefix("x echo hello"), "x ");
+        assert_eq!(omnibar.mode_prefix("echo hello"), "");
+    }
+
+    #[test]
+    fn test_routes_content_search() {
+        let omnibar = create_omnibar();
+        let results = omnibar.search("g oat milk");
         assert_eq!(results.len(), 1);
-        assert!(results[0].name.contains("Execute: echo hello"));
+        assert_eq!(results[0].action, Action::OpenPath("/home/user/notes/todo.md".to_string()));
     }
 
     #[test]
@@ -647,7 +754,7 @@ mod tests {
         let omnibar = create_omnibar();
         let results = omnibar.search("ss term");
         assert_eq!(results.len(), 1);
-        assert_eq!(results[0].exec_path, "gnome-terminal");
+        assert_eq!(results[0].action, Action::Shell("gnome-terminal".to_string()));
     }
 
     #[test]
@@ -656,7 +763,7 @@ mod tests {
         let results = omnibar.search("m test");
         assert_eq!(results.len(), 1);
         assert!(results[0].name.contains("Macro: test"));
-        assert_eq!(results[0].exec_path, "internal:macro:test");
+        assert_eq!(results[0].action, Action::RunMacro("test".to_string()));
     }
 
     #[test]
@@ -683,6 +790,326 @@ mod tests {
         // Check for workspace and app info; note check for screen label if needed
         assert!(results[0].name.contains("[WS 2]"));
         assert!(results[0].name.contains("Gnome-terminal - Terminal"));
-        assert_eq!(results[0].exec_path, "internal:window:0x2");
+        assert_eq!(results[0].action, Action::FocusWindow("0x2".to_string()));
+    }
+
+    #[test]
+    fn test_ai_prompt_is_not_reinterpreted() {
+        let omnibar = create_omnibar();
+        let results = omnibar.search("? what does \"internal:quit\" do?");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].action, Action::AskAi("what does \"internal:quit\" do?".to_string()));
+    }
+
+    #[test]
+    fn test_dictionary_url_is_encoded() {
+        let omnibar = create_omnibar();
+        let results = omnibar.search("d a\"b&c");
+        assert_eq!(results[0].action, Action::OpenUrl("https://google.com/search?q=define+a%22b%26c".to_string()));
+    }
+
+    #[test]
+    fn test_blended_search_labels_sources() {
+        let omnibar = create_omnibar();
+        let results = omnibar.search("term");
+        let window = results.iter().find(|r| r.action == Action::FocusWindow("0x2".to_string())).unwrap();
+        assert_eq!(window.category, Some(Category::Window));
+        let shortcut = results.iter().find(|r| r.action == Action::Shell("gnome-terminal".to_string())).unwrap();
+        assert_eq!(shortcut.category, Some(Category::Shortcut));
+    }
+
+    #[test]
+    fn test_blended_search_calculates_inline() {
+        let omnibar = create_omnibar();
+        let results = omnibar.search("1+1");
+        assert_eq!(results[0].action, Action::CopyText("2".to_string()));
+        assert_eq!(results[0].category, Some(Category::Calculator));
+
+        // Plain words never reach the calculator
+        assert!(!looks_like_math("terminal"));
+    }
+
+    #[test]
+    fn test_blended_search_includes_files() {
+        let omnibar = create_omnibar();
+        let results = omnibar.search("todo");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "todo.md");
+        assert_eq!(results[0].action, Action::OpenPath("/home/user/notes/todo.md".to_string()));
+        assert_eq!(results[0].category, Some(Category::File));
+    }
+
+    #[test]
+    fn test_filters_restrict_blended_search_to_files() {
+        let omnibar = create_omnibar();
+        let results = omnibar.search("ext:md");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "todo.md");
+        // "term" would otherwise find the terminal window and shortcut
+        assert!(omnibar.search("term ext:md").is_empty());
+    }
+
+    /// Equally good matches for "report", in the index's own order.
+    struct Reports;
+
+    impl IFileIndexer for Reports {
+        fn search(&self, _query: &FileQuery, limit: usize) -> Vec<FileMatch> {
+            (0..50).take(limit)
+                .map(|i| FileMatch { path: format!("/home/user/report-{:02}.pdf", i), score: 100.0 })
+                .collect()
+        }
+        fn search_content(&self, _term: &str, _limit: usize) -> Vec<ContentMatch> { vec![] }
+        fn text_files(&self) -> Vec<IndexedFile> { vec![] }
+        fn start(&self) {}
+    }
+
+    #[test]
+    fn test_opened_file_outranks_equal_files() {
+        let omnibar = create_omnibar_with_indexer(Arc::new(Reports));
+        assert_eq!(omnibar.search("report")[0].name, "report-00.pdf");
+
+        // Further down the index's list than the results shown
+        let opened = Action::OpenPath("/home/user/report-30.pdf".to_string());
+        omnibar.record_usage(&opened, "");
+        let results = omnibar.search("report");
+        assert_eq!(results[0].action, opened);
+        assert_eq!(results.len(), MAX_FILE_RESULTS);
+    }
+
+    #[test]
+    fn test_ai_answer_cites_retrieved_passages() {
+        let omnibar = create_omnibar();
+        let mut tokens = vec![];
+        let answer = omnibar.query_ai("what do I need to buy?", &mut |token| tokens.push(token.to_string())).unwrap();
+        assert_eq!(answer.text, "AI response");
+        assert_eq!(tokens, ["AI ", "response"]);
+        // Finished answers can't be cancelled
+        assert!(!omnibar.cancel_ai());
+        // Nothing cited: every excerpt is a source
+        assert_eq!(answer.citations, vec![Citation {
+            number: 1,
+            path: "/home/user/notes/todo.md".to_string(),
+            start_line: 1,
+            end_line: 3,
+        }]);
+        let results = omnibar.citation_results(&answer.citations);
+        assert_eq!(results[0].name, "[1] todo.md, lines 1–3");
+        assert_eq!(results[0].action, Action::OpenPath("/home/user/notes/todo.md".to_string()));
+
+        assert_eq!(cited_numbers("Milk [2] and eggs [1, 3], see [9] or [x]", 3), vec![1, 2, 3]);
+        assert!(cited_numbers("No sources", 3).is_empty());
+    }
+
+    #[test]
+    fn test_chat_keeps_and_saves_turns() {
+        let omnibar = create_omnibar();
+        assert!(!omnibar.chat_open());
+        omnibar.query_ai("what do I need to buy?", &mut |_| {}).unwrap();
+        omnibar.query_ai("and after that?", &mut |_| {}).unwrap();
+        assert!(omnibar.chat_open());
+
+        let chats = omnibar.chats.sessions();
+        assert_eq!(chats.len(), 1);
+        assert_eq!(chats[0].title, "what do I need to buy?");
+        let roles: Vec<ChatRole> = chats[0].messages.iter().map(|m| m.role).collect();
+        assert_eq!(roles, [ChatRole::User, ChatRole::Assistant, ChatRole::User, ChatRole::Assistant]);
+        assert_eq!(chats[0].messages[1].citations.len(), 1);
+        assert_eq!(omnibar.get_overview().ai[1].action, Action::ResumeChat(chats[0].id.clone()));
+
+        // Closed chats can be reopened
+        omnibar.end_chat();
+        assert!(!omnibar.chat_open());
+        assert_eq!(omnibar.resume_chat(&chats[0].id), Some(chats[0].clone()));
+        assert!(omnibar.chat_open());
+    }
+
+    /// Streams until cancelled when asked the first question, remembering
+    /// the messages of each request.
+    #[derive(Default)]
+    struct InterruptedLLM {
+        requests: Mutex<Vec<Vec<ChatMessage>>>,
+        streaming: AtomicBool,
+    }
+    impl ILLMService for InterruptedLLM {
+        fn chat(&self, messages: &[ChatMessage], _tools: &[ToolSpec], on_token: &mut dyn FnMut(&str), cancel: &AtomicBool) -> Result<ChatMessage, ChatError> {
+            let first = self.requests.lock().unwrap().is_empty();
+            self.requests.lock().unwrap().push(messages.to_vec());
+            if first {
+                on_token("Partial");
+                self.streaming.store(true, Ordering::Relaxed);
+                while !cancel.load(Ordering::Relaxed) {
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                }
+                // Let a follow-up that didn't wait overtake the save
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                return Ok(ChatMessage::new(ChatRole::Assistant, "Partial"));
+            }
+            Ok(ChatMessage::new(ChatRole::Assistant, "Done"))
+        }
+        fn list_models(&self) -> Result<Vec<String>, String> { Ok(vec![]) }
+        fn pull_model(&self, _model: &str, _on_progress: Box<dyn Fn(f64) + Send>) -> Result<(), String> { Ok(()) }
+        fn delete_model(&self, _model: &str) -> Result<(), String> { Ok(()) }
+        fn set_model(&self, _model: &str) {}
+        fn embed(&self, _model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> { Ok(texts.iter().map(|_| vec![1.0]).collect()) }
+    }
+
+    #[test]
+    fn test_follow_up_waits_for_cancelled_turn() {
+        let llm = Arc::new(InterruptedLLM::default());
+        let omnibar = Arc::new(create_omnibar_with_llm(llm.clone()));
+        let first = {
+            let omnibar = omnibar.clone();
+            std::thread::spawn(move || omnibar.query_ai("first question", &mut |_| {}))
+        };
+        while !llm.streaming.load(Ordering::Relaxed) {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let answer = omnibar.query_ai("second question", &mut |_| {}).unwrap();
+        assert_eq!(answer.text, "Done");
+        assert_eq!(first.join().unwrap().unwrap().text, "Partial");
+
+        // The follow-up was asked with the interrupted turn
+        let requests = llm.requests.lock().unwrap();
+        let earlier: Vec<&str> = requests[1].iter().map(|m| m.content.as_str()).take(2).collect();
+        assert_eq!(earlier, ["first question", "Partial"]);
+        let saved: Vec<String> = omnibar.chats.sessions()[0].messages.iter().map(|m| m.content.clone()).collect();
+        assert_eq!(saved, ["first question", "Partial", "second question", "Done"]);
+    }
+
+    #[test]
+    fn test_enter_in_chat_opens_selected_source() {
+        let omnibar = create_omnibar();
+        let answer = omnibar.query_ai("what do I need to buy?", &mut |_| {}).unwrap();
+        let source = &omnibar.citation_results(&answer.citations)[0];
+        assert_eq!(omnibar.chat_enter("? ", Some(source)), ChatEnter::Activate(source.action.clone()));
+
+        // Chat rows do nothing, so Enter sends the follow-up
+        let answer_row = SearchResult::new(answer.text, "dialog-information", Action::None);
+        assert_eq!(omnibar.chat_enter("? and then?", Some(&answer_row)), ChatEnter::Ask("and then?".to_string()));
+        assert_eq!(omnibar.chat_enter("? ", None), ChatEnter::Nothing);
+    }
+
+    /// Calls the calculator and proposes a timer, then answers with what it got back.
+    struct ToolCallingLLM;
+    impl ILLMService for ToolCallingLLM {
+        fn chat(&self, messages: &[ChatMessage], tools: &[ToolSpec], on_token: &mut dyn FnMut(&str), _cancel: &AtomicBool) -> Result<ChatMessage, ChatError> {
+            let results: Vec<&str> = messages.iter()
+                .filter(|m| m.role == ChatRole::Tool)
+                .map(|m| m.content.as_str())
+                .collect();
+            if results.is_empty() {
+                assert!(tools.iter().any(|t| t.name == "start_timer"));
+                let call = |id: &str, name: &str, arguments| ToolCall { id: id.to_string(), name: name.to_string(), arguments };
+                let calls = vec![
+                    call("a", "calculate", serde_json::json!({ "expression": "1+1" })),
+                    call("b", "start_timer", serde_json::json!({ "minutes": 2 })),
+                ];
+                on_token("Let me check.");
+                return Ok(ChatMessage { tool_calls: calls, ..ChatMessage::new(ChatRole::Assistant, "Let me check.") });
+            }
+            on_token(results[0]);
+            Ok(ChatMessage::new(ChatRole::Assistant, results[0]))
+        }
+        fn list_models(&self) -> Result<Vec<String>, String> { Ok(vec![]) }
+        fn pull_model(&self, _model: &str, _on_progress: Box<dyn Fn(f64) + Send>) -> Result<(), String> { Ok(()) }
+        fn delete_model(&self, _model: &str) -> Result<(), String> { Ok(()) }
+        fn set_model(&self, _model: &str) {}
+        fn embed(&self, _model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> { Ok(texts.iter().map(|_| vec![1.0]).collect()) }
+    }
+
+    #[test]
+    fn test_ai_tool_calls_answer_or_propose() {
+        let omnibar = create_omnibar_with_llm(Arc::new(ToolCallingLLM));
+        let mut streamed = String::new();
+        let answer = omnibar.query_ai("1+1, then a 2 minute timer", &mut |token| streamed.push_str(token)).unwrap();
+        assert_eq!(answer.text, "Let me check.\n\n2");
+        assert_eq!(streamed, answer.text);
+        // The timer is only offered, never started by the model
+        let actions: Vec<Action> = answer.proposals.iter().map(|p| p.action.clone()).collect();
+        assert_eq!(actions, [Action::Timer(TimerAction::Start { secs: 120 })]);
+        // Selecting it and pressing Enter confirms it
+        assert_eq!(omnibar.chat_enter("? ", answer.proposals.first()), ChatEnter::Activate(actions[0].clone()));
+
+        // Only the question and the final answer are kept
+        let chat = &omnibar.chats.sessions()[0];
+        let roles: Vec<ChatRole> = chat.messages.iter().map(|m| m.role).collect();
+        assert_eq!(roles, [ChatRole::User, ChatRole::Assistant]);
+    }
+
+    #[test]
+    fn test_learns_pick_for_query() {
+        let omnibar = create_omnibar();
+        let results = omnibar.search("term");
+        assert_eq!(results[0].category, Some(Category::Window));
+
+        // Picking the shortcut for "term" moves it to the top next time
+        let shortcut = Action::Shell("gnome-terminal".to_string());
+        omnibar.record_usage(&shortcut, "term");
+        assert_eq!(omnibar.search("term")[0].action, shortcut);
+    }
+
+    #[test]
+    fn test_app_matches_on_keywords() {
+        let matcher = SkimMatcherV2::default();
+        let app = App {
+            name: "Firefox".to_string(),
+            generic_name: Some("Web Browser".to_string()),
+            keywords: vec!["Internet".to_string()],
+            ..Default::default()
+        };
+        assert!(app_match_score(&matcher, &app, "internet").is_some());
+        assert!(app_match_score(&matcher, &app, "browser").is_some());
+        let named = App { name: "Browser".to_string(), ..Default::default() };
+        assert!(app_match_score(&matcher, &named, "browser").unwrap() > app_match_score(&matcher, &app, "browser").unwrap());
+        assert!(app_match_score(&matcher, &app, "zzz").is_none());
+    }
+
+    #[test]
+    fn test_desktop_actions_are_searchable() {
+        let omnibar = create_omnibar();
+        let results = omnibar.search("firefox private");
+        assert_eq!(results[0].name, "Firefox › New Private Window");
+        assert_eq!(results[0].action, Action::Launch("firefox --private-window".to_string()));
+
+        // The plain app name brings up the app with its actions as sub-actions
+        let results = omnibar.search("firefox");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].sub_actions.len(), 1);
+    }
+
+    #[test]
+    fn test_sub_actions_per_result_type() {
+        let omnibar = create_omnibar();
+        let names = |result: &SearchResult| -> Vec<String> {
+            omnibar.sub_actions(result).into_iter().map(|r| r.name).collect()
+        };
+
+        let file = SearchResult::new("todo.md", "text-x-generic", Action::OpenPath("/home/user/notes/todo.md".to_string()));
+        assert_eq!(names(&file), ["Open", "Open with Vim", "Show in Folder", "Copy Path", "Open Terminal Here", "Rename…", "Move to Trash", "New Folder…"]);
+        let file_actions = omnibar.sub_actions(&file);
+        assert_eq!(file_actions[1].action, Action::OpenWith { path: "/home/user/notes/todo.md".to_string(), desktop_id: "vim.desktop".to_string() });
+        assert_eq!(file_actions[4].action, Action::OpenTerminal("/home/user/notes".to_string()));
+
+        let window = &omnibar.search("w chrome")[0];
+        assert_eq!(names(window)[..2], ["Focus", "Close"]);
+        assert!(names(window).contains(&"Move to Workspace 2".to_string()));
+        assert!(!names(window).contains(&"Move to Workspace 1".to_string()));
+
+        let shortcut = &omnibar.search("ss term")[0];
+        assert_eq!(names(shortcut), ["Run", "Edit", "Duplicate", "Delete…"]);
+        assert_eq!(omnibar.sub_actions(shortcut)[1].action, Action::Internal(InternalCommand::EditShortcut("term".to_string())));
+        // Deleting asks first
+        assert_eq!(omnibar.sub_actions(shortcut)[3].action, Action::Internal(InternalCommand::DeleteShortcut("term".to_string())));
+
+        let mac = &omnibar.search("m test")[0];
+        assert_eq!(names(mac), ["Run", "Edit", "Duplicate", "Delete…"]);
+        assert_eq!(omnibar.sub_actions(mac)[3].action, Action::Internal(InternalCommand::DeleteMacro("test".to_string())));
+        // What the confirmation runs
+        assert_eq!(omnibar.activate(&ShortcutProvider::delete_action("term")), Some(Ok(())));
+        assert_eq!(omnibar.activate(&MacroProvider::delete_action("test")), Some(Ok(())));
+
+        // Terminal commands have no secondary actions
+        assert!(names(&omnibar.search("x ls")[0]).is_empty());
     }
 }
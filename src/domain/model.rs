@@ -8,13 +8,784 @@ pub struct Window {
     pub screen: i32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct App {
     pub name: String,
+    /// `command` as a command line; identifies the app in launch results
+    /// and history.
+    pub exec_path: String,
+    /// What launching runs: the expanded `Exec` argv, in `Path` if set.
+    pub command: CommandSpec,
+    pub icon: Option<String>,
+    pub is_running: bool,
+    pub is_favorite: bool,
+    /// Extra searchable text from the desktop entry.
+    pub generic_name: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    /// Path of the `.desktop` file the app was read from.
+    pub desktop_file: Option<String>,
+    /// Desktop file ID, e.g. `org.gnome.Nautilus.desktop` or `kde-okular.desktop`.
+    pub desktop_id: Option<String>,
+    /// `[Desktop Action ...]` jump-list entries, in `Actions=` order.
+    pub actions: Vec<DesktopAction>,
+    /// `StartupWMClass` from the desktop entry: the WM class its windows use.
+    pub startup_wm_class: Option<String>,
+    /// `Terminal=true`: the command has to run inside a terminal emulator.
+    pub terminal: bool,
+}
+
+impl App {
+    /// Executable name from the launch command, skipping `env` and its
+    /// `VAR=value` arguments (`env GDK_BACKEND=x11 /usr/bin/foo %u` -> `foo`).
+    /// `None` when the command is a shell script rather than a program.
+    pub fn program_name(&self) -> Option<String> {
+        let argv = self.command.argv();
+        let mut args = argv.iter().map(String::as_str);
+        let mut program = args.next()?;
+        if program == "env" {
+            program = args.find(|a| !a.contains('=') && !a.starts_with('-'))?;
+        }
+        if program == "sh" && args.next() == Some("-c") {
+            return None;
+        }
+        let name = program.rsplit('/').next().unwrap_or(program);
+        if name.is_empty() { None } else { Some(name.to_string()) }
+    }
+
+    /// The command behind `exec_path`, which is either the app's own or
+    /// one of its desktop actions'.
+    pub fn command_for(&self, exec_path: &str) -> Option<&CommandSpec> {
+        if self.exec_path == exec_path {
+            return Some(&self.command);
+        }
+        self.actions.iter().find(|a| a.exec_path == exec_path).map(|a| &a.command)
+    }
+
+    /// Desktop file ID without the `.desktop` suffix, e.g. `org.gnome.Nautilus`.
+    pub fn app_id(&self) -> Option<&str> {
+        self.desktop_id.as_deref().map(|id| id.trim_end_matches(".desktop"))
+    }
+
+    /// Whether windows with this WM class belong to the app. Compares against
+    /// `StartupWMClass`, the desktop file ID (and its last reverse-DNS segment)
+    /// and the program name, ignoring case.
+    pub fn matches_window_class(&self, class: &str) -> bool {
+        let class = class.to_lowercase();
+        if class.is_empty() {
+            return false;
+        }
+        if let Some(wm_class) = &self.startup_wm_class {
+            // An explicit class is authoritative
+            return wm_class.to_lowercase() == class;
+        }
+        if let Some(id) = self.app_id() {
+            let id = id.to_lowercase();
+            if id == class || id.rsplit('.').next() == Some(class.as_str()) {
+                return true;
+            }
+        }
+        self.program_name().is_some_and(|p| p.to_lowercase() == class)
+    }
+}
+
+/// An additional way to launch an app, such as Firefox's "New Private Window".
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DesktopAction {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    /// Command line, like `App::exec_path`.
     pub exec_path: String,
+    pub command: CommandSpec,
+}
+
+/// A program started directly from its argument vector. Paths, URLs and other
+/// data passed in `args` are never seen by a shell.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CommandSpec {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub env: Vec<(String, String)>,
+    /// Written to the program's standard input, e.g. text for `xclip`.
+    pub stdin: Option<String>,
+}
+
+impl CommandSpec {
+    pub fn new<I, S>(program: &str, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            program: program.to_string(),
+            args: args.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Runs `script` with `sh -c`, passing `args` as `$1`, `$2`, ... so they
+    /// are never spliced into the script text.
+    pub fn script<I, S>(script: &str, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut argv = vec!["-c".to_string(), script.to_string(), "sh".to_string()];
+        argv.extend(args.into_iter().map(Into::into));
+        Self::new("sh", argv)
+    }
+
+    /// A command line the user typed themselves.
+    pub fn shell(command_line: &str) -> Self {
+        Self::new("sh", ["-c", command_line])
+    }
+
+    pub fn with_cwd(mut self, cwd: &str) -> Self {
+        self.cwd = Some(cwd.to_string());
+        self
+    }
+
+    pub fn with_stdin(mut self, input: &str) -> Self {
+        self.stdin = Some(input.to_string());
+        self
+    }
+
+    /// Program followed by its arguments.
+    pub fn argv(&self) -> Vec<String> {
+        std::iter::once(self.program.clone()).chain(self.args.iter().cloned()).collect()
+    }
+
+    /// Equivalent shell command line, for logs and messages.
+    pub fn command_line(&self) -> String {
+        self.argv().iter().map(|arg| shell_quote(arg)).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Quotes an argument only when the shell would otherwise split or expand it,
+/// so plain commands like `firefox` stay readable.
+pub fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// What happens when a search result is activated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Start an application by its `App::exec_path` (or a desktop action's).
+    Launch(String),
+    /// Run a shell command line (user-typed commands and shortcuts).
+    Shell(String),
+    /// Run a user-typed shell command line inside a terminal emulator.
+    RunInTerminal(String),
+    /// Open a file or directory with its default handler.
+    OpenPath(String),
+    /// Open a file with the application with this desktop file ID.
+    OpenWith { path: String, desktop_id: String },
+    /// Reveal a file in the file manager.
+    ShowInFolder(String),
+    /// Open a terminal in this directory.
+    OpenTerminal(String),
+    OpenUrl(String),
+    FocusWindow(String),
+    RunMacro(String),
+    Timer(TimerAction),
+    /// A power or desktop action understood by `ISystemPower` (e.g. "reboot").
+    System(String),
+    /// Ask the assistant, continuing the open chat. An empty prompt just
+    /// switches the launcher to AI mode.
+    AskAi(String),
+    /// Reopen the saved chat with this id, handled by the UI.
+    ResumeChat(String),
+    CopyText(String),
+    /// Launch's own commands, handled by the UI.
+    Internal(InternalCommand),
+    /// Replace the query after the mode prefix with this text (e.g. a
+    /// completed command line), handled by the UI.
+    Complete(String),
+    /// Handled by the search provider with this id.
+    Provider { id: String, payload: String },
+    /// Informational row with nothing to run.
+    None,
+}
+
+impl Action {
+    /// Identifies the target in the launch history, or `None` for actions
+    /// that aren't worth ranking (transient windows, copied text, timers).
+    pub fn usage_key(&self) -> Option<String> {
+        match self {
+            Action::Launch(cmd) => Some(format!("launch:{}", cmd)),
+            Action::Shell(cmd) => Some(format!("shell:{}", cmd)),
+            Action::RunInTerminal(cmd) => Some(format!("terminal:{}", cmd)),
+            Action::OpenPath(path) | Action::OpenWith { path, .. } => Some(format!("path:{}", path)),
+            Action::OpenUrl(url) => Some(format!("url:{}", url)),
+            Action::RunMacro(name) => Some(format!("macro:{}", name)),
+            Action::System(name) => Some(format!("system:{}", name)),
+            Action::Provider { id, payload } => Some(format!("{}:{}", id, payload)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerAction {
+    Start { secs: u64 },
+    Pomodoro,
+    Stopwatch,
+    TogglePause,
+    Stop,
+    Restart,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InternalCommand {
+    Settings,
+    About,
+    Quit,
+    /// Open the edit dialog of the shortcut with this key.
+    EditShortcut(String),
+    /// Open the edit dialog of the macro with this name.
+    EditMacro(String),
+    /// Ask for a new name for this file.
+    RenameFile(String),
+    /// Ask for the name of a folder to create in this directory.
+    NewFolder(String),
+    /// Ask before deleting the shortcut with this key.
+    DeleteShortcut(String),
+    /// Ask before deleting the macro with this name.
+    DeleteMacro(String),
+}
+
+/// Source of a row in the blended (prefix-less) search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    App,
+    Window,
+    Shortcut,
+    Macro,
+    File,
+    Calculator,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::App, Category::Window, Category::Shortcut,
+        Category::Macro, Category::File, Category::Calculator,
+    ];
+
+    /// Key used for this source in settings.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Category::App => "apps",
+            Category::Window => "windows",
+            Category::Shortcut => "shortcuts",
+            Category::Macro => "macros",
+            Category::File => "files",
+            Category::Calculator => "calculator",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::App => "Application",
+            Category::Window => "Window",
+            Category::Shortcut => "Shortcut",
+            Category::Macro => "Macro",
+            Category::File => "File",
+            Category::Calculator => "Calculator",
+        }
+    }
+
+    /// Multiplier applied to fuzzy scores when blending sources.
+    pub fn default_weight(&self) -> f64 {
+        match self {
+            Category::App => 1.0,
+            Category::Window => 0.9,
+            Category::Shortcut => 0.8,
+            Category::Macro => 0.8,
+            Category::File => 0.5,
+            Category::Calculator => 1.0,
+        }
+    }
+}
+
+/// A row in the result list or overview grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub name: String,
     pub icon: Option<String>,
+    pub action: Action,
     pub is_running: bool,
     pub is_favorite: bool,
+    /// Set on blended results so the UI can label where a row came from.
+    pub category: Option<Category>,
+    /// Secondary actions listed on Ctrl+Enter.
+    pub sub_actions: Vec<SearchResult>,
+    /// Dimmed text shown after the name (e.g. a file's size and age).
+    pub detail: Option<String>,
+}
+
+impl SearchResult {
+    pub fn new(name: impl Into<String>, icon: &str, action: Action) -> Self {
+        Self {
+            name: name.into(),
+            icon: Some(icon.to_string()),
+            action,
+            is_running: false,
+            is_favorite: false,
+            category: None,
+            sub_actions: Vec::new(),
+            detail: None,
+        }
+    }
+
+    pub fn with_category(mut self, category: Category) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl From<App> for SearchResult {
+    fn from(app: App) -> Self {
+        let sub_actions = app.actions.into_iter()
+            .map(|a| Self {
+                name: a.name,
+                icon: a.icon.or_else(|| app.icon.clone()),
+                action: Action::Launch(a.exec_path),
+                is_running: false,
+                is_favorite: false,
+                category: None,
+                sub_actions: Vec::new(),
+                detail: None,
+            })
+            .collect();
+        Self {
+            name: app.name,
+            icon: app.icon,
+            action: Action::Launch(app.exec_path),
+            is_running: app.is_running,
+            is_favorite: app.is_favorite,
+            category: None,
+            sub_actions,
+            detail: None,
+        }
+    }
+}
+
+/// One activation in the launch history.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UsageRecord {
+    /// `Action::usage_key` of what was launched.
+    pub key: String,
+    /// Text in the search entry at the time, empty when picked from the overview.
+    pub query: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+/// A directory entry with the metadata shown by the file browser.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileEntry {
+    pub name: String,
+    pub is_dir: bool,
+    /// Size in bytes, 0 for directories.
+    pub size: u64,
+    /// Last modification, in seconds since the Unix epoch.
+    pub modified: Option<u64>,
+}
+
+/// Order of the file browser's listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileSort {
+    #[default]
+    Name,
+    /// Most recently modified first.
+    Modified,
+    /// Largest first.
+    Size,
+}
+
+impl FileSort {
+    /// The next order, for cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            FileSort::Name => FileSort::Modified,
+            FileSort::Modified => FileSort::Size,
+            FileSort::Size => FileSort::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileSort::Name => "name",
+            FileSort::Modified => "modification time",
+            FileSort::Size => "size",
+        }
+    }
+}
+
+/// Where the file index looks and what it leaves out.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct IndexSettings {
+    /// Directories indexed recursively; `~` is the home directory.
+    pub roots: Vec<String>,
+    /// Gitignore-style globs skipped in every root, on top of `.gitignore` files.
+    pub ignore: Vec<String>,
+    /// Larger files are indexed by name only.
+    pub max_content_size: u64,
+}
+
+impl Default for IndexSettings {
+    fn default() -> Self {
+        Self {
+            roots: vec!["~".to_string()],
+            ignore: [".*", "node_modules", "target", "dist", "build", "venv", "__pycache__"]
+                .into_iter().map(str::to_string).collect(),
+            max_content_size: 512 * 1024,
+        }
+    }
+}
+
+/// A line of a file containing the searched text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentMatch {
+    pub path: String,
+    /// 1-based.
+    pub line_number: usize,
+    pub line: String,
+}
+
+/// An indexed file matching a search, with its ranking score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMatch {
+    pub path: String,
+    pub score: f64,
+}
+
+/// A file search: fuzzy text plus `ext:rs`, `in:~/work` and `modified:<7d`
+/// filters. Filters can be combined and repeated; anything else is text.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileQuery {
+    pub text: String,
+    /// Lowercase extensions without the dot; `ext:rs,toml` allows either.
+    pub extensions: Vec<String>,
+    /// Directories the file must be in, as typed (`~/work`, `/srv`), or a
+    /// folder name anywhere in the path (`in:src`).
+    pub within: Vec<String>,
+    /// Modified at most this many seconds ago (`modified:<7d`).
+    pub modified_within: Option<u64>,
+    /// Modified at least this many seconds ago (`modified:>1y`).
+    pub modified_before: Option<u64>,
+}
+
+impl FileQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = FileQuery::default();
+        let mut text = Vec::new();
+        for word in query.split_whitespace() {
+            let filter = word.split_once(':').filter(|(_, value)| !value.is_empty());
+            let handled = match filter {
+                Some(("ext", value)) => {
+                    parsed.extensions.extend(value.split(',')
+                        .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                        .filter(|ext| !ext.is_empty()));
+                    true
+                },
+                Some(("in", value)) => {
+                    parsed.within.push(value.trim_end_matches('/').to_string());
+                    true
+                },
+                Some(("modified", value)) => {
+                    if let Some(age) = value.strip_prefix('<').and_then(parse_age) {
+                        parsed.modified_within = Some(age);
+                        true
+                    } else if let Some(age) = value.strip_prefix('>').and_then(parse_age) {
+                        parsed.modified_before = Some(age);
+                        true
+                    } else {
+                        false
+                    }
+                },
+                _ => false,
+            };
+            if !handled {
+                text.push(word);
+            }
+        }
+        parsed.text = text.join(" ");
+        parsed
+    }
+
+    /// A query with just text, for callers that don't take filters.
+    pub fn text(text: &str) -> Self {
+        FileQuery { text: text.to_string(), ..Default::default() }
+    }
+
+    pub fn has_filters(&self) -> bool {
+        !self.extensions.is_empty() || !self.within.is_empty()
+            || self.modified_within.is_some() || self.modified_before.is_some()
+    }
+}
+
+/// Seconds in an age like `30m`, `12h`, `7d`, `2w` or `1y`.
+fn parse_age(age: &str) -> Option<u64> {
+    let split = age.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = age.split_at(split);
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        "y" => 365 * 86_400,
+        _ => return None,
+    };
+    number.parse::<u64>().ok().map(|n| n * unit_secs)
+}
+
+/// A file whose contents are in the file index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFile {
+    pub path: String,
+    /// Seconds since the Unix epoch.
+    pub modified: u64,
+}
+
+/// API an LLM server speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmProtocol {
+    #[default]
+    Ollama,
+    /// `/v1/chat/completions`, as served by llama.cpp, vLLM or LM Studio.
+    OpenAi,
+}
+
+/// An LLM server the assistant can use.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LlmBackend {
+    /// Shown in the AI settings.
+    pub name: String,
+    pub protocol: LlmProtocol,
+    /// Scheme, host and port, plus the `/v1` path for OpenAI-compatible servers.
+    pub base_url: String,
+    /// Sent as a bearer token.
+    pub api_key: Option<String>,
+    /// Longest wait for the server to send something, in seconds.
+    pub timeout_secs: u64,
+}
+
+impl Default for LlmBackend {
+    /// A local Ollama, started on demand.
+    fn default() -> Self {
+        Self {
+            name: "Ollama".to_string(),
+            protocol: LlmProtocol::Ollama,
+            base_url: "http://127.0.0.1:11434".to_string(),
+            api_key: None,
+            timeout_secs: 300,
+        }
+    }
+}
+
+impl LlmBackend {
+    /// Whether `base_url` points at this machine.
+    pub fn is_local(&self) -> bool {
+        let rest = self.base_url.split_once("://").map_or(self.base_url.as_str(), |(_, rest)| rest);
+        let authority = rest.split('/').next().unwrap_or_default();
+        let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        let host = match authority.strip_prefix('[') {
+            Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+            None => authority.split(':').next().unwrap_or_default(),
+        };
+        host.eq_ignore_ascii_case("localhost") || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+    }
+}
+
+/// Which files the AI assistant reads, and how many passages it is given.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RagSettings {
+    /// Name of the entry in `backends` computing the embeddings, regardless
+    /// of the backend chosen for chat. A local Ollama when unset.
+    pub embedding_backend: Option<String>,
+    /// Model computing the embeddings, served by `embedding_backend`.
+    pub embedding_model: String,
+    /// Allows an `embedding_backend` on another machine. Embedding uploads
+    /// the contents of every embedded file to it.
+    pub allow_remote_embeddings: bool,
+    /// Extensions of the indexed files that are embedded.
+    pub extensions: Vec<String>,
+    /// Passages retrieved for each question.
+    pub passages: usize,
+}
+
+impl Default for RagSettings {
+    fn default() -> Self {
+        Self {
+            embedding_backend: None,
+            embedding_model: "nomic-embed-text".to_string(),
+            allow_remote_embeddings: false,
+            extensions: ["md", "txt", "rs", "toml", "json", "yaml", "yml", "js", "ts", "py", "sh", "html", "css"]
+                .into_iter().map(str::to_string).collect(),
+            passages: 6,
+        }
+    }
+}
+
+/// Consecutive lines of a file, retrieved as context for a question.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Passage {
+    pub path: String,
+    /// 1-based, inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// A passage an AI answer refers to as `[number]`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Citation {
+    pub number: usize,
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl Citation {
+    /// `[2] notes.md, lines 10–40`
+    pub fn label(&self) -> String {
+        let name = self.path.rsplit('/').next().unwrap_or(&self.path);
+        format!("[{}] {}, lines {}–{}", self.number, name, self.start_line, self.end_line)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AiAnswer {
+    pub text: String,
+    pub citations: Vec<Citation>,
+    /// Actions the assistant suggested; they run only when the user picks them.
+    pub proposals: Vec<SearchResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+    /// The result of a tool call, answering the assistant.
+    Tool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+    /// Sources of an assistant message.
+    #[serde(default)]
+    pub citations: Vec<Citation>,
+    /// Tools an assistant message asks to call.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// The call a tool message answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    pub fn new(role: ChatRole, content: impl Into<String>) -> Self {
+        Self { role, content: content.into(), citations: vec![], tool_calls: vec![], tool_call_id: None }
+    }
+}
+
+/// Why the assistant gave no reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatError {
+    /// The model or server can't call functions; asking without tools works.
+    ToolsUnsupported(String),
+    Other(String),
+}
+
+impl std::fmt::Display for ChatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChatError::ToolsUnsupported(message) | ChatError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for ChatError {
+    fn from(message: String) -> Self {
+        ChatError::Other(message)
+    }
+}
+
+/// A launcher capability offered to the assistant as a function it can call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema of the arguments object.
+    pub parameters: serde_json::Value,
+}
+
+/// A function call requested by the assistant.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ToolCall {
+    /// Pairs the call with its result; servers that don't assign ids get generated ones.
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+/// A conversation with the assistant, saved after every answer.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChatSession {
+    pub id: String,
+    /// The first question, shortened.
+    pub title: String,
+    /// Seconds since the Unix epoch of the last message.
+    pub updated: u64,
+    pub messages: Vec<ChatMessage>,
+}
+
+/// An application registered to open a MIME type.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MimeHandler {
+    /// Desktop file ID, e.g. `org.gnome.TextEditor.desktop`.
+    pub desktop_id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    /// Needs a terminal (`Terminal=true`).
+    pub terminal: bool,
+}
+
+impl MimeHandler {
+    /// Desktop file ID without the `.desktop` suffix.
+    pub fn app_id(&self) -> &str {
+        self.desktop_id.trim_end_matches(".desktop")
+    }
+}
+
+/// A started command that exited with an error shortly after launch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchFailure {
+    pub command: String,
+    /// The start of its stderr, or its exit status when it printed nothing.
+    pub message: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
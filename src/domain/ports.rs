@@ -1,7 +1,12 @@
-use crate::domain::model::{App, Macro, Window};
+use crate::domain::model::{Action, App, ChatError, ChatMessage, ChatSession, CommandSpec, ContentMatch, FileEntry, FileMatch, FileQuery, IndexedFile, LaunchFailure, Macro, MimeHandler, Passage, SearchResult, ToolSpec, UsageRecord, Window};
+use std::sync::atomic::AtomicBool;
 
 pub trait IAppRepository {
     fn find_apps(&self) -> Vec<App>;
+    /// Directories whose changes mean `find_apps` would return something new.
+    fn watch_paths(&self) -> Vec<String> {
+        vec![]
+    }
 }
 
 use std::collections::HashMap;
@@ -12,13 +17,71 @@ pub trait IProcessMonitor {
 }
 
 pub trait ICommandExecutor {
-    fn execute(&self, cmd: &str);
+    /// Starts a shell command line with `sh -c` without waiting for it. Only
+    /// for commands the user wrote; anything else goes through `spawn`.
+    /// Fails only if it could not be started.
+    fn execute(&self, cmd: &str) -> Result<(), String>;
+
+    /// Starts a program directly from its argument vector, without a shell.
+    fn spawn(&self, spec: &CommandSpec) -> Result<(), String>;
+
+    /// Starts `spec` directly, and `fallback` instead if `spec` can't be
+    /// started or exits with an error. Returns once `spec` was started.
+    fn spawn_or(&self, spec: &CommandSpec, fallback: &CommandSpec) -> Result<(), String> {
+        self.spawn(spec).or_else(|_| self.spawn(fallback))
+    }
+
+    /// Starts an installed application. `app_id` is its desktop file ID
+    /// without `.desktop`, used to name the process group it runs in.
+    fn launch_app(&self, spec: &CommandSpec, _app_id: Option<&str>) -> Result<(), String> {
+        self.spawn(spec)
+    }
+
+    /// Commands that failed after `execute` returned, e.g. by exiting with an
+    /// error right after starting. Each failure is returned once.
+    fn take_failures(&self) -> Vec<LaunchFailure> {
+        vec![]
+    }
+}
+
+/// File types and the applications that open them, per the XDG MIME
+/// database and `mimeapps.list`.
+pub trait IMimeDatabase {
+    /// MIME type of a file, `inode/directory` for directories.
+    fn mime_type(&self, path: &str) -> String;
+    /// Themed icon name for a MIME type, e.g. `application-pdf`.
+    fn icon_name(&self, mime_type: &str) -> String;
+    /// Applications that can open the type, the default one first.
+    fn handlers(&self, mime_type: &str) -> Vec<MimeHandler>;
+    /// Command opening `path` with `handler`.
+    fn open_command(&self, handler: &MimeHandler, path: &str) -> Option<CommandSpec>;
+}
+
+/// The user's terminal emulator.
+pub trait ITerminalLauncher {
+    /// Command opening a terminal window in `cwd`, running `command` in it
+    /// when given (in the command's own `cwd` if it has one).
+    fn terminal_command(&self, command: Option<&CommandSpec>, cwd: Option<&str>) -> CommandSpec;
 }
 
 pub trait IFileSystem {
     fn list_dir(&self, path: &str) -> Vec<String>; // Returns raw names
     fn is_dir(&self, path: &str) -> bool;
     fn exists(&self, path: &str) -> bool;
+    /// Entries of a directory with their metadata, read in one pass.
+    fn read_dir(&self, path: &str) -> Vec<FileEntry> {
+        self.list_dir(path).into_iter()
+            .map(|name| {
+                let is_dir = self.is_dir(&format!("{}/{}", path.trim_end_matches('/'), name));
+                FileEntry { name, is_dir, ..Default::default() }
+            })
+            .collect()
+    }
+    /// Moves a file or directory to the user's trash.
+    fn trash(&self, path: &str) -> Result<(), String>;
+    /// Renames without replacing an existing file.
+    fn rename(&self, path: &str, new_path: &str) -> Result<(), String>;
+    fn create_dir(&self, path: &str) -> Result<(), String>;
 }
 
 pub trait ISystemPower {
@@ -44,16 +107,44 @@ pub trait IDictionaryService {
 }
 
 pub trait ILLMService {
-    fn query(&self, prompt: &str, context: Option<String>) -> Result<String, String>;
+    /// Generates the next assistant message of a conversation, passing each
+    /// piece of its text to `on_token` as it arrives. The model may ask to
+    /// call some of `tools`, listed in the message's `tool_calls`; models
+    /// that can't fail with `ChatError::ToolsUnsupported`. Setting `cancel`
+    /// stops generation; the message so far is returned.
+    fn chat(&self, messages: &[ChatMessage], tools: &[ToolSpec], on_token: &mut dyn FnMut(&str), cancel: &AtomicBool) -> Result<ChatMessage, ChatError>;
     fn list_models(&self) -> Result<Vec<String>, String>;
     fn pull_model(&self, model: &str, on_progress: Box<dyn Fn(f64) + Send>) -> Result<(), String>;
     fn delete_model(&self, model: &str) -> Result<(), String>;
     fn set_model(&self, model: &str);
+    /// One embedding vector per text, computed by `model`.
+    fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String>;
+}
+
+/// Saved conversations with the assistant.
+pub trait IChatHistory {
+    /// All sessions, most recently updated first.
+    fn sessions(&self) -> Vec<ChatSession>;
+    fn get(&self, id: &str) -> Option<ChatSession>;
+    /// Creates or replaces the session with the same id.
+    fn save(&self, session: &ChatSession) -> Result<(), String>;
 }
 
 pub trait IFileIndexer {
-    fn search(&self, term: &str) -> Vec<String>;
-    fn index_home(&self);
+    /// Indexed files matching `query`, best first.
+    fn search(&self, query: &FileQuery, limit: usize) -> Vec<FileMatch>;
+    /// Files whose contents contain `term` (at least 3 characters), case-insensitively.
+    fn search_content(&self, term: &str, limit: usize) -> Vec<ContentMatch>;
+    /// Files whose contents are indexed, i.e. text files below the size limit.
+    fn text_files(&self) -> Vec<IndexedFile>;
+    /// Loads the saved index, brings it up to date and keeps watching for changes.
+    fn start(&self);
+}
+
+/// Passages of the user's files, searchable by meaning.
+pub trait IPassageIndex {
+    /// The passages most relevant to `question`, best first.
+    fn retrieve(&self, question: &str, limit: usize) -> Result<Vec<Passage>, String>;
 }
 
 pub trait IMacroRepository {
@@ -66,6 +157,9 @@ pub trait IMacroRepository {
 pub trait IWindowRepository {
     fn get_open_windows(&self) -> Vec<Window>;
     fn focus_window(&self, id: &str) -> Result<(), String>;
+    fn close_window(&self, id: &str) -> Result<(), String>;
+    /// Moves a window to a zero-based workspace index.
+    fn move_window_to_workspace(&self, id: &str, workspace: i32) -> Result<(), String>;
 }
 
 pub trait ITimeService {
@@ -78,3 +172,31 @@ pub trait ITimeService {
     fn toggle_pause(&self);
     fn restart(&self);
 }
+
+/// Launch history used for frecency ranking.
+pub trait IUsageHistory {
+    fn record(&self, key: &str, query: &str);
+    /// All stored records, oldest first.
+    fn records(&self) -> Vec<UsageRecord>;
+}
+
+/// Supplies the secondary actions listed for a result on Ctrl+Enter
+/// (e.g. "Show in Folder" for files). Returns nothing for results it doesn't know.
+pub trait IActionProvider {
+    fn actions_for(&self, result: &SearchResult) -> Vec<SearchResult>;
+}
+
+/// A search mode reachable through a query prefix (e.g. `w ` for windows).
+/// Providers are registered with the Omnibar, which strips the prefix and
+/// forwards the remainder of the query.
+pub trait ISearchProvider {
+    /// Stable identifier, used as the key when remapping prefixes in settings.
+    fn id(&self) -> &str;
+    /// Prefix used when the settings don't override it, including the trailing space.
+    fn default_prefix(&self) -> &str;
+    fn search(&self, query: &str) -> Vec<SearchResult>;
+    /// Runs an action produced by this provider. Returns `None` if the action isn't ours.
+    fn activate(&self, _action: &Action) -> Option<Result<(), String>> {
+        None
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+
+/// A parsed `.desktop` file, following the freedesktop Desktop Entry Specification.
+/// Keys are stored per group (`Desktop Entry`, `Desktop Action new-window`, ...) so
+/// action sections can never override the main entry.
+#[derive(Debug, Clone, Default)]
+pub struct DesktopEntry {
+    groups: HashMap<String, HashMap<String, String>>,
+    /// Path of the file itself, substituted for `%k`.
+    pub path: String,
+}
+
+pub const MAIN_GROUP: &str = "Desktop Entry";
+
+impl DesktopEntry {
+    pub fn parse(content: &str, path: &str) -> Option<Self> {
+        let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut current: Option<String> = None;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = Some(group.to_string());
+                groups.entry(group.to_string()).or_default();
+                continue;
+            }
+            let (Some(group), Some((key, value))) = (&current, line.split_once('=')) else { continue };
+            // The first occurrence of a key in a group wins
+            groups.get_mut(group)?
+                .entry(key.trim().to_string())
+                .or_insert_with(|| value.trim().to_string());
+        }
+
+        if !groups.contains_key(MAIN_GROUP) {
+            return None;
+        }
+        Some(Self { groups, path: path.to_string() })
+    }
+
+    /// Raw (still escaped) value of a key.
+    fn raw(&self, group: &str, key: &str) -> Option<&str> {
+        self.groups.get(group)?.get(key).map(String::as_str)
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.get_in(MAIN_GROUP, key)
+    }
+
+    pub fn get_in(&self, group: &str, key: &str) -> Option<String> {
+        self.raw(group, key).map(unescape)
+    }
+
+    /// Looks up `key[locale]` for each candidate in order, falling back to `key`.
+    pub fn get_localized(&self, key: &str, locales: &[String]) -> Option<String> {
+        self.get_localized_in(MAIN_GROUP, key, locales)
+    }
+
+    pub fn get_localized_in(&self, group: &str, key: &str, locales: &[String]) -> Option<String> {
+        locales.iter()
+            .find_map(|locale| self.get_in(group, &format!("{}[{}]", key, locale)))
+            .or_else(|| self.get_in(group, key))
+    }
+
+    pub fn get_bool(&self, key: &str) -> bool {
+        self.raw(MAIN_GROUP, key) == Some("true")
+    }
+
+    /// A `;`-separated list value. `\;` is a literal semicolon.
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.raw(MAIN_GROUP, key).map(split_list).unwrap_or_default()
+    }
+
+    pub fn get_localized_list(&self, key: &str, locales: &[String]) -> Vec<String> {
+        locales.iter()
+            .find_map(|locale| self.raw(MAIN_GROUP, &format!("{}[{}]", key, locale)))
+            .or_else(|| self.raw(MAIN_GROUP, key))
+            .map(split_list)
+            .unwrap_or_default()
+    }
+
+    /// Expands the main `Exec` key into an argument vector, passing `files`
+    /// (paths or URLs) to the `%f %u %F %U` field codes.
+    pub fn exec_argv(&self, files: &[String], locales: &[String]) -> Option<Vec<String>> {
+        self.exec_argv_in(MAIN_GROUP, files, locales)
+    }
+
+    pub fn exec_argv_in(&self, group: &str, files: &[String], locales: &[String]) -> Option<Vec<String>> {
+        let exec = self.get_in(group, "Exec")?;
+        let name = self.get_localized("Name", locales).unwrap_or_default();
+        let icon = self.get("Icon");
+
+        let mut argv = Vec::new();
+        for arg in split_exec(&exec)? {
+            match arg.as_str() {
+                "%F" | "%U" => argv.extend(files.iter().cloned()),
+                "%i" => {
+                    if let Some(icon) = &icon {
+                        argv.push("--icon".to_string());
+                        argv.push(icon.clone());
+                    }
+                },
+                _ => {
+                    let had_code = arg.contains('%');
+                    let expanded = expand_field_codes(&arg, files.first().map(String::as_str), &name, &self.path);
+                    // An argument made only of a file code disappears when there is no file
+                    if !(had_code && expanded.is_empty()) {
+                        argv.push(expanded);
+                    }
+                }
+            }
+        }
+        if argv.is_empty() { None } else { Some(argv) }
+    }
+}
+
+/// Candidate locale suffixes for the current environment, most specific first
+/// (`de_DE@euro`, `de_DE`, `de@euro`, `de`).
+pub fn current_locales() -> Vec<String> {
+    let raw = ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty())
+        .unwrap_or_default();
+    locale_candidates(&raw)
+}
+
+fn locale_candidates(raw: &str) -> Vec<String> {
+    if raw.is_empty() || raw == "C" || raw == "POSIX" {
+        return vec![];
+    }
+    let (rest, modifier) = match raw.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (raw, None),
+    };
+    // The encoding is not part of the key
+    let rest = rest.split('.').next().unwrap_or(rest);
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+
+    let mut candidates = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        candidates.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{}@{}", lang, modifier));
+    }
+    candidates.push(lang.to_string());
+    candidates
+}
+
+/// Resolves the `\s \n \t \r \\` escapes of string values.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some(other) => { out.push('\\'); out.push(other); },
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(other) => { current.push('\\'); current.push(other); },
+                None => {},
+            },
+            ';' => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+    items.into_iter()
+        .map(|item| unescape(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Splits an `Exec` value into arguments. Double-quoted arguments may contain
+/// spaces and the escapes `\" \` \$ \\`. Returns `None` on an unterminated quote.
+fn split_exec(exec: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut in_quotes = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_arg = true;
+            },
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            },
+            ' ' | '\t' if !in_quotes => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            },
+            _ => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Some(args)
+}
+
+/// Expands the single-value field codes inside one argument. Deprecated codes
+/// (`%d %D %n %N %v %m`) and file codes without a file are removed.
+fn expand_field_codes(arg: &str, file: Option<&str>, name: &str, desktop_path: &str) -> String {
+    let mut out = String::new();
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('f') | Some('u') => out.push_str(file.unwrap_or("")),
+            Some('c') => out.push_str(name),
+            Some('k') => out.push_str(desktop_path),
+            Some('%') => out.push('%'),
+            _ => {},
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = r#"
+[Desktop Entry]
+Name=Firefox
+Name[de]=Firefox Browser
+GenericName=Web Browser
+Keywords=Internet;WWW;Browser\;Web;
+Exec=flatpak run --command=firefox org.mozilla.firefox "--class=Fire fox" %U
+Icon=firefox
+Type=Application
+
+[Desktop Action new-window]
+Name=New Window
+Exec=flatpak run org.mozilla.firefox --new-window %u
+"#;
+
+    #[test]
+    fn test_action_groups_do_not_override_main_entry() {
+        let entry = DesktopEntry::parse(FIREFOX, "/x/firefox.desktop").unwrap();
+        assert_eq!(entry.get("Name").as_deref(), Some("Firefox"));
+        assert_eq!(entry.get_in("Desktop Action new-window", "Name").as_deref(), Some("New Window"));
+    }
+
+    #[test]
+    fn test_localized_and_list_values() {
+        let entry = DesktopEntry::parse(FIREFOX, "").unwrap();
+        let de = locale_candidates("de_DE.UTF-8");
+        assert_eq!(de, ["de_DE", "de"]);
+        assert_eq!(entry.get_localized("Name", &de).as_deref(), Some("Firefox Browser"));
+        assert_eq!(entry.get_localized("Name", &locale_candidates("fr_FR")).as_deref(), Some("Firefox"));
+        assert_eq!(entry.get_list("Keywords"), ["Internet", "WWW", "Browser;Web"]);
+    }
+
+    #[test]
+    fn test_exec_keeps_arguments_and_expands_field_codes() {
+        let entry = DesktopEntry::parse(FIREFOX, "").unwrap();
+        assert_eq!(
+            entry.exec_argv(&[], &[]).unwrap(),
+            ["flatpak", "run", "--command=firefox", "org.mozilla.firefox", "--class=Fire fox"]
+        );
+        let files = vec!["a.html".to_string(), "b.html".to_string()];
+        assert_eq!(entry.exec_argv(&files, &[]).unwrap()[5..], ["a.html", "b.html"]);
+        assert_eq!(
+            entry.exec_argv_in("Desktop Action new-window", &files, &[]).unwrap(),
+            ["flatpak", "run", "org.mozilla.firefox", "--new-window", "a.html"]
+        );
+    }
+
+    #[test]
+    fn test_exec_icon_name_and_location_codes() {
+        let content = "[Desktop Entry]\nName=Foo\nIcon=foo\nExec=foo %i --title=%c %k 100%% \"a \\\"b\\\"\"\n";
+        let entry = DesktopEntry::parse(content, "/apps/foo.desktop").unwrap();
+        assert_eq!(
+            entry.exec_argv(&[], &[]).unwrap(),
+            ["foo", "--icon", "foo", "--title=Foo", "/apps/foo.desktop", "100%", "a \"b\""]
+        );
+    }
+}
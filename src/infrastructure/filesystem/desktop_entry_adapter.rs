@@ -1,10 +1,10 @@
-use crate::domain::model::App;
+use crate::domain::model::{App, CommandSpec, DesktopAction};
 use crate::domain::ports::IAppRepository;
+use crate::infrastructure::filesystem::desktop_entry::{current_locales, DesktopEntry};
 use walkdir::WalkDir;
 use std::fs;
-use std::path::Path;
-use regex::Regex;
-use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 pub struct LinuxAppRepoAdapter;
 
@@ -13,68 +13,278 @@ impl LinuxAppRepoAdapter {
         Self
     }
 
-    fn parse_desktop_file(path: &Path) -> Option<App> {
+    fn read_desktop_file(path: &Path) -> Option<DesktopEntry> {
         let content = fs::read_to_string(path).ok()?;
-        
-        static RE_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^Name=(.*)$").unwrap());
-        static RE_EXEC: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^Exec=(.*)$").unwrap());
-        static RE_ICON: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^Icon=(.*)$").unwrap());
-        static RE_NO_DISPLAY: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^NoDisplay=true$").unwrap());
+        DesktopEntry::parse(&content, &path.to_string_lossy())
+    }
 
-        if RE_NO_DISPLAY.is_match(&content) {
+    fn app_from_entry(entry: &DesktopEntry, locales: &[String], desktops: &[String]) -> Option<App> {
+        if entry.get("Type").is_some_and(|t| t != "Application") {
+            return None;
+        }
+        if entry.get_bool("NoDisplay") || entry.get_bool("Hidden") {
+            return None;
+        }
+        if !is_shown_in(entry, desktops) {
+            return None;
+        }
+        if entry.get("TryExec").is_some_and(|try_exec| !is_executable(&try_exec)) {
             return None;
         }
 
-        let name = RE_NAME.captures(&content)?.get(1)?.as_str().trim().to_string();
-        let exec_raw = RE_EXEC.captures(&content)?.get(1)?.as_str().trim();
-        
-        // Clean up Exec command (remove %u, %F, etc.)
-        let exec_path = exec_raw.split_whitespace().next()?.to_string();
+        let name = entry.get_localized("Name", locales)?;
+        let command = launch_command(entry, entry.exec_argv(&[], locales)?)?;
 
-        let icon = RE_ICON.captures(&content).map(|c| c.get(1).unwrap().as_str().trim().to_string());
+        let actions = entry.get_list("Actions").into_iter()
+            .filter_map(|id| {
+                let group = format!("Desktop Action {}", id);
+                let command = launch_command(entry, entry.exec_argv_in(&group, &[], locales)?)?;
+                Some(DesktopAction {
+                    name: entry.get_localized_in(&group, "Name", locales)?,
+                    icon: entry.get_in(&group, "Icon"),
+                    exec_path: command.command_line(),
+                    command,
+                    id,
+                })
+            })
+            .collect();
 
         Some(App {
             name,
-            exec_path,
-            icon,
+            exec_path: command.command_line(),
+            command,
+            icon: entry.get("Icon"),
             is_running: false, // Default state
             is_favorite: false,
+            generic_name: entry.get_localized("GenericName", locales),
+            keywords: entry.get_localized_list("Keywords", locales),
+            categories: entry.get_list("Categories"),
+            desktop_file: Some(entry.path.clone()),
+            desktop_id: None,
+            actions,
+            startup_wm_class: entry.get("StartupWMClass"),
+            terminal: entry.get_bool("Terminal"),
         })
     }
 }
 
+/// Builds the command for an expanded `Exec`, honouring `Path`. `Terminal`
+/// is applied at launch time, with the configured terminal.
+fn launch_command(entry: &DesktopEntry, argv: Vec<String>) -> Option<CommandSpec> {
+    let mut argv = argv.into_iter();
+    let mut command = CommandSpec::new(&argv.next()?, argv);
+    if let Some(dir) = entry.get("Path").filter(|d| !d.is_empty()) {
+        command = command.with_cwd(&dir);
+    }
+    Some(command)
+}
+
+/// Applies `OnlyShowIn` / `NotShowIn` against `$XDG_CURRENT_DESKTOP`.
+fn is_shown_in(entry: &DesktopEntry, desktops: &[String]) -> bool {
+    let only = entry.get_list("OnlyShowIn");
+    if !only.is_empty() && !only.iter().any(|d| desktops.contains(d)) {
+        return false;
+    }
+    !entry.get_list("NotShowIn").iter().any(|d| desktops.contains(d))
+}
+
+pub(crate) fn current_desktops() -> Vec<String> {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .map(|v| v.split(':').filter(|d| !d.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// `TryExec` is either an absolute path or a name looked up in `$PATH`.
+pub(crate) fn is_executable(program: &str) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    let runnable = |p: &Path| fs::metadata(p).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0);
+
+    if program.contains('/') {
+        return runnable(Path::new(program));
+    }
+    std::env::var("PATH")
+        .unwrap_or_default()
+        .split(':')
+        .any(|dir| runnable(&Path::new(dir).join(program)))
+}
+
+/// `applications` directories in precedence order: `$XDG_DATA_HOME`, then
+/// `$XDG_DATA_DIRS`, then the Flatpak and Snap export directories when the
+/// session didn't already add them.
+fn application_dirs() -> Vec<PathBuf> {
+    data_dirs().into_iter().map(|root| root.join("applications")).collect()
+}
+
+/// Data directories in the same precedence order, holding `applications/`,
+/// `mime/` and so on.
+pub(crate) fn data_dirs() -> Vec<PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    data_dirs_from(
+        std::env::var("XDG_DATA_HOME").ok().as_deref(),
+        std::env::var("XDG_DATA_DIRS").ok().as_deref(),
+        &home,
+    )
+}
+
+fn data_dirs_from(data_home: Option<&str>, data_dirs: Option<&str>, home: &str) -> Vec<PathBuf> {
+    // Empty or relative values are invalid per the Base Directory spec and fall back to defaults
+    let data_home = data_home
+        .filter(|d| d.starts_with('/'))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(home).join(".local/share"));
+    let data_dirs: Vec<PathBuf> = data_dirs
+        .map(|dirs| dirs.split(':').filter(|d| d.starts_with('/')).map(PathBuf::from).collect())
+        .filter(|dirs: &Vec<PathBuf>| !dirs.is_empty())
+        .unwrap_or_else(|| vec![PathBuf::from("/usr/local/share"), PathBuf::from("/usr/share")]);
+
+    let mut roots = vec![data_home.clone()];
+    roots.extend(data_dirs);
+    roots.extend([
+        data_home.join("flatpak/exports/share"),
+        PathBuf::from("/var/lib/flatpak/exports/share"),
+        PathBuf::from("/var/lib/snapd/desktop"),
+    ]);
+
+    let mut seen = HashSet::new();
+    roots.into_iter()
+        .filter(|dir| seen.insert(dir.clone()))
+        .collect()
+}
+
+/// Desktop file ID: the path below `applications/` with `/` replaced by `-`.
+pub(crate) fn desktop_file_id(apps_dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(apps_dir).ok()?;
+    Some(relative.to_string_lossy().replace('/', "-"))
+}
+
 impl IAppRepository for LinuxAppRepoAdapter {
     fn find_apps(&self) -> Vec<App> {
+        Self::find_apps_in(&application_dirs())
+    }
+
+    fn watch_paths(&self) -> Vec<String> {
+        application_dirs().iter().map(|d| d.to_string_lossy().to_string()).collect()
+    }
+}
+
+impl LinuxAppRepoAdapter {
+    fn find_apps_in(dirs: &[PathBuf]) -> Vec<App> {
         let mut apps = Vec::new();
-        let paths = vec![
-            "/usr/share/applications",
-            "/usr/local/share/applications",
-            // Add home directory handling carefully if needed, relying on std::env::home_dir is deprecated, 
-            // but we can assume linux environment.
-        ];
-        
-        // Expand home dir manually for now or use a crate if strictly needed, 
-        // but let's stick to system paths + simple home expansion
-        let home = std::env::var("HOME").unwrap_or_default();
-        let local_apps = format!("{}/.local/share/applications", home);
-        
-        let all_paths = [paths.as_slice(), &[local_apps.as_str()]].concat();
-
-        for dir in all_paths {
-            for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
-                if entry.path().extension().map_or(false, |ext| ext == "desktop") {
-                    if let Some(app) = Self::parse_desktop_file(entry.path()) {
-                        apps.push(app);
-                    }
+        let locales = current_locales();
+        let desktops = current_desktops();
+        // IDs already claimed by a higher-precedence directory. A hidden entry
+        // still claims its ID, which is how users delete system entries.
+        let mut seen_ids = HashSet::new();
+
+        for dir in dirs {
+            for file in WalkDir::new(dir).follow_links(true).into_iter().filter_map(|e| e.ok()) {
+                if file.path().extension().is_none_or(|ext| ext != "desktop") {
+                    continue;
+                }
+                let Some(id) = desktop_file_id(dir, file.path()) else { continue };
+                if seen_ids.contains(&id) {
+                    continue;
+                }
+                let Some(entry) = Self::read_desktop_file(file.path()) else { continue };
+                seen_ids.insert(id.clone());
+
+                if let Some(mut app) = Self::app_from_entry(&entry, &locales, &desktops) {
+                    app.desktop_id = Some(id);
+                    apps.push(app);
                 }
             }
         }
-        
-        // Deduplicate based on name + exec or just assume list is fine? 
-        // Let's simple-dedup by name for now just in case.
-        apps.sort_by(|a, b| a.name.cmp(&b.name));
-        apps.dedup_by(|a, b| a.name == b.name);
 
+        apps.sort_by(|a, b| a.name.cmp(&b.name));
         apps
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(content: &str, desktops: &[&str]) -> Option<App> {
+        let entry = DesktopEntry::parse(content, "/apps/test.desktop").unwrap();
+        let desktops: Vec<String> = desktops.iter().map(|d| d.to_string()).collect();
+        LinuxAppRepoAdapter::app_from_entry(&entry, &[], &desktops)
+    }
+
+    #[test]
+    fn test_flatpak_exec_keeps_arguments() {
+        let app = app("[Desktop Entry]\nType=Application\nName=Bar\nExec=/usr/bin/flatpak run --branch=stable org.foo.Bar @@u %U @@\nKeywords=foo;bar;\n", &[]).unwrap();
+        assert_eq!(app.exec_path, "/usr/bin/flatpak run --branch=stable org.foo.Bar @@u @@");
+        assert_eq!(app.command.argv(), ["/usr/bin/flatpak", "run", "--branch=stable", "org.foo.Bar", "@@u", "@@"]);
+        assert_eq!(app.keywords, ["foo", "bar"]);
+        assert_eq!(app.desktop_file.as_deref(), Some("/apps/test.desktop"));
+    }
+
+    #[test]
+    fn test_hidden_and_desktop_filters() {
+        assert!(app("[Desktop Entry]\nName=A\nExec=a\nHidden=true\n", &[]).is_none());
+        assert!(app("[Desktop Entry]\nName=A\nExec=a\nOnlyShowIn=KDE;\n", &["GNOME"]).is_none());
+        assert!(app("[Desktop Entry]\nName=A\nExec=a\nOnlyShowIn=KDE;\n", &["ubuntu", "KDE"]).is_some());
+        assert!(app("[Desktop Entry]\nName=A\nExec=a\nNotShowIn=GNOME;\n", &["GNOME"]).is_none());
+        assert!(app("[Desktop Entry]\nName=A\nExec=a\nTryExec=/nonexistent/a\n", &[]).is_none());
+        assert!(app("[Desktop Entry]\nType=Link\nName=A\nURL=https://example.com\n", &[]).is_none());
+    }
+
+    #[test]
+    fn test_working_dir_and_quoting() {
+        let runner = app("[Desktop Entry]\nName=A\nPath=/opt/my app\nExec=run \"it's here\"\n", &[]).unwrap();
+        assert_eq!(runner.command, CommandSpec::new("run", ["it's here"]).with_cwd("/opt/my app"));
+        assert_eq!(runner.exec_path, "run 'it'\\''s here'");
+
+        // The terminal is chosen when launching, not baked into the command
+        let htop = app("[Desktop Entry]\nName=htop\nExec=htop\nTerminal=true\n", &[]).unwrap();
+        assert_eq!(htop.exec_path, "htop");
+        assert!(htop.terminal);
+    }
+
+    #[test]
+    fn test_data_dirs_precedence() {
+        let dirs = data_dirs_from(None, Some("/usr/share:relative:/var/lib/flatpak/exports/share"), "/home/u");
+        let dirs: Vec<String> = dirs.iter().map(|d| d.to_string_lossy().to_string()).collect();
+        assert_eq!(dirs, [
+            "/home/u/.local/share",
+            "/usr/share",
+            "/var/lib/flatpak/exports/share",
+            "/home/u/.local/share/flatpak/exports/share",
+            "/var/lib/snapd/desktop",
+        ]);
+    }
+
+    #[test]
+    fn test_user_entry_shadows_system_entry_by_id() {
+        let root = tempfile::tempdir().unwrap();
+        let user = root.path().join("user");
+        let system = root.path().join("system");
+        fs::create_dir_all(user.join("applications")).unwrap();
+        fs::create_dir_all(system.join("applications/kde")).unwrap();
+        fs::write(user.join("applications/foo.desktop"), "[Desktop Entry]\nName=Foo (mine)\nExec=foo --mine\n").unwrap();
+        fs::write(system.join("applications/foo.desktop"), "[Desktop Entry]\nName=Foo\nExec=foo\n").unwrap();
+        fs::write(user.join("applications/bar.desktop"), "[Desktop Entry]\nName=Bar\nExec=bar\nHidden=true\n").unwrap();
+        fs::write(system.join("applications/bar.desktop"), "[Desktop Entry]\nName=Bar\nExec=bar\n").unwrap();
+        fs::write(system.join("applications/kde/okular.desktop"), "[Desktop Entry]\nName=Okular\nExec=okular\n").unwrap();
+
+        let apps = LinuxAppRepoAdapter::find_apps_in(&[user.join("applications"), system.join("applications")]);
+
+        let names: Vec<&str> = apps.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["Foo (mine)", "Okular"]);
+        assert_eq!(apps[1].desktop_id.as_deref(), Some("kde-okular.desktop"));
+    }
+
+    #[test]
+    fn test_desktop_actions() {
+        let app = app(concat!(
+            "[Desktop Entry]\nName=Firefox\nExec=firefox %u\nIcon=firefox\nActions=new-window;new-private-window;missing;\n",
+            "[Desktop Action new-window]\nName=New Window\nExec=firefox --new-window %u\n",
+            "[Desktop Action new-private-window]\nName=New Private Window\nExec=firefox --private-window %u\n",
+        ), &[]).unwrap();
+        assert_eq!(app.name, "Firefox");
+        assert_eq!(app.exec_path, "firefox");
+        let actions: Vec<(&str, &str)> = app.actions.iter().map(|a| (a.name.as_str(), a.exec_path.as_str())).collect();
+        assert_eq!(actions, [("New Window", "firefox --new-window"), ("New Private Window", "firefox --private-window")]);
+        assert_eq!(app.actions[1].command, CommandSpec::new("firefox", ["--private-window"]));
+    }
+}
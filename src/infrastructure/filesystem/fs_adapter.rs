@@ -1,5 +1,8 @@
+use crate::domain::model::FileEntry;
 use crate::domain::ports::IFileSystem;
+use crate::infrastructure::filesystem::trash::move_to_trash;
 use std::path::Path;
+use std::time::UNIX_EPOCH;
 
 pub struct LocalFileSystemAdapter;
 
@@ -9,6 +12,12 @@ impl LocalFileSystemAdapter {
     }
 }
 
+impl Default for LocalFileSystemAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl IFileSystem for LocalFileSystemAdapter {
     fn list_dir(&self, path: &str) -> Vec<String> {
         let mut results = Vec::new();
@@ -29,4 +38,39 @@ impl IFileSystem for LocalFileSystemAdapter {
     fn exists(&self, path: &str) -> bool {
         Path::new(path).exists()
     }
+
+    fn read_dir(&self, path: &str) -> Vec<FileEntry> {
+        let Ok(entries) = std::fs::read_dir(path) else { return vec![] };
+        entries.flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                // Follow symlinks so linked folders can be entered
+                let meta = std::fs::metadata(entry.path()).or_else(|_| entry.metadata()).ok()?;
+                Some(FileEntry {
+                    name,
+                    is_dir: meta.is_dir(),
+                    size: if meta.is_dir() { 0 } else { meta.len() },
+                    modified: meta.modified().ok()
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs()),
+                })
+            })
+            .collect()
+    }
+
+    fn trash(&self, path: &str) -> Result<(), String> {
+        move_to_trash(path)
+    }
+
+    fn rename(&self, path: &str, new_path: &str) -> Result<(), String> {
+        // fs::rename silently replaces an existing file
+        if std::fs::symlink_metadata(new_path).is_ok() {
+            return Err(format!("{} already exists", new_path));
+        }
+        std::fs::rename(path, new_path).map_err(|e| format!("Cannot rename {}: {}", path, e))
+    }
+
+    fn create_dir(&self, path: &str) -> Result<(), String> {
+        std::fs::create_dir(path).map_err(|e| format!("Cannot create {}: {}", path, e))
+    }
 }
@@ -0,0 +1,621 @@
+use crate::domain::model::{CommandSpec, MimeHandler};
+use crate::domain::ports::IMimeDatabase;
+use crate::infrastructure::filesystem::desktop_entry::{current_locales, DesktopEntry};
+use crate::infrastructure::filesystem::desktop_entry_adapter::{current_desktops, data_dirs, is_executable};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+const DIRECTORY: &str = "inode/directory";
+const UNKNOWN: &str = "application/octet-stream";
+const TEXT: &str = "text/plain";
+/// Bytes looked at to tell text from binary when no glob matches.
+const SNIFF_LEN: u64 = 512;
+
+/// A pattern from shared-mime-info's `globs2`, e.g. `50:image/png:*.png`.
+#[derive(Debug)]
+struct Glob {
+    weight: u32,
+    mime: String,
+    /// Lowercased unless the glob is case-sensitive.
+    pattern: String,
+    case_sensitive: bool,
+}
+
+/// Globs sorted by how they are matched. Nearly all are `*.ext`, found by
+/// looking up the file name's extensions; only the rest are tried in turn.
+#[derive(Debug, Default)]
+struct Globs {
+    /// `*.tar.gz` under `tar.gz`.
+    extensions: HashMap<String, Vec<Glob>>,
+    /// Whole file names such as `Makefile`.
+    names: HashMap<String, Vec<Glob>>,
+    /// Everything else, with the pattern split into characters once.
+    patterns: Vec<(Vec<char>, Glob)>,
+}
+
+impl Globs {
+    fn insert(&mut self, glob: Glob) {
+        let special = |p: &str| p.contains(['*', '?', '[']);
+        if let Some(extension) = glob.pattern.strip_prefix("*.").filter(|ext| !special(ext)) {
+            self.extensions.entry(extension.to_string()).or_default().push(glob);
+        } else if !special(&glob.pattern) {
+            self.names.entry(glob.pattern.clone()).or_default().push(glob);
+        } else {
+            self.patterns.push((glob.pattern.chars().collect(), glob));
+        }
+    }
+
+    /// The globs matching `file_name`.
+    fn matching(&self, file_name: &str) -> Vec<&Glob> {
+        let lower = file_name.to_lowercase();
+        let mut matched: Vec<&Glob> = globs_under(&self.names, file_name, &lower).collect();
+        for (i, _) in file_name.match_indices('.') {
+            let extension = &file_name[i + 1..];
+            matched.extend(globs_under(&self.extensions, extension, &extension.to_lowercase()));
+        }
+        if !self.patterns.is_empty() {
+            let name: Vec<char> = file_name.chars().collect();
+            let lower: Vec<char> = lower.chars().collect();
+            matched.extend(self.patterns.iter()
+                .filter(|(pattern, g)| glob_match(pattern, if g.case_sensitive { &name } else { &lower }))
+                .map(|(_, g)| g));
+        }
+        matched
+    }
+}
+
+/// Globs stored under `text`, or under its lowercase form `lower` for the
+/// case-insensitive ones, which are stored lowercased.
+fn globs_under<'a>(map: &'a HashMap<String, Vec<Glob>>, text: &str, lower: &str) -> impl Iterator<Item = &'a Glob> {
+    let same = text == lower;
+    let exact = map.get(text).into_iter().flatten().filter(move |g| g.case_sensitive || same);
+    let folded = map.get(lower).filter(|_| !same).into_iter().flatten().filter(|g| !g.case_sensitive);
+    exact.chain(folded)
+}
+
+/// shared-mime-info data merged from every `mime/` directory.
+#[derive(Debug, Default)]
+struct MimeInfo {
+    globs: Globs,
+    aliases: HashMap<String, String>,
+    parents: HashMap<String, Vec<String>>,
+    icons: HashMap<String, String>,
+}
+
+impl MimeInfo {
+    /// Reads `mime/` below each data directory, highest precedence first.
+    fn load(data_dirs: &[PathBuf]) -> Self {
+        let mut info = Self::default();
+        let mut seen_globs = HashSet::new();
+
+        for dir in data_dirs.iter().map(|d| d.join("mime")) {
+            let globs = fs::read_to_string(dir.join("globs2"))
+                .map(|content| parse_globs2(&content))
+                .or_else(|_| fs::read_to_string(dir.join("globs")).map(|content| parse_globs(&content)))
+                .unwrap_or_default();
+            for glob in globs {
+                if seen_globs.insert((glob.mime.clone(), glob.pattern.clone())) {
+                    info.globs.insert(glob);
+                }
+            }
+
+            // Earlier directories win, so only fill in what is still missing
+            for (alias, canonical) in read_pairs(&dir.join("aliases"), ' ') {
+                info.aliases.entry(alias).or_insert(canonical);
+            }
+            for (child, parent) in read_pairs(&dir.join("subclasses"), ' ') {
+                let parents = info.parents.entry(child).or_default();
+                if !parents.contains(&parent) {
+                    parents.push(parent);
+                }
+            }
+            for (mime, icon) in read_pairs(&dir.join("icons"), ':') {
+                info.icons.entry(mime).or_insert(icon);
+            }
+        }
+        info
+    }
+}
+
+fn parse_globs2(content: &str) -> Vec<Glob> {
+    content.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, ':');
+            let weight = fields.next()?.parse().ok()?;
+            let mime = fields.next()?.to_string();
+            let pattern = fields.next()?.to_string();
+            let case_sensitive = fields.next().is_some_and(|flags| flags.split(',').any(|f| f == "cs"));
+            Some(Glob::new(weight, mime, pattern, case_sensitive))
+        })
+        .collect()
+}
+
+/// The older `globs` format: `mime:pattern`, all with the default weight.
+fn parse_globs(content: &str) -> Vec<Glob> {
+    content.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(mime, pattern)| Glob::new(50, mime.to_string(), pattern.to_string(), false))
+        .collect()
+}
+
+impl Glob {
+    fn new(weight: u32, mime: String, pattern: String, case_sensitive: bool) -> Self {
+        let pattern = if case_sensitive { pattern } else { pattern.to_lowercase() };
+        Self { weight, mime, pattern, case_sensitive }
+    }
+}
+
+fn read_pairs(path: &Path, separator: char) -> Vec<(String, String)> {
+    fs::read_to_string(path)
+        .map(|content| {
+            content.lines()
+                .filter(|line| !line.starts_with('#'))
+                .filter_map(|line| line.split_once(separator))
+                .map(|(a, b)| (a.trim().to_string(), b.trim().to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Sections of an ini-style file (`mimeapps.list`, `mimeinfo.cache`), with
+/// every value split as a `;`-separated list.
+type Sections = HashMap<String, HashMap<String, Vec<String>>>;
+
+fn parse_sections(content: &str) -> Sections {
+    let mut sections: Sections = HashMap::new();
+    let mut current = None;
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(name.to_string());
+            continue;
+        }
+        let (Some(section), Some((key, value))) = (&current, line.split_once('=')) else { continue };
+        let ids = value.split(';').map(str::trim).filter(|id| !id.is_empty()).map(str::to_string);
+        sections.entry(section.clone()).or_default()
+            .entry(key.trim().to_string()).or_default()
+            .extend(ids);
+    }
+    sections
+}
+
+/// Whether `name` matches a shell glob with `*`, `?` and `[...]`.
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Where to resume after the last `*`: (pattern index, name index)
+    let mut backtrack = None;
+
+    while n < name.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+                continue;
+            },
+            Some('?') => Some(p + 1),
+            Some('[') => match_class(pattern, p, name[n]),
+            Some(&c) if c == name[n] => Some(p + 1),
+            _ => None,
+        };
+        match (step, backtrack) {
+            (Some(next), _) => {
+                p = next;
+                n += 1;
+            },
+            (None, Some((star, matched))) => {
+                // Let the `*` swallow one more character
+                p = star + 1;
+                n = matched + 1;
+                backtrack = Some((star, matched + 1));
+            },
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Matches `c` against the class starting at `pattern[start] == '['`,
+/// returning the index after the class.
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<usize> {
+    let mut i = start + 1;
+    let negate = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negate {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    while let Some(&item) = pattern.get(i) {
+        if item == ']' && !first {
+            return (matched != negate).then_some(i + 1);
+        }
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|&end| end != ']') {
+            matched |= (item..=pattern[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= item == c;
+            i += 1;
+        }
+        first = false;
+    }
+    // Unterminated: treat `[` as a literal
+    (c == '[').then_some(start + 1)
+}
+
+/// Parsed `mimeapps.list` and `mimeinfo.cache` files, with the state of
+/// each candidate file when they were read.
+struct Associations {
+    stamps: Vec<Option<(SystemTime, u64)>>,
+    lists: Vec<Sections>,
+    caches: Vec<Sections>,
+}
+
+/// Modification time and size, to notice a file being changed.
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn read_sections(paths: &[PathBuf]) -> Vec<Sections> {
+    paths.iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|content| parse_sections(&content))
+        .collect()
+}
+
+/// Resolves file types with shared-mime-info globs and picks applications
+/// from `mimeapps.list` and `mimeinfo.cache`, following the XDG MIME
+/// Applications spec. The globs are read once; associations are re-read
+/// when one of their files changes, so changes made in other programs
+/// apply immediately.
+pub struct XdgMimeAdapter {
+    info: MimeInfo,
+    /// Data directories, highest precedence first.
+    data_dirs: Vec<PathBuf>,
+    /// Directories searched for `mimeapps.list`, highest precedence first.
+    config_dirs: Vec<PathBuf>,
+    /// Lowercased `$XDG_CURRENT_DESKTOP` names, for `<desktop>-mimeapps.list`.
+    desktops: Vec<String>,
+    locales: Vec<String>,
+    associations: Mutex<Option<Arc<Associations>>>,
+}
+
+impl XdgMimeAdapter {
+    pub fn new() -> Self {
+        let home = std::env::var("HOME").unwrap_or_default();
+        let config_home = std::env::var("XDG_CONFIG_HOME").ok()
+            .filter(|d| d.starts_with('/'))
+            .map(PathBuf::from)
+            .unwrap_or_else(|| Path::new(&home).join(".config"));
+        let config_dirs: Vec<PathBuf> = std::env::var("XDG_CONFIG_DIRS").ok()
+            .map(|dirs| dirs.split(':').filter(|d| d.starts_with('/')).map(PathBuf::from).collect())
+            .filter(|dirs: &Vec<PathBuf>| !dirs.is_empty())
+            .unwrap_or_else(|| vec![PathBuf::from("/etc/xdg")]);
+
+        let mut adapter = Self::from_dirs(data_dirs(), std::iter::once(config_home).chain(config_dirs).collect(), current_desktops());
+        adapter.locales = current_locales();
+        adapter
+    }
+
+    fn from_dirs(data_dirs: Vec<PathBuf>, config_dirs: Vec<PathBuf>, desktops: Vec<String>) -> Self {
+        Self {
+            info: MimeInfo::load(&data_dirs),
+            data_dirs,
+            config_dirs,
+            desktops: desktops.iter().map(|d| d.to_lowercase()).collect(),
+            locales: vec![],
+            associations: Mutex::new(None),
+        }
+    }
+
+    fn canonical(&self, mime_type: &str) -> String {
+        let mime_type = mime_type.to_lowercase();
+        self.info.aliases.get(&mime_type).cloned().unwrap_or(mime_type)
+    }
+
+    /// The type followed by its ancestors, nearest first. Every `text/*`
+    /// type is a kind of `text/plain`.
+    fn with_parents(&self, mime_type: &str) -> Vec<String> {
+        let mut chain = vec![self.canonical(mime_type)];
+        let mut i = 0;
+        while i < chain.len() {
+            let mut parents = self.info.parents.get(&chain[i]).cloned().unwrap_or_default();
+            if chain[i].starts_with("text/") {
+                parents.push(TEXT.to_string());
+            }
+            for parent in parents.iter().map(|p| self.canonical(p)) {
+                if !chain.contains(&parent) {
+                    chain.push(parent);
+                }
+            }
+            i += 1;
+        }
+        chain
+    }
+
+    /// Type from the file name alone, per the glob weights.
+    fn match_globs(&self, file_name: &str) -> Option<&str> {
+        self.info.globs.matching(file_name).into_iter()
+            // Case-sensitive and longer patterns are the more specific ones
+            .max_by_key(|g| (g.weight, g.case_sensitive, g.pattern.len()))
+            .map(|g| g.mime.as_str())
+    }
+
+    /// Paths of the `mimeapps.list` files, highest precedence first. The
+    /// ones in data directories are deprecated but still honoured.
+    fn mimeapps_lists(&self) -> Vec<PathBuf> {
+        let config = self.config_dirs.iter().cloned();
+        let data = self.data_dirs.iter().map(|d| d.join("applications"));
+        config.chain(data)
+            .flat_map(|dir| {
+                self.desktops.iter()
+                    .map(|desktop| dir.join(format!("{}-mimeapps.list", desktop)))
+                    .chain(std::iter::once(dir.join("mimeapps.list")))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn mimeinfo_caches(&self) -> Vec<PathBuf> {
+        self.data_dirs.iter().map(|d| d.join("applications/mimeinfo.cache")).collect()
+    }
+
+    /// The associations, read again only if one of their files was
+    /// created, changed or removed since the last call.
+    fn associations(&self) -> Arc<Associations> {
+        let lists = self.mimeapps_lists();
+        let caches = self.mimeinfo_caches();
+        let stamps: Vec<_> = lists.iter().chain(&caches).map(|path| stamp(path)).collect();
+
+        let mut cached = self.associations.lock().unwrap();
+        if let Some(associations) = cached.as_ref().filter(|a| a.stamps == stamps) {
+            return associations.clone();
+        }
+        let associations = Arc::new(Associations {
+            stamps,
+            lists: read_sections(&lists),
+            caches: read_sections(&caches),
+        });
+        *cached = Some(associations.clone());
+        associations
+    }
+
+    /// The installed desktop entry with this ID. A hidden entry in a
+    /// higher-precedence directory removes the application.
+    fn find_entry(&self, desktop_id: &str) -> Option<DesktopEntry> {
+        let path = self.data_dirs.iter()
+            .map(|d| d.join("applications"))
+            .flat_map(|apps| desktop_file_candidates(&apps, desktop_id))
+            .find(|path| path.is_file())?;
+        let entry = DesktopEntry::parse(&fs::read_to_string(&path).ok()?, &path.to_string_lossy())?;
+
+        if entry.get_bool("Hidden") || entry.get("Type").is_some_and(|t| t != "Application") {
+            return None;
+        }
+        if entry.get("Exec").is_none() || entry.get("TryExec").is_some_and(|try_exec| !is_executable(&try_exec)) {
+            return None;
+        }
+        Some(entry)
+    }
+
+    fn handler(&self, desktop_id: &str) -> Option<MimeHandler> {
+        let entry = self.find_entry(desktop_id)?;
+        Some(MimeHandler {
+            desktop_id: desktop_id.to_string(),
+            name: entry.get_localized("Name", &self.locales)?,
+            icon: entry.get("Icon"),
+            terminal: entry.get_bool("Terminal"),
+        })
+    }
+}
+
+impl Default for XdgMimeAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where a desktop file ID may live below `applications/`: `kde-foo.desktop`
+/// is either `kde-foo.desktop` or `kde/foo.desktop`.
+fn desktop_file_candidates(apps_dir: &Path, desktop_id: &str) -> Vec<PathBuf> {
+    let mut candidates = vec![apps_dir.join(desktop_id)];
+    for (i, _) in desktop_id.match_indices('-') {
+        candidates.push(apps_dir.join(&desktop_id[..i]).join(&desktop_id[i + 1..]));
+    }
+    candidates
+}
+
+/// Files without a known extension are text if their start is valid UTF-8
+/// without NUL bytes.
+fn sniff(path: &str) -> &'static str {
+    let mut start = Vec::new();
+    let read = fs::File::open(path).and_then(|f| f.take(SNIFF_LEN).read_to_end(&mut start));
+    if read.is_err() || start.contains(&0) {
+        return UNKNOWN;
+    }
+    match std::str::from_utf8(&start) {
+        Ok(_) => TEXT,
+        // Cut off in the middle of a character
+        Err(e) if e.error_len().is_none() => TEXT,
+        Err(_) => UNKNOWN,
+    }
+}
+
+impl IMimeDatabase for XdgMimeAdapter {
+    fn mime_type(&self, path: &str) -> String {
+        if Path::new(path).is_dir() {
+            return DIRECTORY.to_string();
+        }
+        let file_name = Path::new(path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        match self.match_globs(&file_name) {
+            Some(mime) => self.canonical(mime),
+            None => sniff(path).to_string(),
+        }
+    }
+
+    fn icon_name(&self, mime_type: &str) -> String {
+        let mime_type = self.canonical(mime_type);
+        self.info.icons.get(&mime_type).cloned().unwrap_or_else(|| mime_type.replace('/', "-"))
+    }
+
+    fn handlers(&self, mime_type: &str) -> Vec<MimeHandler> {
+        let chain = self.with_parents(mime_type);
+        let associations = self.associations();
+        let (lists, caches) = (&associations.lists, &associations.caches);
+        let lookup = |sections: &[Sections], section: &str, mime: &str| -> Vec<String> {
+            sections.iter()
+                .filter_map(|s| s.get(section)?.get(mime))
+                .flatten()
+                .cloned()
+                .collect()
+        };
+
+        // Like GIO: a default set for the type or any parent comes first,
+        // then everything associated with the type, then with its parents
+        let mut ids = Vec::new();
+        for mime in &chain {
+            let removed = lookup(lists, "Removed Associations", mime);
+            let default = lookup(lists, "Default Applications", mime).into_iter()
+                .find(|id| !removed.contains(id) && self.find_entry(id).is_some());
+            ids.extend(default);
+        }
+        for mime in &chain {
+            let removed = lookup(lists, "Removed Associations", mime);
+            let associated = lookup(lists, "Added Associations", mime).into_iter()
+                .chain(lookup(caches, "MIME Cache", mime));
+            ids.extend(associated.filter(|id| !removed.contains(id)));
+        }
+
+        let mut seen = HashSet::new();
+        ids.into_iter()
+            .filter(|id| seen.insert(id.clone()))
+            .filter_map(|id| self.handler(&id))
+            .collect()
+    }
+
+    fn open_command(&self, handler: &MimeHandler, path: &str) -> Option<CommandSpec> {
+        let entry = self.find_entry(&handler.desktop_id)?;
+        let mut argv = entry.exec_argv(&[path.to_string()], &self.locales)?;
+        // Entries without a file field code get the file appended, as GIO does
+        let exec = entry.get("Exec").unwrap_or_default();
+        if !["%f", "%F", "%u", "%U"].iter().any(|code| exec.contains(code)) {
+            argv.push(path.to_string());
+        }
+
+        let mut spec = CommandSpec::new(&argv[0], &argv[1..]);
+        spec.cwd = entry.get("Path").filter(|d| !d.is_empty());
+        Some(spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn app(name: &str, exec: &str) -> String {
+        format!("[Desktop Entry]\nType=Application\nName={}\nExec={}\n", name, exec)
+    }
+
+    fn database() -> (tempfile::TempDir, XdgMimeAdapter) {
+        let root = tempfile::tempdir().unwrap();
+        let r = root.path();
+        write(r, "data/mime/globs2", "# comment\n50:image/png:*.png\n50:application/x-compressed-tar:*.tar.gz\n50:text/x-makefile:Makefile\n50:text/x-readme:README*\n50:text/x-python:*.py\n50:text/x-csrc:*.c\n50:text/x-c++src:*.C:cs\n");
+        write(r, "data/mime/aliases", "image/x-png image/png\n");
+        write(r, "data/mime/icons", "text/x-readme:text-x-generic\n");
+        write(r, "data/applications/gimp.desktop", &app("GIMP", "gimp %U"));
+        write(r, "data/applications/eog.desktop", &app("Image Viewer", "eog"));
+        write(r, "data/applications/viewer.desktop", &app("Viewer", "viewer %f"));
+        write(r, "data/applications/gedit.desktop", &app("Text Editor", "gedit %F"));
+        write(r, "data/applications/vim.desktop", &(app("Vim", "vim") + "Terminal=true\n"));
+        write(r, "data/applications/kde/kate.desktop", &app("Kate", "kate %U"));
+        write(r, "data/applications/mimeinfo.cache", "[MIME Cache]\nimage/png=eog.desktop;gimp.desktop;viewer.desktop;\ntext/plain=gedit.desktop;vim.desktop;\ntext/x-python=missing.desktop;\n");
+        write(r, "config/mimeapps.list", "[Default Applications]\nimage/png=uninstalled.desktop;gimp.desktop\n\n[Added Associations]\ntext/x-python=kde-kate.desktop;\n\n[Removed Associations]\nimage/png=viewer.desktop;\n");
+
+        let adapter = XdgMimeAdapter::from_dirs(vec![r.join("data")], vec![r.join("config")], vec!["GNOME".to_string()]);
+        (root, adapter)
+    }
+
+    fn ids(handlers: Vec<MimeHandler>) -> Vec<String> {
+        handlers.into_iter().map(|h| h.desktop_id).collect()
+    }
+
+    #[test]
+    fn test_glob_match() {
+        let matches = |pattern: &str, name: &str| {
+            glob_match(&pattern.chars().collect::<Vec<_>>(), &name.chars().collect::<Vec<_>>())
+        };
+        assert!(matches("*.tar.gz", "a.tar.gz"));
+        assert!(matches("readme*", "readme.md"));
+        assert!(matches("*.[ch]", "x.h"));
+        assert!(matches("core.[!a-z]*", "core.123"));
+        assert!(!matches("*.[!0-9]", "x.5"));
+        assert!(!matches("*.png", "png"));
+    }
+
+    #[test]
+    fn test_mime_type_from_globs_and_content() {
+        let (root, db) = database();
+        assert_eq!(db.mime_type("/x/Photo.PNG"), "image/png");
+        assert_eq!(db.mime_type("/x/README.md"), "text/x-readme");
+        assert_eq!(db.mime_type("/x/main.C"), "text/x-c++src");
+        assert_eq!(db.mime_type("/x/main.c"), "text/x-csrc");
+        assert_eq!(db.mime_type("/x/src.TAR.GZ"), "application/x-compressed-tar");
+        assert_eq!(db.mime_type("/x/makefile"), "text/x-makefile");
+        assert_eq!(db.mime_type(&root.path().to_string_lossy()), "inode/directory");
+
+        let notes = root.path().join("notes");
+        fs::write(&notes, "buy milk\n").unwrap();
+        assert_eq!(db.mime_type(&notes.to_string_lossy()), "text/plain");
+        let blob = root.path().join("blob");
+        fs::write(&blob, [0x7f, b'E', b'L', b'F', 0, 1]).unwrap();
+        assert_eq!(db.mime_type(&blob.to_string_lossy()), "application/octet-stream");
+
+        assert_eq!(db.icon_name("application/pdf"), "application-pdf");
+        assert_eq!(db.icon_name("text/x-readme"), "text-x-generic");
+    }
+
+    #[test]
+    fn test_handlers_follow_mimeapps_list() {
+        let (_root, db) = database();
+        // Uninstalled default skipped, removed association dropped, alias resolved
+        assert_eq!(ids(db.handlers("image/x-png")), ["gimp.desktop", "eog.desktop"]);
+        // Added association first, then what handles the text/plain parent
+        let python = db.handlers("text/x-python");
+        assert_eq!(ids(python.clone()), ["kde-kate.desktop", "gedit.desktop", "vim.desktop"]);
+        assert_eq!(python[0].name, "Kate");
+        assert!(python[2].terminal);
+        assert!(db.handlers("application/x-unknown").is_empty());
+    }
+
+    #[test]
+    fn test_associations_are_reread_when_changed() {
+        let (root, db) = database();
+        assert_eq!(ids(db.handlers("image/png"))[0], "gimp.desktop");
+        assert!(Arc::ptr_eq(&db.associations(), &db.associations()));
+
+        write(root.path(), "config/mimeapps.list", "[Default Applications]\nimage/png=eog.desktop\n");
+        assert_eq!(ids(db.handlers("image/png")), ["eog.desktop", "gimp.desktop", "viewer.desktop"]);
+    }
+
+    #[test]
+    fn test_open_command_passes_the_file() {
+        let (_root, db) = database();
+        let image = db.handlers("image/png");
+        assert_eq!(db.open_command(&image[0], "/p/a b.png").unwrap().argv(), ["gimp", "/p/a b.png"]);
+        // No field code: appended
+        assert_eq!(db.open_command(&image[1], "/p/a.png").unwrap().argv(), ["eog", "/p/a.png"]);
+    }
+}
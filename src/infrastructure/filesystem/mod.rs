@@ -1,3 +1,6 @@
 pub mod desktop_entry_adapter;
 pub mod procfs_adapter;
 pub mod fs_adapter;
+pub mod desktop_entry;
+pub mod mime_adapter;
+pub mod trash;
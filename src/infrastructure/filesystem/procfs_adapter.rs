@@ -1,13 +1,183 @@
 use crate::domain::model::App;
-use crate::domain::ports::IProcessMonitor;
+use crate::domain::ports::{IProcessMonitor, IWindowRepository};
+use std::collections::HashSet;
 use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Interpreters and launchers whose real program is a later argument
+/// (`python3 /usr/bin/foo`, `env FOO=1 app`, `java -jar app.jar`).
+const WRAPPERS: &[&str] = &["env", "sh", "bash", "python", "python2", "python3", "java", "node", "perl", "ruby", "gjs", "mono"];
+
+/// Launch commands that say nothing about which app runs (`flatpak run org.foo.Bar`).
+/// Those apps are matched through their systemd scope or windows instead.
+const GENERIC_LAUNCHERS: &[&str] = &["flatpak", "snap", "bwrap", "sh", "bash", "env", "gtk-launch", "xdg-open"];
 
-pub struct ProcFsMonitorAdapter;
+pub struct ProcFsMonitorAdapter {
+    windows: Option<Arc<dyn IWindowRepository + Send + Sync>>,
+}
 
 impl ProcFsMonitorAdapter {
     pub fn new() -> Self {
-        Self
+        Self { windows: None }
+    }
+
+    /// Also treats apps with an open window as running.
+    pub fn with_windows(windows: Arc<dyn IWindowRepository + Send + Sync>) -> Self {
+        Self { windows: Some(windows) }
     }
+
+    fn snapshot(&self) -> RunningSnapshot {
+        let mut snapshot = RunningSnapshot::default();
+
+        if let Ok(entries) = fs::read_dir("/proc") {
+            for entry in entries.flatten() {
+                let is_pid = entry.file_name().to_str().is_some_and(|n| n.parse::<u32>().is_ok());
+                if !is_pid {
+                    continue;
+                }
+                let path = entry.path();
+
+                // 1. `comm` (short name)
+                if let Ok(comm) = fs::read_to_string(path.join("comm")) {
+                    snapshot.programs.insert(comm.trim().to_lowercase());
+                }
+
+                // 2. `cmdline` (null-separated args), looking through wrappers
+                if let Ok(cmdline) = fs::read_to_string(path.join("cmdline")) {
+                    let args: Vec<&str> = cmdline.split('\0').filter(|a| !a.is_empty()).collect();
+                    snapshot.programs.extend(programs_from_args(&args));
+                }
+
+                // 3. systemd scope of apps started by the desktop (`app-gnome-firefox-1234.scope`)
+                if let Ok(cgroup) = fs::read_to_string(path.join("cgroup")) {
+                    if let Some(app_id) = cgroup_app_id(&cgroup) {
+                        snapshot.app_ids.insert(app_id.to_lowercase());
+                    }
+                }
+
+                // 4. Desktop file recorded by GLib/BAMF when launching (only readable for our own processes)
+                if let Ok(environ) = fs::read(path.join("environ")) {
+                    snapshot.desktop_files.extend(launched_desktop_files(&String::from_utf8_lossy(&environ)));
+                }
+            }
+        }
+
+        // 5. Open windows
+        if let Some(windows) = &self.windows {
+            snapshot.window_classes = windows.get_open_windows().into_iter().map(|w| w.app_name).collect();
+        }
+
+        snapshot
+    }
+}
+
+impl Default for ProcFsMonitorAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What is running right now, gathered in a single pass over `/proc`.
+#[derive(Debug, Default)]
+struct RunningSnapshot {
+    /// Lowercased `comm`, `argv[0]` and wrapped program names.
+    programs: HashSet<String>,
+    /// Lowercased application IDs from systemd scopes.
+    app_ids: HashSet<String>,
+    desktop_files: HashSet<String>,
+    window_classes: Vec<String>,
+}
+
+impl RunningSnapshot {
+    fn is_running(&self, app: &App) -> bool {
+        if app.desktop_file.as_ref().is_some_and(|f| self.desktop_files.contains(f)) {
+            return true;
+        }
+        if app.app_id().is_some_and(|id| self.app_ids.contains(&id.to_lowercase())) {
+            return true;
+        }
+        if self.window_classes.iter().any(|class| app.matches_window_class(class)) {
+            return true;
+        }
+
+        let Some(program) = app.program_name().map(|p| p.to_lowercase()) else { return false };
+        if GENERIC_LAUNCHERS.contains(&program.as_str()) {
+            return false;
+        }
+        if self.programs.contains(&program) {
+            return true;
+        }
+        // Chrome's launcher script is google-chrome(-stable) but the process is `chrome`
+        program.contains("google-chrome") && self.programs.contains("chrome")
+    }
+}
+
+/// Program names a process may be known by: `argv[0]`, plus the script or
+/// binary a wrapper runs.
+fn programs_from_args(args: &[&str]) -> Vec<String> {
+    let Some(first) = args.first() else { return vec![] };
+    let base = basename(first);
+    let mut names = vec![base.clone()];
+
+    let is_wrapper = WRAPPERS.contains(&base.as_str()) || base.starts_with("python");
+    if is_wrapper {
+        // First argument that is neither an option nor `VAR=value`
+        if let Some(target) = args[1..].iter().find(|a| !a.starts_with('-') && !a.contains('=')) {
+            let target = basename(target);
+            if let Some((stem, _ext)) = target.rsplit_once('.') {
+                names.push(stem.to_string());
+            }
+            names.push(target);
+        }
+    }
+    names
+}
+
+fn basename(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Extracts the application ID from a process's cgroup, following the systemd
+/// naming convention for desktop apps: `app[-<launcher>]-<id>-<random>.scope`,
+/// `app[-<launcher>]-<id>@<random>.service`, and Snap's `snap.<snap>.<app>-<uuid>.scope`.
+/// `-` inside the ID is escaped as `\x2d`.
+fn cgroup_app_id(cgroup: &str) -> Option<String> {
+    let unit = cgroup.lines()
+        .filter_map(|line| line.rsplit('/').next())
+        .find(|unit| unit.starts_with("app-") || unit.starts_with("snap."))?;
+
+    if let Some(snap) = unit.strip_prefix("snap.") {
+        let name = snap.strip_suffix(".scope")?;
+        let (name, _uuid) = name.rsplit_once('-')?;
+        // Snap desktop IDs are `<snap>_<app>.desktop`
+        return Some(name.replacen('.', "_", 1));
+    }
+
+    let unit = unit.strip_prefix("app-")?;
+    let name = if let Some(scope) = unit.strip_suffix(".scope") {
+        scope.rsplit_once('-')?.0
+    } else if let Some(service) = unit.strip_suffix(".service") {
+        service.split('@').next()?
+    } else {
+        return None;
+    };
+    // Unescaped dashes separate the optional launcher from the ID
+    let id = name.split_once('-').map_or(name, |(_launcher, id)| id);
+    Some(id.replace("\\x2d", "-"))
+}
+
+fn launched_desktop_files(environ: &str) -> Vec<String> {
+    environ.split('\0')
+        .filter_map(|var| {
+            var.strip_prefix("GIO_LAUNCHED_DESKTOP_FILE=")
+                .or_else(|| var.strip_prefix("BAMF_DESKTOP_FILE_HINT="))
+        })
+        .map(str::to_string)
+        .collect()
 }
 
 impl IProcessMonitor for ProcFsMonitorAdapter {
@@ -26,69 +196,82 @@ impl IProcessMonitor for ProcFsMonitorAdapter {
     }
 
     fn update_app_status(&self, apps: &mut [App]) {
-        let mut running_commands = std::collections::HashSet::new();
-        
-        if let Ok(entries) = fs::read_dir("/proc") {
-            for entry in entries.flatten() {
-                if let Ok(file_name) = entry.file_name().into_string() {
-                    if file_name.parse::<u32>().is_ok() {
-                        let path = entry.path();
-                        
-                        // 1. Check `comm` (short name)
-                        if let Ok(comm) = fs::read_to_string(path.join("comm")) {
-                            running_commands.insert(comm.trim().to_string());
-                        }
-                        
-                        // 2. Check `cmdline` (full args)
-                        if let Ok(cmdline) = fs::read_to_string(path.join("cmdline")) {
-                            // cmdline is null-separated
-                            if let Some(first_arg) = cmdline.split('\0').next() {
-                                if !first_arg.is_empty() {
-                                    // Add the full path/command
-                                    running_commands.insert(first_arg.to_string());
-                                    // Also add just the binary name
-                                    if let Some(name) = Path::new(first_arg).file_name().and_then(|n| n.to_str()) {
-                                        running_commands.insert(name.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
+        let snapshot = self.snapshot();
         for app in apps {
-            // exec_path line e.g. "/usr/bin/firefox %u" or "gnome-terminal"
-            // We want the primary command token
-            let exec_clean = app.exec_path.split_whitespace().next().unwrap_or(&app.exec_path);
-            let exec_bin = Path::new(exec_clean).file_name().and_then(|n| n.to_str()).unwrap_or(exec_clean);
-            
-            // Check if our exact binary name is running
-            if running_commands.contains(exec_bin) {
-                app.is_running = true;
-                continue;
-            }
-            
-            // Fallback: Check if any running command contains our binary name (fuzzy)
-            // Or if our exec path contains the running command (e.g. wrapper scripts)
-            // But strict matching is better to avoid false positives.
-            // Let's try matching ignoring case?
-            if running_commands.iter().any(|rc| rc.eq_ignore_ascii_case(exec_bin)) {
-                app.is_running = true;
-                continue; 
-            }
-            
-            // Special case for some common browsers that have different process names
-            // e.g. chrome -> chrome, google-chrome-stable
-            if exec_bin.contains("google-chrome") && running_commands.iter().any(|rc| rc.contains("chrome")) {
-                 app.is_running = true;
-                 continue;
-            }
-            
-            app.is_running = false;
+            app.is_running = snapshot.is_running(app);
         }
     }
 }
 
-use std::path::Path;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::CommandSpec;
+
+    fn app(exec: &str, desktop_id: &str) -> App {
+        let mut argv = exec.split_whitespace();
+        App {
+            name: desktop_id.to_string(),
+            exec_path: exec.to_string(),
+            command: CommandSpec::new(argv.next().unwrap(), argv),
+            desktop_id: Some(format!("{}.desktop", desktop_id)),
+            desktop_file: Some(format!("/usr/share/applications/{}.desktop", desktop_id)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cgroup_app_id() {
+        let gnome = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/app-gnome-firefox-4242.scope\n";
+        assert_eq!(cgroup_app_id(gnome).as_deref(), Some("firefox"));
+        let flatpak = "0::/user.slice/user@1000.service/app.slice/app-flatpak-org.mozilla.firefox-1234.scope";
+        assert_eq!(cgroup_app_id(flatpak).as_deref(), Some("org.mozilla.firefox"));
+        let escaped = "0::/app.slice/app-gnome-gnome\\x2dsystem\\x2dmonitor-77.scope";
+        assert_eq!(cgroup_app_id(escaped).as_deref(), Some("gnome-system-monitor"));
+        let service = "0::/app.slice/app-org.gnome.Terminal@abc.service";
+        assert_eq!(cgroup_app_id(service).as_deref(), Some("org.gnome.Terminal"));
+        let snap = "0::/user.slice/user@1000.service/app.slice/snap.firefox.firefox-9f6e.scope";
+        assert_eq!(cgroup_app_id(snap).as_deref(), Some("firefox_firefox"));
+        let ours = "0::/user.slice/user@1000.service/app.slice/app-launch-gnome\\x2dcalculator-812_4.scope";
+        assert_eq!(cgroup_app_id(ours).as_deref(), Some("gnome-calculator"));
+        assert_eq!(cgroup_app_id("0::/user.slice/user@1000.service/init.scope"), None);
+    }
+
+    #[test]
+    fn test_wrapped_programs() {
+        assert!(programs_from_args(&["/usr/bin/python3", "-s", "/usr/bin/meld"]).contains(&"meld".to_string()));
+        assert!(programs_from_args(&["env", "FOO=1", "/opt/app/app-bin"]).contains(&"app-bin".to_string()));
+        assert!(programs_from_args(&["java", "-jar", "/opt/tool.jar"]).contains(&"tool".to_string()));
+        assert_eq!(programs_from_args(&["/usr/bin/vim", "notes.txt"]), ["vim"]);
+    }
+
+    #[test]
+    fn test_running_signals() {
+        let mut snapshot = RunningSnapshot::default();
+
+        // Flatpak: the launch command is generic, the scope identifies the app
+        let flatpak = app("/usr/bin/flatpak run --branch=stable org.mozilla.firefox", "org.mozilla.firefox");
+        assert!(!snapshot.is_running(&flatpak));
+        snapshot.programs.insert("flatpak".to_string());
+        assert!(!snapshot.is_running(&flatpak));
+        snapshot.app_ids.insert("org.mozilla.firefox".to_string());
+        assert!(snapshot.is_running(&flatpak));
+
+        // Electron app with a window class that differs from its binary
+        let mut code = app("/usr/share/code/code --unity-launch", "code");
+        code.startup_wm_class = Some("Code".to_string());
+        snapshot.window_classes.push("code".to_string());
+        assert!(snapshot.is_running(&code));
+
+        // Launched by GLib in this session
+        let editor = app("gnome-text-editor", "org.gnome.TextEditor");
+        assert!(!snapshot.is_running(&editor));
+        snapshot.desktop_files.insert("/usr/share/applications/org.gnome.TextEditor.desktop".to_string());
+        assert!(snapshot.is_running(&editor));
+
+        // env prefix in Exec
+        let wrapped = app("env GDK_BACKEND=x11 /opt/foo/foo-bin", "foo");
+        snapshot.programs.insert("foo-bin".to_string());
+        assert!(snapshot.is_running(&wrapped));
+    }
+}
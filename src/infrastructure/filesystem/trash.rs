@@ -0,0 +1,186 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Sticky bit, required on a shared `$topdir/.Trash`.
+const STICKY: u32 = 0o1000;
+
+/// Moves `path` to the trash, following the FreeDesktop.org Trash spec:
+/// the home trash for files on the home device, otherwise a per-user trash
+/// at the top of the file's mount.
+pub fn move_to_trash(path: &str) -> Result<(), String> {
+    let path = Path::new(path);
+    // The entry itself, not what a symlink points to
+    let meta = fs::symlink_metadata(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let absolute = std::path::absolute(path).map_err(|e| e.to_string())?;
+    let home_trash = data_home().join("Trash");
+
+    let home_device = nearest_existing(&home_trash).and_then(|p| fs::metadata(p).ok()).map(|m| m.dev());
+    if home_device == Some(meta.dev()) {
+        return trash_into(&absolute, &home_trash, None);
+    }
+    let top = mount_top(&absolute, meta.dev());
+    trash_into(&absolute, &topdir_trash(&top), Some(&top))
+}
+
+fn data_home() -> PathBuf {
+    std::env::var("XDG_DATA_HOME").ok()
+        .filter(|d| d.starts_with('/'))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(&std::env::var("HOME").unwrap_or_default()).join(".local/share"))
+}
+
+fn nearest_existing(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|p| p.exists())
+}
+
+/// Highest ancestor of `path` on the same device, i.e. its mount point.
+fn mount_top(path: &Path, device: u64) -> PathBuf {
+    let mut top = path.parent().unwrap_or(path);
+    while let Some(parent) = top.parent() {
+        if fs::metadata(parent).map(|m| m.dev()).ok() != Some(device) {
+            break;
+        }
+        top = parent;
+    }
+    top.to_path_buf()
+}
+
+fn current_uid() -> u32 {
+    // The process's own /proc entry belongs to its user
+    fs::metadata("/proc/self").map(|m| m.uid()).unwrap_or(0)
+}
+
+/// `$topdir/.Trash/$uid` when the administrator set up a shared, sticky
+/// `.Trash`, otherwise `$topdir/.Trash-$uid`.
+fn topdir_trash(top: &Path) -> PathBuf {
+    let uid = current_uid();
+    let shared = top.join(".Trash");
+    let usable = fs::symlink_metadata(&shared)
+        .is_ok_and(|m| m.is_dir() && m.permissions().mode() & STICKY != 0);
+    if usable {
+        return shared.join(uid.to_string());
+    }
+    top.join(format!(".Trash-{}", uid))
+}
+
+/// Writes the `.trashinfo` first, claiming a unique name, then moves the file.
+/// `top` makes the recorded path relative, as required outside the home trash.
+fn trash_into(path: &Path, trash: &Path, top: Option<&Path>) -> Result<(), String> {
+    let files = trash.join("files");
+    let info = trash.join("info");
+    for dir in [&files, &info] {
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)
+            .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    }
+
+    let name = path.file_name().ok_or("Cannot trash the root directory")?.to_string_lossy().to_string();
+    let recorded = top.and_then(|t| path.strip_prefix(t).ok()).unwrap_or(path);
+    let content = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        escape_path(&recorded.to_string_lossy()),
+        deletion_date(SystemTime::now()),
+    );
+
+    let mut n = 0;
+    let (info_file, candidate, mut file) = loop {
+        let candidate = if n == 0 { name.clone() } else { format!("{}.{}", name, n) };
+        let info_file = info.join(format!("{}.trashinfo", candidate));
+        // Creating the info file atomically reserves the name
+        match fs::OpenOptions::new().write(true).create_new(true).open(&info_file) {
+            Ok(file) => break (info_file, candidate, file),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(format!("Cannot write {}: {}", info_file.display(), e)),
+        }
+    };
+
+    let moved = file.write_all(content.as_bytes())
+        .and_then(|_| fs::rename(path, files.join(&candidate)));
+    if let Err(e) = moved {
+        let _ = fs::remove_file(&info_file);
+        return Err(format!("Cannot move {} to the trash: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+/// Percent-encodes a path like a URL path, keeping `/`.
+fn escape_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// `YYYY-MM-DDThh:mm:ss`. Without a time zone database at hand this is UTC;
+/// file managers only use it for display and sorting.
+fn deletion_date(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", year, month, day, rest / 3600, rest % 3600 / 60, rest % 60)
+}
+
+/// Gregorian date of a day count since 1970-01-01 (Howard Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_trash_info_and_name_collisions() {
+        let root = tempfile::tempdir().unwrap();
+        let trash = root.path().join("Trash");
+        for _ in 0..2 {
+            let file = root.path().join("my notes.txt");
+            fs::write(&file, "x").unwrap();
+            trash_into(&file, &trash, None).unwrap();
+            assert!(!file.exists());
+        }
+
+        assert!(trash.join("files/my notes.txt").exists());
+        assert!(trash.join("files/my notes.txt.1").exists());
+        let info = fs::read_to_string(trash.join("info/my notes.txt.1.trashinfo")).unwrap();
+        let expected = format!("Path={}/my%20notes.txt\n", escape_path(&root.path().to_string_lossy()));
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&expected));
+        assert_eq!(fs::metadata(trash.join("info")).unwrap().permissions().mode() & 0o777, 0o700);
+    }
+
+    #[test]
+    fn test_topdir_paths_are_relative() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("photos")).unwrap();
+        let file = root.path().join("photos/a.jpg");
+        fs::write(&file, "x").unwrap();
+
+        let trash = topdir_trash(root.path());
+        assert_eq!(trash, root.path().join(format!(".Trash-{}", current_uid())));
+        trash_into(&file, &trash, Some(root.path())).unwrap();
+        let info = fs::read_to_string(trash.join("info/a.jpg.trashinfo")).unwrap();
+        assert!(info.contains("Path=photos/a.jpg\n"));
+    }
+
+    #[test]
+    fn test_deletion_date() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_210_096); // 2024-02-29 12:34:56 UTC
+        assert_eq!(deletion_date(time), "2024-02-29T12:34:56");
+        assert_eq!(deletion_date(UNIX_EPOCH), "1970-01-01T00:00:00");
+    }
+}
@@ -0,0 +1,87 @@
+use crate::domain::model::ChatSession;
+use crate::domain::ports::IChatHistory;
+use std::fs;
+use std::path::PathBuf;
+
+/// Chats saved as one JSON file each in `$XDG_DATA_HOME/launch/chats`.
+pub struct ChatStore {
+    dir: PathBuf,
+}
+
+impl ChatStore {
+    pub fn new() -> Self {
+        let dir = dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("launch")
+            .join("chats");
+        Self::with_dir(dir)
+    }
+
+    pub fn with_dir(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+}
+
+impl Default for ChatStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IChatHistory for ChatStore {
+    fn sessions(&self) -> Vec<ChatSession> {
+        let Ok(entries) = fs::read_dir(&self.dir) else { return vec![] };
+        let mut sessions: Vec<ChatSession> = entries.flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+            .filter_map(|content| serde_json::from_str(&content).ok())
+            .collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.updated));
+        sessions
+    }
+
+    fn get(&self, id: &str) -> Option<ChatSession> {
+        let content = fs::read_to_string(self.path(id)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self, session: &ChatSession) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(session).map_err(|e| e.to_string())?;
+        fs::write(self.path(&session.id), json).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::{ChatMessage, ChatRole};
+
+    #[test]
+    fn test_saves_and_lists_recent_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ChatStore::with_dir(dir.path().join("chats"));
+        assert!(store.sessions().is_empty());
+
+        let session = |id: &str, updated| ChatSession {
+            id: id.to_string(),
+            title: format!("chat {}", id),
+            updated,
+            messages: vec![ChatMessage::new(ChatRole::User, "hi"), ChatMessage::new(ChatRole::Assistant, "hello")],
+        };
+        store.save(&session("1", 10)).unwrap();
+        store.save(&session("2", 20)).unwrap();
+        let mut updated = session("1", 30);
+        updated.messages.push(ChatMessage::new(ChatRole::User, "again"));
+        store.save(&updated).unwrap();
+
+        let ids: Vec<String> = store.sessions().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, ["1", "2"]);
+        assert_eq!(store.get("1"), Some(updated));
+        assert_eq!(store.get("3"), None);
+    }
+}
@@ -0,0 +1,414 @@
+use crate::domain::model::{IndexedFile, Passage, RagSettings};
+use crate::domain::ports::{IFileIndexer, ILLMService, IPassageIndex};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+
+/// Bumped when the cache layout changes; older caches are then rebuilt.
+const CACHE_VERSION: u32 = 1;
+/// Lines per passage.
+const PASSAGE_LINES: usize = 40;
+/// Lines shared by consecutive passages, so no sentence is only ever cut in half.
+const PASSAGE_OVERLAP: usize = 8;
+/// Longer passages are cut before embedding; embedding models have small windows.
+const MAX_PASSAGE_CHARS: usize = 2000;
+/// Passages sent to the embedding model per request.
+const EMBED_BATCH: usize = 16;
+/// Files embedded between two saves of the cache.
+const SAVE_EVERY: usize = 50;
+/// Pause between passes over the file index.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Serialize, Deserialize)]
+struct StoredFile {
+    path: String,
+    modified: u64,
+    /// First and last line of each passage; the vectors follow the header in this order.
+    passages: Vec<(usize, usize)>,
+}
+
+/// The cache's first line, followed by the vectors as little-endian f32.
+#[derive(Serialize, Deserialize)]
+struct CacheHeader {
+    version: u32,
+    model: String,
+    dimensions: usize,
+    files: Vec<StoredFile>,
+}
+
+struct EmbeddedFile {
+    modified: u64,
+    /// Line range and unit-length vector of each passage.
+    passages: Vec<((usize, usize), Vec<f32>)>,
+}
+
+/// Embeds passages of the indexed text files with the embedding backend from
+/// the settings (a local Ollama by default) and finds those closest to a
+/// question by cosine similarity. Vectors are kept in
+/// `$XDG_CACHE_HOME/launch/embeddings`; passage text is re-read from the files.
+pub struct EmbeddingIndexAdapter {
+    inner: Arc<Embedder>,
+}
+
+struct Embedder {
+    llm: Arc<dyn ILLMService + Send + Sync>,
+    files: Arc<dyn IFileIndexer + Send + Sync>,
+    settings: RagSettings,
+    cache_file: PathBuf,
+    embedded: Mutex<HashMap<String, EmbeddedFile>>,
+    loaded: Once,
+    started: AtomicBool,
+}
+
+impl EmbeddingIndexAdapter {
+    pub fn new(
+        llm: Arc<dyn ILLMService + Send + Sync>,
+        files: Arc<dyn IFileIndexer + Send + Sync>,
+        settings: RagSettings,
+    ) -> Self {
+        let cache_dir = dirs::cache_dir().unwrap_or_else(|| PathBuf::from(".")).join("launch");
+        Self::with_cache_dir(llm, files, settings, cache_dir)
+    }
+
+    pub fn with_cache_dir(
+        llm: Arc<dyn ILLMService + Send + Sync>,
+        files: Arc<dyn IFileIndexer + Send + Sync>,
+        settings: RagSettings,
+        cache_dir: PathBuf,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Embedder {
+                llm,
+                files,
+                settings,
+                cache_file: cache_dir.join("embeddings"),
+                embedded: Mutex::new(HashMap::new()),
+                loaded: Once::new(),
+                started: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Resumes embedding in the background if the assistant was used before.
+    /// Otherwise it starts with the first question, so the embedding backend
+    /// isn't started or sent anything for people who never use it.
+    pub fn resume(&self) {
+        if self.inner.cache_file.exists() {
+            Embedder::start(&self.inner);
+        }
+    }
+}
+
+impl Embedder {
+    fn start(this: &Arc<Self>) {
+        if this.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let embedder = this.clone();
+        std::thread::spawn(move || embedder.run());
+    }
+
+    fn ensure_loaded(&self) {
+        self.loaded.call_once(|| {
+            let Ok(file) = fs::File::open(&self.cache_file) else { return };
+            match self.read(&mut BufReader::new(file)) {
+                Some(embedded) => *self.embedded.lock().unwrap() = embedded,
+                None => println!("Discarding embeddings cache {}", self.cache_file.display()),
+            }
+        });
+    }
+
+    fn read(&self, input: &mut impl BufRead) -> Option<HashMap<String, EmbeddedFile>> {
+        let mut line = Vec::new();
+        input.read_until(b'\n', &mut line).ok()?;
+        let header: CacheHeader = serde_json::from_slice(&line).ok()?;
+        // Vectors of another model aren't comparable
+        if header.version != CACHE_VERSION || header.model != self.settings.embedding_model {
+            return None;
+        }
+        let mut rest = Vec::new();
+        input.read_to_end(&mut rest).ok()?;
+        let mut values = rest.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+
+        let mut embedded = HashMap::new();
+        for file in header.files {
+            let mut passages = Vec::new();
+            for range in file.passages {
+                let vector: Vec<f32> = values.by_ref().take(header.dimensions).collect();
+                if vector.len() != header.dimensions {
+                    return None;
+                }
+                passages.push((range, vector));
+            }
+            embedded.insert(file.path, EmbeddedFile { modified: file.modified, passages });
+        }
+        Some(embedded)
+    }
+
+    fn save(&self) {
+        let embedded = self.embedded.lock().unwrap();
+        let dimensions = embedded.values().flat_map(|f| &f.passages).map(|(_, v)| v.len()).next().unwrap_or(0);
+        let header = CacheHeader {
+            version: CACHE_VERSION,
+            model: self.settings.embedding_model.clone(),
+            dimensions,
+            files: embedded.iter()
+                .map(|(path, file)| StoredFile {
+                    path: path.clone(),
+                    modified: file.modified,
+                    passages: file.passages.iter().map(|(range, _)| *range).collect(),
+                })
+                .collect(),
+        };
+
+        if let Some(dir) = self.cache_file.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let temp = self.cache_file.with_extension("tmp");
+        let written = fs::File::create(&temp).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            serde_json::to_writer(&mut out, &header)?;
+            out.write_all(b"\n")?;
+            for file in &header.files {
+                for (_, vector) in &embedded[&file.path].passages {
+                    for value in vector {
+                        out.write_all(&value.to_le_bytes())?;
+                    }
+                }
+            }
+            out.flush()
+        });
+        if let Err(e) = written.and_then(|_| fs::rename(&temp, &self.cache_file)) {
+            println!("Failed to save embeddings: {}", e);
+        }
+    }
+
+    fn run(&self) {
+        self.ensure_loaded();
+        loop {
+            if let Err(e) = self.sweep() {
+                println!("Embedding files for the assistant failed: {} (does the embedding backend serve `{}`?)", e, self.settings.embedding_model);
+            }
+            std::thread::sleep(SWEEP_INTERVAL);
+        }
+    }
+
+    /// Embeds new and changed files and forgets deleted ones.
+    fn sweep(&self) -> Result<(), String> {
+        let files: Vec<IndexedFile> = self.files.text_files().into_iter()
+            .filter(|file| {
+                let extension = file.path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+                extension.is_some_and(|ext| self.settings.extensions.contains(&ext))
+            })
+            .collect();
+        let live: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        let mut changed = {
+            let mut embedded = self.embedded.lock().unwrap();
+            let before = embedded.len();
+            embedded.retain(|path, _| live.contains(path.as_str()));
+            usize::from(embedded.len() != before)
+        };
+
+        for file in &files {
+            let current = self.embedded.lock().unwrap().get(&file.path).map(|f| f.modified);
+            if current == Some(file.modified) {
+                continue;
+            }
+            let passages = fs::read_to_string(&file.path).map(|text| split_passages(&text)).unwrap_or_default();
+            let mut embedded_passages = Vec::new();
+            for batch in passages.chunks(EMBED_BATCH) {
+                let texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+                let vectors = self.llm.embed(&self.settings.embedding_model, &texts);
+                let vectors = match vectors {
+                    Ok(vectors) => vectors,
+                    Err(e) => {
+                        // Keep what is done so far
+                        if changed > 0 {
+                            self.save();
+                        }
+                        return Err(e);
+                    },
+                };
+                embedded_passages.extend(batch.iter().map(|(range, _)| *range).zip(vectors.into_iter().map(normalized)));
+            }
+            self.embedded.lock().unwrap()
+                .insert(file.path.clone(), EmbeddedFile { modified: file.modified, passages: embedded_passages });
+
+            changed += 1;
+            if changed % SAVE_EVERY == 0 {
+                self.save();
+            }
+        }
+        if changed > 0 {
+            self.save();
+        }
+        Ok(())
+    }
+
+    fn retrieve(this: &Arc<Self>, question: &str, limit: usize) -> Result<Vec<Passage>, String> {
+        Self::start(this);
+        this.ensure_loaded();
+        let query = this.llm.embed(&this.settings.embedding_model, &[question.to_string()])?
+            .pop()
+            .map(normalized)
+            .ok_or("The embedding model returned nothing")?;
+
+        let mut best: Vec<(f32, String, (usize, usize))> = {
+            let embedded = this.embedded.lock().unwrap();
+            embedded.iter()
+                .flat_map(|(path, file)| file.passages.iter().map(move |(range, vector)| (path, range, vector)))
+                .filter(|(_, _, vector)| vector.len() == query.len())
+                .map(|(path, range, vector)| (dot(&query, vector), path.clone(), *range))
+                .collect()
+        };
+        best.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Ok(best.into_iter()
+            .take(limit)
+            .filter_map(|(_, path, (start, end))| {
+                let text = fs::read_to_string(&path).ok()?;
+                let lines: Vec<&str> = text.lines().skip(start - 1).take(end + 1 - start).collect();
+                Some(Passage { path, start_line: start, end_line: end, text: lines.join("\n") })
+            })
+            .collect())
+    }
+}
+
+/// Overlapping runs of lines, with their 1-based line ranges. Blank runs are skipped.
+fn split_passages(text: &str) -> Vec<((usize, usize), String)> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut passages = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + PASSAGE_LINES).min(lines.len());
+        let passage: String = lines[start..end].join("\n").chars().take(MAX_PASSAGE_CHARS).collect();
+        if !passage.trim().is_empty() {
+            passages.push(((start + 1, end), passage));
+        }
+        if end == lines.len() {
+            break;
+        }
+        start = end - PASSAGE_OVERLAP;
+    }
+    passages
+}
+
+fn normalized(mut vector: Vec<f32>) -> Vec<f32> {
+    let length = dot(&vector, &vector).sqrt();
+    if length > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= length);
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+impl IPassageIndex for EmbeddingIndexAdapter {
+    fn retrieve(&self, question: &str, limit: usize) -> Result<Vec<Passage>, String> {
+        Embedder::retrieve(&self.inner, question, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::{ChatError, ChatMessage, ContentMatch, FileMatch, FileQuery, ToolSpec};
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// Embeds text as counts of a few topic words, so similarity is predictable.
+    struct TopicLLM {
+        calls: Mutex<usize>,
+    }
+
+    impl ILLMService for TopicLLM {
+        fn chat(&self, _messages: &[ChatMessage], _tools: &[ToolSpec], _on_token: &mut dyn FnMut(&str), _cancel: &AtomicBool) -> Result<ChatMessage, ChatError> { unimplemented!() }
+        fn list_models(&self) -> Result<Vec<String>, String> { unimplemented!() }
+        fn pull_model(&self, _model: &str, _on_progress: Box<dyn Fn(f64) + Send>) -> Result<(), String> { unimplemented!() }
+        fn delete_model(&self, _model: &str) -> Result<(), String> { unimplemented!() }
+        fn set_model(&self, _model: &str) {}
+        fn embed(&self, _model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+            *self.calls.lock().unwrap() += texts.len();
+            Ok(texts.iter()
+                .map(|text| ["garden", "tax", "rust"].iter().map(|topic| text.matches(topic).count() as f32 + 0.1).collect())
+                .collect())
+        }
+    }
+
+    struct Files(Vec<IndexedFile>);
+
+    impl IFileIndexer for Files {
+        fn search(&self, _query: &FileQuery, _limit: usize) -> Vec<FileMatch> { vec![] }
+        fn search_content(&self, _term: &str, _limit: usize) -> Vec<ContentMatch> { vec![] }
+        fn text_files(&self) -> Vec<IndexedFile> { self.0.clone() }
+        fn start(&self) {}
+    }
+
+    fn adapter(dir: &Path, llm: Arc<TopicLLM>) -> EmbeddingIndexAdapter {
+        let files = ["garden.md", "taxes.txt", "photo.jpg"].iter()
+            .map(|name| IndexedFile { path: dir.join(name).to_string_lossy().to_string(), modified: 1 })
+            .collect();
+        let adapter = EmbeddingIndexAdapter::with_cache_dir(llm, Arc::new(Files(files)), RagSettings::default(), dir.join("cache"));
+        // Searches run on what is embedded, without the background thread
+        adapter.inner.started.store(true, Ordering::SeqCst);
+        adapter
+    }
+
+    #[test]
+    fn test_retrieves_most_similar_passage() {
+        let dir = tempdir().unwrap();
+        let garden: Vec<String> = (1..=50).map(|i| if i == 45 { "plant the garden beds".to_string() } else { format!("line {}", i) }).collect();
+        fs::write(dir.path().join("garden.md"), garden.join("\n")).unwrap();
+        fs::write(dir.path().join("taxes.txt"), "file the tax return\ntax deadline").unwrap();
+        fs::write(dir.path().join("photo.jpg"), "garden").unwrap();
+
+        let llm = Arc::new(TopicLLM { calls: Mutex::new(0) });
+        let index = adapter(dir.path(), llm.clone());
+        index.inner.sweep().unwrap();
+
+        let passages = index.retrieve("when is the tax deadline?", 1).unwrap();
+        assert_eq!(passages[0].path, dir.path().join("taxes.txt").to_string_lossy());
+        assert_eq!((passages[0].start_line, passages[0].end_line), (1, 2));
+        assert!(passages[0].text.contains("deadline"));
+
+        let passages = index.retrieve("garden", 1).unwrap();
+        assert_eq!((passages[0].start_line, passages[0].end_line), (33, 50));
+        assert!(passages[0].text.contains("plant the garden beds"));
+
+        // Unchanged files aren't embedded again
+        let calls = *llm.calls.lock().unwrap();
+        index.inner.sweep().unwrap();
+        assert_eq!(*llm.calls.lock().unwrap(), calls);
+    }
+
+    #[test]
+    fn test_embeddings_survive_restart() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("garden.md"), "garden notes").unwrap();
+        fs::write(dir.path().join("taxes.txt"), "tax notes").unwrap();
+        let first = adapter(dir.path(), Arc::new(TopicLLM { calls: Mutex::new(0) }));
+        first.inner.sweep().unwrap();
+
+        let llm = Arc::new(TopicLLM { calls: Mutex::new(0) });
+        let second = adapter(dir.path(), llm.clone());
+        let passages = second.retrieve("garden", 1).unwrap();
+        assert!(passages[0].path.ends_with("garden.md"));
+        // Only the question was embedded
+        assert_eq!(*llm.calls.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_split_passages() {
+        let text: Vec<String> = (1..=80).map(|i| format!("line {}", i)).collect();
+        let ranges: Vec<(usize, usize)> = split_passages(&text.join("\n")).into_iter().map(|(range, _)| range).collect();
+        assert_eq!(ranges, [(1, 40), (33, 72), (65, 80)]);
+        assert!(split_passages("\n\n  \n").is_empty());
+    }
+}
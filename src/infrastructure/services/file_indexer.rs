@@ -1,137 +1,660 @@
+use crate::domain::model::{ContentMatch, FileMatch, FileQuery, IndexSettings, IndexedFile};
 use crate::domain::ports::IFileIndexer;
-use walkdir::WalkDir;
-use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
+use crate::infrastructure::services::ignore_rules::IgnoreRules;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+/// Bumped when the cache layout changes; older caches are then rebuilt.
+const CACHE_VERSION: u32 = 2;
+/// A match only in the directories counts for less than one in the file name.
+const DIR_MATCH_FACTOR: f64 = 0.5;
+/// Points lost per folder between the home directory (or `/`) and the file.
+const DEPTH_PENALTY: f64 = 2.0;
+/// Points for a file modified just now, halving every `RECENCY_HALF_LIFE` seconds.
+const RECENCY_BONUS: f64 = 30.0;
+const RECENCY_HALF_LIFE: f64 = 7.0 * 86_400.0;
+/// Bytes checked for NUL to tell text from binary files.
+const SNIFF_BYTES: usize = 8192;
+/// A burst of changes (a build, a checkout) is applied once it has been quiet this long.
+const CHANGE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileRecord {
+    path: String,
+    modified: u64,
+    size: u64,
+    /// Whether the contents are indexed.
+    text: bool,
+}
+
+/// File names plus a trigram index of their contents. A file's id is its slot
+/// in `files`; removing a file empties the slot and leaves its id in the
+/// postings, where searches skip it until the next save compacts them.
+#[derive(Default)]
+struct Index {
+    files: Vec<Option<FileRecord>>,
+    ids: HashMap<String, u32>,
+    /// Trigram -> ids of the files containing it, ascending.
+    postings: HashMap<u32, Vec<u32>>,
+}
+
+/// The cache's first line, followed by the postings in binary.
+#[derive(Serialize, Deserialize)]
+struct CacheHeader {
+    version: u32,
+    files: Vec<FileRecord>,
+}
+
+impl Index {
+    fn get(&self, path: &str) -> Option<&FileRecord> {
+        self.ids.get(path).and_then(|&id| self.files[id as usize].as_ref())
+    }
+
+    fn live(&self) -> impl Iterator<Item = &FileRecord> {
+        self.files.iter().flatten()
+    }
+
+    fn insert(&mut self, record: FileRecord, trigrams: HashSet<u32>) {
+        self.remove(&record.path);
+        let id = self.files.len() as u32;
+        for trigram in trigrams {
+            self.postings.entry(trigram).or_default().push(id);
+        }
+        self.ids.insert(record.path.clone(), id);
+        self.files.push(Some(record));
+    }
+
+    fn remove(&mut self, path: &str) {
+        if let Some(id) = self.ids.remove(path) {
+            self.files[id as usize] = None;
+        }
+    }
+
+    /// Removes `path` and, for a directory, everything below it.
+    fn remove_tree(&mut self, path: &str) {
+        let dir = format!("{}/", path.trim_end_matches('/'));
+        let gone: Vec<String> = self.ids.keys()
+            .filter(|p| *p == path || p.starts_with(&dir))
+            .cloned()
+            .collect();
+        for path in gone {
+            self.remove(&path);
+        }
+    }
+
+    /// Files containing every trigram of `term`. They may still not contain
+    /// `term` itself, so callers check the file.
+    fn candidates(&self, term: &str) -> Vec<&FileRecord> {
+        let trigrams = trigrams(term.as_bytes());
+        let lists: Option<Vec<&Vec<u32>>> = trigrams.iter().map(|t| self.postings.get(t)).collect();
+        let Some(mut lists) = lists.filter(|l| !l.is_empty()) else { return vec![] };
+        lists.sort_by_key(|list| list.len());
+
+        let mut ids = lists[0].clone();
+        for list in &lists[1..] {
+            ids.retain(|id| list.binary_search(id).is_ok());
+        }
+        ids.into_iter().filter_map(|id| self.files[id as usize].as_ref()).collect()
+    }
+
+    /// The same index without removed files, renumbered.
+    fn compacted(&self) -> Index {
+        let mut renumbered = vec![None; self.files.len()];
+        let mut index = Index::default();
+        for (old, record) in self.files.iter().enumerate() {
+            if let Some(record) = record {
+                renumbered[old] = Some(index.files.len() as u32);
+                index.ids.insert(record.path.clone(), index.files.len() as u32);
+                index.files.push(Some(record.clone()));
+            }
+        }
+        for (&trigram, ids) in &self.postings {
+            let ids: Vec<u32> = ids.iter().filter_map(|&id| renumbered[id as usize]).collect();
+            if !ids.is_empty() {
+                index.postings.insert(trigram, ids);
+            }
+        }
+        index
+    }
+
+    fn write(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let header = CacheHeader { version: CACHE_VERSION, files: self.live().cloned().collect() };
+        serde_json::to_writer(&mut *out, &header)?;
+        out.write_all(b"\n")?;
+        for (trigram, ids) in &self.postings {
+            out.write_all(&trigram.to_le_bytes())?;
+            out.write_all(&(ids.len() as u32).to_le_bytes())?;
+            for id in ids {
+                out.write_all(&id.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads what `write` wrote; expects a compacted index.
+    fn read(input: &mut impl BufRead) -> Option<Index> {
+        let mut line = Vec::new();
+        input.read_until(b'\n', &mut line).ok()?;
+        let header: CacheHeader = serde_json::from_slice(&line).ok()?;
+        if header.version != CACHE_VERSION {
+            return None;
+        }
+        let mut index = Index::default();
+        for record in header.files {
+            index.ids.insert(record.path.clone(), index.files.len() as u32);
+            index.files.push(Some(record));
+        }
+
+        let mut rest = Vec::new();
+        input.read_to_end(&mut rest).ok()?;
+        let mut words = rest.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+        while let Some(trigram) = words.next() {
+            let count = words.next()? as usize;
+            let ids: Vec<u32> = words.by_ref().take(count).collect();
+            if ids.len() != count || ids.iter().any(|&id| id as usize >= index.files.len()) {
+                return None;
+            }
+            index.postings.insert(trigram, ids);
+        }
+        Some(index)
+    }
+}
+
+/// Every 3-byte window of the lowercased text, packed into a u32.
+fn trigrams(bytes: &[u8]) -> HashSet<u32> {
+    let lower = String::from_utf8_lossy(bytes).to_lowercase();
+    lower.as_bytes().windows(3)
+        .map(|w| (w[0] as u32) << 16 | (w[1] as u32) << 8 | w[2] as u32)
+        .collect()
+}
+
+/// Contents of a text file no larger than `max_size`; None for binary files.
+fn read_text(path: &Path, max_size: u64) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    fs::File::open(path).ok()?.take(max_size).read_to_end(&mut bytes).ok()?;
+    if bytes[..bytes.len().min(SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    Some(bytes)
+}
+
+/// Indexes file names and contents under the configured roots. The index is
+/// saved under `$XDG_CACHE_HOME/launch`, so a restart only re-reads files
+/// that changed, and inotify keeps it current while running.
 pub struct FileIndexerAdapter {
-    index: Arc<Mutex<Vec<String>>>,
-    home_dir: PathBuf,
+    inner: Arc<Indexer>,
+}
+
+struct Indexer {
+    home: PathBuf,
+    roots: Vec<PathBuf>,
+    ignore: Vec<String>,
+    max_content_size: u64,
+    cache_file: PathBuf,
+    index: Mutex<Index>,
 }
 
 impl FileIndexerAdapter {
-    pub fn new() -> Self {
-        let home_dir = dirs::home_dir().expect("Could not find home directory");
+    pub fn new(settings: IndexSettings) -> Self {
+        let cache_dir = dirs::cache_dir().unwrap_or_else(|| PathBuf::from(".")).join("launch");
+        Self::with_cache_dir(settings, cache_dir)
+    }
+
+    pub fn with_cache_dir(settings: IndexSettings, cache_dir: PathBuf) -> Self {
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        Self::with_home(settings, cache_dir, home)
+    }
+
+    fn with_home(settings: IndexSettings, cache_dir: PathBuf, home: PathBuf) -> Self {
+        let roots = settings.roots.iter()
+            .map(|root| match root.strip_prefix('~') {
+                Some(rest) => home.join(rest.trim_start_matches('/')),
+                None => PathBuf::from(root),
+            })
+            .collect();
         Self {
-            index: Arc::new(Mutex::new(Vec::new())),
-            home_dir,
+            inner: Arc::new(Indexer {
+                home,
+                roots,
+                ignore: settings.ignore,
+                max_content_size: settings.max_content_size,
+                cache_file: cache_dir.join("file-index"),
+                index: Mutex::new(Index::default()),
+            }),
         }
     }
 }
 
-impl IFileIndexer for FileIndexerAdapter {
-    fn search(&self, term: &str) -> Vec<String> {
-        let term_lower = term.to_lowercase();
-        let index = self.index.lock().unwrap();
-        index.iter()
-            .filter(|path| path.to_lowercase().contains(&term_lower))
-            .take(10) // Limit context to top 10 matches
-            .cloned()
-            .collect()
+impl Indexer {
+    fn load(&self) {
+        let Ok(file) = fs::File::open(&self.cache_file) else { return };
+        match Index::read(&mut BufReader::new(file)) {
+            Some(index) => *self.index.lock().unwrap() = index,
+            None => println!("Discarding unreadable file index {}", self.cache_file.display()),
+        }
+    }
+
+    fn save(&self) {
+        let compacted = self.index.lock().unwrap().compacted();
+        if let Some(dir) = self.cache_file.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        // Written aside and renamed, so a crash never leaves half an index
+        let temp = self.cache_file.with_extension("tmp");
+        let written = fs::File::create(&temp).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            compacted.write(&mut out)?;
+            out.flush()
+        });
+        match written.and_then(|_| fs::rename(&temp, &self.cache_file)) {
+            Ok(()) => *self.index.lock().unwrap() = compacted,
+            Err(e) => println!("Failed to save file index: {}", e),
+        }
+    }
+
+    /// Indexes everything under the roots and drops files that are gone.
+    fn scan(&self, mut watcher: Option<&mut RecommendedWatcher>) {
+        let mut seen = HashSet::new();
+        for root in &self.roots {
+            let rules = IgnoreRules::from_globs(root, &self.ignore);
+            self.walk(root, &rules, &mut seen, &mut watcher);
+        }
+        let mut index = self.index.lock().unwrap();
+        let gone: Vec<String> = index.live()
+            .filter(|record| !seen.contains(&record.path))
+            .map(|record| record.path.clone())
+            .collect();
+        for path in gone {
+            index.remove(&path);
+        }
+    }
+
+    fn walk(&self, dir: &Path, parent_rules: &IgnoreRules, seen: &mut HashSet<String>, watcher: &mut Option<&mut RecommendedWatcher>) {
+        let rules = parent_rules.with_gitignore(dir);
+        if let Some(watcher) = watcher {
+            // Each directory on its own, so ignored trees use no inotify watches
+            let _ = watcher.watch(dir, RecursiveMode::NonRecursive);
+        }
+        let Ok(entries) = fs::read_dir(dir) else { return };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else { continue };
+            let path = entry.path();
+            // Symlinks are skipped; they could loop or leave the root
+            if file_type.is_symlink() || rules.is_ignored(&path, file_type.is_dir()) {
+                continue;
+            }
+            if file_type.is_dir() {
+                self.walk(&path, &rules, seen, watcher);
+            } else if file_type.is_file() {
+                self.update_file(&path);
+                seen.insert(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    /// Re-reads a file unless its size and modification time are unchanged.
+    fn update_file(&self, path: &Path) {
+        let Ok(meta) = fs::metadata(path) else { return };
+        let key = path.to_string_lossy().to_string();
+        let modified = meta.modified().map(unix_secs).unwrap_or(0);
+        let unchanged = self.index.lock().unwrap().get(&key)
+            .is_some_and(|record| record.modified == modified && record.size == meta.len());
+        if unchanged {
+            return;
+        }
+        // Read without holding the lock, so searches aren't blocked
+        let text = (meta.len() <= self.max_content_size)
+            .then(|| read_text(path, self.max_content_size))
+            .flatten();
+        let record = FileRecord { path: key, modified, size: meta.len(), text: text.is_some() };
+        let trigrams = text.map(|bytes| trigrams(&bytes)).unwrap_or_default();
+        self.index.lock().unwrap().insert(record, trigrams);
+    }
+
+    /// Rules in effect in `dir`, or None if `dir` or a parent is ignored.
+    fn rules_for(&self, root: &Path, dir: &Path) -> Option<IgnoreRules> {
+        let mut rules = IgnoreRules::from_globs(root, &self.ignore).with_gitignore(root);
+        let relative = dir.strip_prefix(root).ok()?;
+        let mut current = root.to_path_buf();
+        for component in relative.components() {
+            current.push(component);
+            if rules.is_ignored(&current, true) {
+                return None;
+            }
+            rules = rules.with_gitignore(&current);
+        }
+        Some(rules)
+    }
+
+    /// Brings the index up to date for one path reported as changed.
+    fn refresh(&self, path: &Path, watcher: &mut Option<&mut RecommendedWatcher>) {
+        let Some(root) = self.roots.iter().find(|root| path.starts_with(root)) else { return };
+        let key = path.to_string_lossy().to_string();
+        let parent = path.parent().unwrap_or(root);
+        let rules = if path == root.as_path() {
+            Some(IgnoreRules::from_globs(root, &self.ignore))
+        } else {
+            self.rules_for(root, parent)
+        };
+
+        match (rules, fs::symlink_metadata(path)) {
+            // A changed or removed .gitignore can hide or reveal anything in its directory
+            (Some(rules), _) if path.file_name().is_some_and(|n| n == ".gitignore") => {
+                self.index.lock().unwrap().remove_tree(&parent.to_string_lossy());
+                let rules = self.rules_for(root, parent.parent().unwrap_or(root)).unwrap_or(rules);
+                self.walk(parent, &rules, &mut HashSet::new(), watcher);
+            },
+            (Some(rules), Ok(meta)) if meta.is_dir() && !rules.is_ignored(path, true) => {
+                self.walk(path, &rules, &mut HashSet::new(), watcher);
+            },
+            (Some(rules), Ok(meta)) if meta.is_file() && !rules.is_ignored(path, false) => {
+                self.update_file(path);
+            },
+            _ => self.index.lock().unwrap().remove_tree(&key),
+        }
     }
 
-    fn index_home(&self) {
-        let index_clone = self.index.clone();
-        let home = self.home_dir.clone();
-        
-        std::thread::spawn(move || {
-            let mut paths = Vec::new();
-            println!("Starting file indexing of {}...", home.display());
-            
-            // Allowed extensions for "source code / documents" awareness
-            let allowed_extensions: Vec<&str> = vec![
-                "md", "txt", "rs", "toml", "json", "yaml", "yml", "js", "ts", "py", "sh", "html", "css"
-            ];
-
-            for entry in WalkDir::new(&home)
-                .max_depth(5) 
-                .into_iter()
-                .filter_entry(|e| {
-                    let name = e.file_name().to_string_lossy();
-                    // strict exclusions
-                    !name.starts_with('.') && 
-                    name != "node_modules" && 
-                    name != "target" && 
-                    name != "dist" && 
-                    name != "build" &&
-                    name != "venv"
-                })
-                .filter_map(|e| e.ok()) 
+    fn run(&self) {
+        self.load();
+        let (sender, receiver) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event
+                && !event.kind.is_access()
             {
-                if entry.file_type().is_file() {
-                    let path = entry.path();
-                    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                        if allowed_extensions.contains(&ext.to_lowercase().as_str()) {
-                            paths.push(path.to_string_lossy().to_string());
-                        }
-                    }
-                }
+                let _ = sender.send(event.paths);
             }
-            
-            let mut index = index_clone.lock().unwrap();
-            *index = paths;
-            println!("Indexing complete. Found {} locations.", index.len());
         });
+        let mut watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                println!("Failed to start file index watcher: {}", e);
+                None
+            }
+        };
+
+        println!("Updating file index of {:?}...", self.roots);
+        self.scan(watcher.as_mut());
+        self.save();
+        println!("File index up to date: {} files.", self.index.lock().unwrap().files.len());
+
+        let cache_dir = self.cache_file.parent().map(Path::to_path_buf).unwrap_or_default();
+        while let Ok(paths) = receiver.recv() {
+            let mut changed: HashSet<PathBuf> = paths.into_iter().collect();
+            while let Ok(paths) = receiver.recv_timeout(CHANGE_DEBOUNCE) {
+                changed.extend(paths);
+            }
+            // Saving the index must not count as a change to index
+            changed.retain(|path| !path.starts_with(&cache_dir));
+            if changed.is_empty() {
+                continue;
+            }
+            let mut watcher = watcher.as_mut();
+            for path in &changed {
+                self.refresh(path, &mut watcher);
+            }
+            self.save();
+        }
+    }
+
+    /// Fuzzy-matches the file name, falling back to the path below home.
+    /// Shallow and recently modified files rank higher; ties go to the shorter path.
+    fn search(&self, query: &FileQuery, limit: usize) -> Vec<FileMatch> {
+        let matcher = SkimMatcherV2::default();
+        let now = unix_secs(SystemTime::now());
+        let text = query.text.trim();
+        let home = self.home.to_string_lossy();
+        let within: Vec<String> = query.within.iter()
+            .map(|dir| match dir.strip_prefix('~') {
+                Some(rest) => format!("{}{}", home.trim_end_matches('/'), rest),
+                None => dir.clone(),
+            })
+            .collect();
+
+        let index = self.index.lock().unwrap();
+        let mut matches: Vec<(f64, &str)> = index.live()
+            .filter(|record| matches_filters(record, query, &within, now))
+            .filter_map(|record| {
+                let below_home = record.path.strip_prefix(home.trim_end_matches('/')).filter(|rest| rest.starts_with('/'));
+                let shown = below_home.unwrap_or(&record.path);
+                let name = shown.rsplit('/').next().unwrap_or(shown);
+                let score = if text.is_empty() {
+                    0.0
+                } else if let Some(score) = matcher.fuzzy_match(name, text) {
+                    score as f64
+                } else {
+                    matcher.fuzzy_match(shown, text)? as f64 * DIR_MATCH_FACTOR
+                };
+                let depth = shown.matches('/').count().saturating_sub(1) as f64;
+                let age = now.saturating_sub(record.modified) as f64;
+                let recency = RECENCY_BONUS * 0.5f64.powf(age / RECENCY_HALF_LIFE);
+                Some((score - depth * DEPTH_PENALTY + recency, record.path.as_str()))
+            })
+            .collect();
+        matches.sort_by(|(a, path_a), (b, path_b)| b.total_cmp(a).then(path_a.len().cmp(&path_b.len())));
+
+        matches.into_iter()
+            .take(limit)
+            .map(|(score, path)| FileMatch { path: path.to_string(), score })
+            .collect()
+    }
+
+    fn search_content(&self, term: &str, limit: usize) -> Vec<ContentMatch> {
+        let term = term.to_lowercase();
+        if term.len() < 3 {
+            return vec![];
+        }
+        let candidates: Vec<FileRecord> = self.index.lock().unwrap()
+            .candidates(&term).into_iter().cloned().collect();
+
+        candidates.into_iter()
+            .filter_map(|record| {
+                let bytes = read_text(Path::new(&record.path), self.max_content_size)?;
+                let text = String::from_utf8_lossy(&bytes);
+                let (i, line) = text.lines().enumerate().find(|(_, line)| line.to_lowercase().contains(&term))?;
+                Some(ContentMatch { path: record.path, line_number: i + 1, line: line.trim().to_string() })
+            })
+            .take(limit)
+            .collect()
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// `ext:`, `in:` and `modified:` filters; several values of one filter are alternatives.
+fn matches_filters(record: &FileRecord, query: &FileQuery, within: &[String], now: u64) -> bool {
+    let name = record.path.rsplit('/').next().unwrap_or(&record.path);
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+    if !query.extensions.is_empty() && !extension.is_some_and(|ext| query.extensions.contains(&ext)) {
+        return false;
+    }
+    let in_dir = |dir: &String| {
+        if dir.starts_with('/') {
+            record.path.starts_with(&format!("{}/", dir))
+        } else {
+            record.path.contains(&format!("/{}/", dir))
+        }
+    };
+    if !within.is_empty() && !within.iter().any(in_dir) {
+        return false;
+    }
+    let age = now.saturating_sub(record.modified);
+    query.modified_within.is_none_or(|max| age <= max) && query.modified_before.is_none_or(|min| age >= min)
+}
+
+impl IFileIndexer for FileIndexerAdapter {
+    fn search(&self, query: &FileQuery, limit: usize) -> Vec<FileMatch> {
+        self.inner.search(query, limit)
+    }
+
+    fn search_content(&self, term: &str, limit: usize) -> Vec<ContentMatch> {
+        self.inner.search_content(term, limit)
+    }
+
+    fn text_files(&self) -> Vec<IndexedFile> {
+        self.inner.index.lock().unwrap().live()
+            .filter(|record| record.text)
+            .map(|record| IndexedFile { path: record.path.clone(), modified: record.modified })
+            .collect()
+    }
+
+    fn start(&self) {
+        let inner = self.inner.clone();
+        std::thread::spawn(move || inner.run());
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs::File;
-    use std::io::Write;
     use tempfile::tempdir;
 
+    /// A home directory with a project, a dotfile and ignored build output.
+    fn home() -> tempfile::TempDir {
+        let dir = tempdir().unwrap();
+        let home = dir.path().join("home");
+        fs::create_dir_all(home.join("project/target")).unwrap();
+        fs::create_dir_all(home.join("project/logs")).unwrap();
+        fs::write(home.join("notes.txt"), "Shopping list\nBuy OAT milk\n").unwrap();
+        fs::write(home.join(".secret"), "oat milk").unwrap();
+        fs::write(home.join("project/.gitignore"), "logs/\n").unwrap();
+        fs::write(home.join("project/main.rs"), "fn main() {\n    println!(\"oat milk\");\n}\n").unwrap();
+        fs::write(home.join("project/target/out.rs"), "oat milk").unwrap();
+        fs::write(home.join("project/logs/run.txt"), "oat milk").unwrap();
+        fs::write(home.join("project/data.bin"), b"oat\0milk").unwrap();
+        dir
+    }
+
+    fn indexer(dir: &Path) -> FileIndexerAdapter {
+        let settings = IndexSettings { roots: vec![dir.join("home").to_string_lossy().to_string()], ..Default::default() };
+        FileIndexerAdapter::with_home(settings, dir.join("cache"), dir.join("home"))
+    }
+
+    fn names(indexer: &FileIndexerAdapter, query: &str) -> Vec<String> {
+        indexer.search(&FileQuery::parse(query), 10).into_iter()
+            .map(|m| m.path.rsplit_once("/home/").unwrap().1.to_string())
+            .collect()
+    }
+
+    fn matches(indexer: &FileIndexerAdapter, term: &str) -> Vec<(String, usize)> {
+        let mut found: Vec<(String, usize)> = indexer.search_content(term, 10).into_iter()
+            .map(|m| (Path::new(&m.path).file_name().unwrap().to_string_lossy().to_string(), m.line_number))
+            .collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn test_indexes_names_and_contents_respecting_ignores() {
+        let dir = home();
+        let indexer = indexer(dir.path());
+        indexer.inner.scan(None);
+
+        assert_eq!(matches(&indexer, "oat milk"), [("main.rs".to_string(), 2), ("notes.txt".to_string(), 2)]);
+        assert!(names(&indexer, "shopping").is_empty());
+        assert_eq!(names(&indexer, "notes"), ["notes.txt"]);
+        // Binary files are found by name only
+        assert_eq!(names(&indexer, "data.bin"), ["project/data.bin"]);
+        assert!(indexer.search_content("oa", 10).is_empty());
+        assert_eq!(indexer.text_files().len(), 2);
+    }
+
     #[test]
-    fn test_indexing_files() {
-        // Create a temp directory
+    fn test_index_survives_restart() {
+        let dir = home();
+        let first = indexer(dir.path());
+        first.inner.scan(None);
+        first.inner.save();
+
+        let second = indexer(dir.path());
+        second.inner.load();
+        assert_eq!(matches(&second, "shopping"), [("notes.txt".to_string(), 1)]);
+        assert_eq!(names(&second, "main.rs"), ["project/main.rs"]);
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let dir = home();
+        let indexer = indexer(dir.path());
+        indexer.inner.scan(None);
+        let home = dir.path().join("home");
+
+        fs::write(home.join("notes.txt"), "nothing here").unwrap();
+        fs::create_dir(home.join("new")).unwrap();
+        fs::write(home.join("new/todo.md"), "call about the oat milk").unwrap();
+        fs::write(home.join("project/logs/more.txt"), "oat milk").unwrap();
+        fs::remove_file(home.join("project/main.rs")).unwrap();
+        for path in ["notes.txt", "new", "project/logs/more.txt", "project/main.rs"] {
+            indexer.inner.refresh(&home.join(path), &mut None);
+        }
+
+        assert_eq!(matches(&indexer, "oat milk"), [("todo.md".to_string(), 1)]);
+        assert!(names(&indexer, "main.rs").is_empty());
+    }
+
+    #[test]
+    fn test_removed_gitignore_reveals_files() {
+        let dir = home();
+        let indexer = indexer(dir.path());
+        indexer.inner.scan(None);
+        let home = dir.path().join("home");
+        assert!(names(&indexer, "run.txt").is_empty());
+
+        fs::remove_file(home.join("project/.gitignore")).unwrap();
+        indexer.inner.refresh(&home.join("project/.gitignore"), &mut None);
+
+        assert_eq!(names(&indexer, "run.txt"), ["project/logs/run.txt"]);
+        assert_eq!(names(&indexer, "main.rs"), ["project/main.rs"]);
+    }
+
+    #[test]
+    fn test_ranking_and_filters() {
         let dir = tempdir().unwrap();
-        let path = dir.path();
-
-        // Create a non-hidden subdirectory to act as home, 
-        // because tempdir() creates a hidden folder starting with .tmp, 
-        // which our indexer logic correctly ignores.
-        let home_path = path.join("home");
-        std::fs::create_dir(&home_path).unwrap();
-
-        // Create some files
-        let file1_path = home_path.join("test.txt");
-        let mut file1 = File::create(&file1_path).unwrap();
-        writeln!(file1, "Hello World").unwrap();
-
-        let file2_path = home_path.join("ignored.bin");
-        let mut file2 = File::create(&file2_path).unwrap();
-        writeln!(file2, "Binary data").unwrap();
-
-        let nested_dir = home_path.join("nested");
-        std::fs::create_dir(&nested_dir).unwrap();
-        let file3_path = nested_dir.join("deep.rs");
-        let mut file3 = File::create(&file3_path).unwrap();
-        writeln!(file3, "fn main() {{}}").unwrap();
-
-        // Mock indexer with this temp dir as home
-        let indexer = FileIndexerAdapter {
-            index: Arc::new(Mutex::new(Vec::new())),
-            home_dir: home_path,
-        };
+        let home = dir.path().join("home");
+        let days_ago = |days: u64| SystemTime::now() - Duration::from_secs(days * 86_400);
+        for (path, age) in [
+            ("report.md", 40),
+            ("work/q3/final/report.md", 40),
+            ("reports/summary.txt", 40),
+            ("a/plan.md", 0),
+            ("b/plan.txt", 60),
+        ] {
+            let path = home.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::File::create(&path).unwrap().set_modified(days_ago(age)).unwrap();
+        }
+        let indexer = indexer(dir.path());
+        indexer.inner.scan(None);
 
-        // Run index_home 
-        indexer.index_home();
-        
-        // Wait for thread
-        std::thread::sleep(std::time::Duration::from_secs(2));
-
-        // Create a search term (should match "test" in "test.txt")
-        let results = indexer.search("test");
-        assert!(!results.is_empty(), "Should find test.txt");
-        // We expect the full path
-        assert!(results.iter().any(|p| p.contains("test.txt")), "Results should contain test.txt");
-
-        // Check index directly
-        let index = indexer.index.lock().unwrap();
-        assert!(index.iter().any(|p| p.contains("test.txt")), "Index should contain test.txt");
-        assert!(index.iter().any(|p| p.contains("deep.rs")), "Index should contain deep.rs");
-        
-        // Should NOT contain ignored.bin (not in allowed extensions)
-        assert!(!index.iter().any(|p| p.contains("ignored.bin")), "Index should NOT contain ignored.bin");
+        // Name over folder matches, shallow over deep
+        assert_eq!(names(&indexer, "report"), ["report.md", "work/q3/final/report.md", "reports/summary.txt"]);
+        // Recently modified first
+        assert_eq!(names(&indexer, "plan"), ["a/plan.md", "b/plan.txt"]);
+
+        assert_eq!(names(&indexer, "ext:txt"), ["reports/summary.txt", "b/plan.txt"]);
+        assert_eq!(names(&indexer, "report in:~/work"), ["work/q3/final/report.md"]);
+        assert_eq!(names(&indexer, "in:reports"), ["reports/summary.txt"]);
+        assert_eq!(names(&indexer, "modified:<7d"), ["a/plan.md"]);
+        assert_eq!(names(&indexer, "ext:md,txt modified:>50d"), ["b/plan.txt"]);
+    }
+
+    #[test]
+    fn test_query_syntax() {
+        let query = FileQuery::parse("ext:rs,.TOML in:~/w modified:<2w main fn modified:soon");
+        assert_eq!(query.text, "main fn modified:soon");
+        assert_eq!(query.extensions, ["rs", "toml"]);
+        assert_eq!(query.within, ["~/w"]);
+        assert_eq!(query.modified_within, Some(14 * 86_400));
+        assert_eq!(query.modified_before, None);
+        assert!(!FileQuery::parse("ext: plain").has_filters());
     }
 }
@@ -0,0 +1,101 @@
+use crate::domain::model::UsageRecord;
+use crate::domain::ports::IUsageHistory;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Oldest records are dropped past this, which also bounds ranking cost.
+const MAX_RECORDS: usize = 2000;
+
+/// Launch history persisted next to `settings.json`.
+pub struct HistoryStore {
+    path: PathBuf,
+    records: Mutex<Vec<UsageRecord>>,
+}
+
+impl HistoryStore {
+    pub fn new() -> Self {
+        let path = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("launch")
+            .join("history.json");
+        Self::with_path(path)
+    }
+
+    pub fn with_path(path: PathBuf) -> Self {
+        let records = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            records: Mutex::new(records),
+        }
+    }
+
+    fn save(&self, records: &[UsageRecord]) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string(records).map_err(|e| e.to_string())?;
+        fs::write(&self.path, json).map_err(|e| e.to_string())
+    }
+}
+
+impl Default for HistoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IUsageHistory for HistoryStore {
+    fn record(&self, key: &str, query: &str) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut records = self.records.lock().unwrap();
+        records.push(UsageRecord {
+            key: key.to_string(),
+            query: query.to_string(),
+            timestamp,
+        });
+        if records.len() > MAX_RECORDS {
+            let excess = records.len() - MAX_RECORDS;
+            records.drain(..excess);
+        }
+
+        if let Err(e) = self.save(&records) {
+            println!("Failed to save launch history: {}", e);
+        }
+    }
+
+    fn records(&self) -> Vec<UsageRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_history_persists() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.json");
+
+        let store = HistoryStore::with_path(path.clone());
+        store.record("launch:firefox", "fi");
+        store.record("launch:gnome-terminal", "");
+
+        let reloaded = HistoryStore::with_path(path);
+        let records = reloaded.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].key, "launch:firefox");
+        assert_eq!(records[0].query, "fi");
+        assert_eq!(records[1].key, "launch:gnome-terminal");
+    }
+}
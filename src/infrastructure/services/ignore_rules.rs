@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One line of a `.gitignore`, or an ignore glob from the settings.
+#[derive(Debug)]
+struct Rule {
+    /// Directory the pattern is relative to.
+    base: PathBuf,
+    pattern: Vec<char>,
+    /// `!pattern`: re-includes what an earlier rule excluded.
+    negated: bool,
+    /// `pattern/`: only matches directories.
+    dir_only: bool,
+    /// A pattern with a slash matches the path from `base`, otherwise just the name.
+    anchored: bool,
+}
+
+/// Gitignore rules in effect for a directory: its own `.gitignore` and
+/// those of its parents up to the indexed root.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Arc<Rule>>,
+}
+
+impl IgnoreRules {
+    /// Rules from globs written like `.gitignore` lines, relative to `base`.
+    pub fn from_globs(base: &Path, globs: &[String]) -> Self {
+        let mut rules = Self::default();
+        rules.extend(base, globs.iter().map(String::as_str));
+        rules
+    }
+
+    /// These rules plus those of `dir/.gitignore`, if there is one.
+    pub fn with_gitignore(&self, dir: &Path) -> Self {
+        let mut rules = self.clone();
+        if let Ok(content) = fs::read_to_string(dir.join(".gitignore")) {
+            rules.extend(dir, content.lines());
+        }
+        rules
+    }
+
+    fn extend<'a>(&mut self, base: &Path, lines: impl Iterator<Item = &'a str>) {
+        self.rules.extend(lines.filter_map(|line| parse(base, line)).map(Arc::new));
+    }
+
+    /// Whether `path` is excluded. The last matching rule decides, so a later
+    /// `!pattern` wins over an earlier one.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let name: Vec<char> = path.file_name().map(|n| n.to_string_lossy().chars().collect()).unwrap_or_default();
+        self.rules.iter().rev()
+            .find(|rule| {
+                if rule.dir_only && !is_dir {
+                    return false;
+                }
+                let Ok(relative) = path.strip_prefix(&rule.base) else { return false };
+                if rule.anchored {
+                    let relative: Vec<char> = relative.to_string_lossy().chars().collect();
+                    glob_match(&rule.pattern, &relative)
+                } else {
+                    glob_match(&rule.pattern, &name)
+                }
+            })
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+fn parse(base: &Path, line: &str) -> Option<Rule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let anchored = line.contains('/');
+    let pattern = line.strip_prefix('/').unwrap_or(line);
+    if pattern.is_empty() {
+        return None;
+    }
+    Some(Rule { base: base.to_path_buf(), pattern: pattern.chars().collect(), negated, dir_only, anchored })
+}
+
+/// Gitignore glob matching: `*` and `?` stay within a path component,
+/// `**` spans any number of them, `[a-z]`/`[!a-z]` match a class.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            let rest = rest.strip_prefix(&['/']).unwrap_or(rest);
+            // A trailing `**` matches everything inside; otherwise try each component start
+            rest.is_empty() || (0..=text.len())
+                .filter(|&i| i == 0 || text[i - 1] == '/')
+                .any(|i| glob_match(rest, &text[i..]))
+        },
+        Some('*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match(&pattern[1..], &text[i..])),
+        Some('?') => text.first().is_some_and(|&c| c != '/') && glob_match(&pattern[1..], &text[1..]),
+        Some('[') => match class_match(&pattern[1..], text.first().copied()) {
+            Some((matched, len)) => matched && glob_match(&pattern[1 + len..], &text[1..]),
+            // No closing bracket: a literal `[`
+            None => text.first() == Some(&'[') && glob_match(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..]),
+        Some(&c) => text.first() == Some(&c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Matches `c` against a class body (after `[`). Returns whether it matched
+/// and how many pattern characters the class used; None if the class isn't
+/// closed or there is no character to match.
+fn class_match(class: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some('!' | '^'));
+    let start = usize::from(negated);
+    // A `]` right after the opening bracket is a literal
+    let end = class.iter().skip(start + 1).position(|&ch| ch == ']')? + start + 1;
+    let body = &class[start..end];
+    let c = c.filter(|&c| c != '/')?;
+
+    let mut matched = false;
+    let mut i = 0;
+    while i < body.len() {
+        if i + 2 < body.len() && body[i + 1] == '-' {
+            matched |= (body[i]..=body[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= body[i] == c;
+            i += 1;
+        }
+    }
+    Some((matched != negated, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(lines: &[&str]) -> IgnoreRules {
+        let globs: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        IgnoreRules::from_globs(Path::new("/repo"), &globs)
+    }
+
+    #[test]
+    fn test_gitignore_patterns() {
+        let rules = rules(&["# build output", "*.log", "/target", "docs/*.pdf", "cache/", "!keep.log", "a/**/z", "[Tt]emp?"]);
+        let ignored = |path: &str, is_dir| rules.is_ignored(Path::new(path), is_dir);
+
+        assert!(ignored("/repo/app.log", false));
+        assert!(ignored("/repo/src/deep/app.log", false));
+        assert!(!ignored("/repo/keep.log", false));
+        assert!(ignored("/repo/target", true));
+        assert!(!ignored("/repo/src/target", true));
+        assert!(ignored("/repo/docs/manual.pdf", false));
+        assert!(!ignored("/repo/docs/en/manual.pdf", false));
+        assert!(ignored("/repo/src/cache", true));
+        assert!(!ignored("/repo/src/cache", false));
+        assert!(ignored("/repo/a/z", false));
+        assert!(ignored("/repo/a/b/c/z", false));
+        assert!(ignored("/repo/Temp1", false));
+        assert!(!ignored("/repo/temp12", false));
+    }
+
+    #[test]
+    fn test_nested_gitignore_applies_below_its_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let sub = root.path().join("web");
+        fs::create_dir(&sub).unwrap();
+        fs::write(sub.join(".gitignore"), "*.min.js\n").unwrap();
+
+        let top = IgnoreRules::default().with_gitignore(root.path());
+        let nested = top.with_gitignore(&sub);
+        assert!(nested.is_ignored(&sub.join("app.min.js"), false));
+        assert!(!top.is_ignored(&root.path().join("app.min.js"), false));
+    }
+}
@@ -1,42 +1,79 @@
+use crate::domain::model::{ChatError, ChatMessage, ChatRole, LlmBackend, ToolCall, ToolSpec};
 use crate::domain::ports::ILLMService;
 use ollama_rs::Ollama;
-use ollama_rs::generation::completion::request::GenerationRequest;
+use ollama_rs::error::OllamaError;
+use ollama_rs::generation::chat::{ChatMessage as OllamaMessage, MessageRole};
+use ollama_rs::generation::chat::request::ChatMessageRequest;
+use ollama_rs::generation::embeddings::request::{EmbeddingsInput, GenerateEmbeddingsRequest};
+use ollama_rs::generation::tools::{ToolCall as OllamaToolCall, ToolCallFunction, ToolInfo};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::runtime::Runtime;
 
+/// How often a running generation checks whether it was cancelled.
+const CANCEL_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+
 pub struct OllamaAdapter {
     runtime: Arc<Runtime>,
     client: Ollama,
     model: std::sync::Mutex<String>,
+    /// `host:port` of a server on this machine, which is started when needed.
+    local_address: Option<String>,
 }
 
 impl OllamaAdapter {
     pub fn new(model: &str) -> Self {
+        Self::with_backend(&LlmBackend::default(), model)
+    }
+
+    pub fn with_backend(backend: &LlmBackend, model: &str) -> Self {
         let runtime = Arc::new(Runtime::new().expect("Failed to create Tokio runtime"));
-        let client = Ollama::default();
+        let timeout = std::time::Duration::from_secs(backend.timeout_secs);
+        let http = reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .read_timeout(timeout)
+            .build()
+            .unwrap_or_default();
+
+        let url = reqwest::Url::parse(&backend.base_url).ok();
+        let server = url.as_ref().and_then(|url| Some((url.scheme(), url.host_str()?, url.port_or_known_default()?)));
+        let (client, local_address) = match server {
+            Some((scheme, host, port)) => {
+                let local = ["127.0.0.1", "localhost", "[::1]"].contains(&host);
+                (Ollama::new_with_client(format!("{}://{}", scheme, host), port, http), local.then(|| format!("{}:{}", host, port)))
+            },
+            None => {
+                println!("Invalid Ollama URL {}, using the default", backend.base_url);
+                (Ollama::default(), Some("127.0.0.1:11434".to_string()))
+            },
+        };
         Self {
             runtime,
             client,
             model: std::sync::Mutex::new(model.to_string()),
+            local_address,
         }
     }
 
     fn ensure_server_running(&self) -> Result<(), String> {
+        // A remote server is someone else's to start
+        let Some(address) = self.local_address.as_deref() else { return Ok(()) };
         // Only run on Linux for now
         if cfg!(target_os = "linux") {
-            // Check if listening on default port
-             if std::net::TcpStream::connect("127.0.0.1:11434").is_err() {
+            // Check if listening
+             if std::net::TcpStream::connect(address).is_err() {
                  println!("Ollama not running. Starting server...");
                  // Attempt to start
                  let _ = std::process::Command::new("ollama")
                      .arg("serve")
+                     .env("OLLAMA_HOST", address)
                      .spawn()
                      .map_err(|e| format!("Failed to auto-start ollama: {}. Is it installed?", e))?;
                  
                  // Wait for up to 5 seconds
                  for _ in 0..10 {
                      std::thread::sleep(std::time::Duration::from_millis(500));
-                     if std::net::TcpStream::connect("127.0.0.1:11434").is_ok() {
+                     if std::net::TcpStream::connect(address).is_ok() {
                          return Ok(());
                      }
                  }
@@ -48,28 +85,63 @@ impl OllamaAdapter {
 }
 
 impl ILLMService for OllamaAdapter {
-    fn query(&self, prompt: &str, context: Option<String>) -> Result<String, String> {
+    fn chat(&self, messages: &[ChatMessage], tools: &[ToolSpec], on_token: &mut dyn FnMut(&str), cancel: &AtomicBool) -> Result<ChatMessage, ChatError> {
         self.ensure_server_running()?;
         let model = self.model.lock().unwrap().clone();
-        
-        let mut full_prompt = prompt.to_string();
-        if let Some(ctx) = context {
-            full_prompt = format!(
-                "Context: The following relevant files/locations were found on the user's system: {}\n\nUser Question: {}\n\nPlease use the context to provide a more accurate and helpful answer.",
-                ctx, prompt
-            );
-        }
-
-        let prompt_string = full_prompt;
+        let messages = messages.iter()
+            .map(|m| {
+                let role = match m.role {
+                    ChatRole::System => MessageRole::System,
+                    ChatRole::User => MessageRole::User,
+                    ChatRole::Assistant => MessageRole::Assistant,
+                    ChatRole::Tool => MessageRole::Tool,
+                };
+                let mut message = OllamaMessage::new(role, m.content.clone());
+                message.tool_calls = m.tool_calls.iter()
+                    .map(|call| OllamaToolCall {
+                        function: ToolCallFunction { name: call.name.clone(), arguments: call.arguments.clone() },
+                    })
+                    .collect();
+                message
+            })
+            .collect();
+        let tools = tools.iter().map(tool_info).collect::<Result<Vec<_>, _>>()?;
+        let with_tools = !tools.is_empty();
         let client = self.client.clone();
         
         // Blocking call to async code
         self.runtime.block_on(async move {
-            let request = GenerationRequest::new(model, prompt_string);
-            match client.generate(request).await {
-                Ok(res) => Ok(res.response),
-                Err(e) => Err(format!("Ollama Error: {}", e)),
+            use tokio_stream::StreamExt;
+            let request = ChatMessageRequest::new(model, messages).tools(tools);
+            let mut stream = client.send_chat_messages_stream(request).await
+                .map_err(|e| match e {
+                    // {"error":"registry.ollama.ai/library/gemma:latest does not support tools"}
+                    OllamaError::Other(body) if with_tools && body.contains("does not support tools") => ChatError::ToolsUnsupported(body),
+                    e => ChatError::Other(format!("Ollama Error: {}", e)),
+                })?;
+
+            let mut reply = ChatMessage::new(ChatRole::Assistant, "");
+            loop {
+                // Dropping the stream closes the connection, which stops Ollama generating
+                let next = tokio::select! {
+                    next = stream.next() => next,
+                    _ = cancelled(cancel) => break,
+                };
+                match next {
+                    Some(Ok(res)) => {
+                        on_token(&res.message.content);
+                        reply.content.push_str(&res.message.content);
+                        // Ollama doesn't number calls; the position identifies them
+                        for call in res.message.tool_calls {
+                            let id = format!("call_{}", reply.tool_calls.len());
+                            reply.tool_calls.push(ToolCall { id, name: call.function.name, arguments: call.function.arguments });
+                        }
+                    },
+                    Some(Err(())) => return Err(ChatError::Other("Ollama Error: the answer stream broke off".to_string())),
+                    None => break,
+                }
             }
+            Ok(reply)
         })
     }
 
@@ -122,8 +194,34 @@ impl ILLMService for OllamaAdapter {
           })
      }
 
+     fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+         self.ensure_server_running()?;
+         let client = self.client.clone();
+         let request = GenerateEmbeddingsRequest::new(model.to_string(), EmbeddingsInput::Multiple(texts.to_vec()));
+         self.runtime.block_on(async move {
+             match client.generate_embeddings(request).await {
+                 Ok(res) => Ok(res.embeddings),
+                 Err(e) => Err(format!("Failed to embed text: {}", e)),
+             }
+         })
+     }
+
      fn set_model(&self, model: &str) {
          let mut m = self.model.lock().unwrap();
          *m = model.to_string();
      }
  }
+
+fn tool_info(spec: &ToolSpec) -> Result<ToolInfo, String> {
+    let info = serde_json::json!({
+        "type": "Function",
+        "function": { "name": spec.name, "description": spec.description, "parameters": spec.parameters },
+    });
+    serde_json::from_value(info).map_err(|e| format!("Invalid tool {}: {}", spec.name, e))
+}
+
+async fn cancelled(cancel: &AtomicBool) {
+    while !cancel.load(Ordering::Relaxed) {
+        tokio::time::sleep(CANCEL_POLL).await;
+    }
+}
@@ -0,0 +1,133 @@
+use crate::domain::model::{ChatError, ChatMessage, LlmBackend, LlmProtocol, RagSettings, ToolSpec};
+use crate::domain::ports::ILLMService;
+use crate::infrastructure::services::llm_adapter::OllamaAdapter;
+use crate::infrastructure::services::openai_adapter::OpenAiAdapter;
+use crate::infrastructure::services::settings_store::SettingsStore;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+/// Adapter speaking the backend's protocol.
+pub fn adapter_for(backend: &LlmBackend, model: &str) -> Arc<dyn ILLMService + Send + Sync> {
+    match backend.protocol {
+        LlmProtocol::Ollama => Arc::new(OllamaAdapter::with_backend(backend, model)),
+        LlmProtocol::OpenAi => Arc::new(OpenAiAdapter::new(backend, model)),
+    }
+}
+
+/// The backend computing embeddings: the one named in the RAG settings, or
+/// a local Ollama. Embedding sends file contents to the backend, so one on
+/// another machine has to be allowed explicitly.
+pub fn embedding_backend(rag: &RagSettings, backends: &[LlmBackend]) -> Result<LlmBackend, String> {
+    let backend = match &rag.embedding_backend {
+        Some(name) => backends.iter().find(|b| b.name == *name).cloned()
+            .ok_or_else(|| format!("There is no backend named '{}' for embeddings", name))?,
+        None => LlmBackend::default(),
+    };
+    if !backend.is_local() && !rag.allow_remote_embeddings {
+        return Err(format!(
+            "Not embedding files with {} at {}, which would upload them; set \"allow_remote_embeddings\" under \"rag\" to allow it",
+            backend.name, backend.base_url,
+        ));
+    }
+    Ok(backend)
+}
+
+type Adapter = Arc<dyn ILLMService + Send + Sync>;
+
+/// Sends requests to the backend selected in the settings, switching when
+/// the selection or its configuration changes. Embeddings go to their own
+/// backend (see [`embedding_backend`]).
+pub struct LlmRouter {
+    settings: Arc<SettingsStore>,
+    current: Mutex<Option<(LlmBackend, Adapter)>>,
+    embedder: Mutex<Option<(LlmBackend, Adapter)>>,
+}
+
+impl LlmRouter {
+    pub fn new(settings: Arc<SettingsStore>) -> Self {
+        Self { settings, current: Mutex::new(None), embedder: Mutex::new(None) }
+    }
+
+    fn backend(&self) -> Adapter {
+        Self::adapter(&self.current, self.settings.get_llm_backend(), &self.settings.get_ai_model())
+    }
+
+    /// The adapter in `slot` if it is for `selected`, or a new one.
+    fn adapter(slot: &Mutex<Option<(LlmBackend, Adapter)>>, selected: LlmBackend, model: &str) -> Adapter {
+        let mut current = slot.lock().unwrap();
+        match current.as_ref() {
+            Some((backend, adapter)) if *backend == selected => adapter.clone(),
+            _ => {
+                let adapter = adapter_for(&selected, model);
+                *current = Some((selected, adapter.clone()));
+                adapter
+            },
+        }
+    }
+}
+
+impl ILLMService for LlmRouter {
+    fn chat(&self, messages: &[ChatMessage], tools: &[ToolSpec], on_token: &mut dyn FnMut(&str), cancel: &AtomicBool) -> Result<ChatMessage, ChatError> {
+        self.backend().chat(messages, tools, on_token, cancel)
+    }
+
+    fn list_models(&self) -> Result<Vec<String>, String> {
+        self.backend().list_models()
+    }
+
+    fn pull_model(&self, model: &str, on_progress: Box<dyn Fn(f64) + Send>) -> Result<(), String> {
+        self.backend().pull_model(model, on_progress)
+    }
+
+    fn delete_model(&self, model: &str) -> Result<(), String> {
+        self.backend().delete_model(model)
+    }
+
+    fn set_model(&self, model: &str) {
+        self.backend().set_model(model)
+    }
+
+    fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let backend = embedding_backend(&self.settings.get_rag_settings(), &self.settings.get_llm_backends())?;
+        Self::adapter(&self.embedder, backend, model).embed(model, texts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backend(name: &str, base_url: &str) -> LlmBackend {
+        LlmBackend { name: name.to_string(), protocol: LlmProtocol::OpenAi, base_url: base_url.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_embedding_backend_is_explicit_and_local() {
+        let backends = [backend("llama.cpp", "http://localhost:8080/v1"), backend("Cloud", "https://api.example.com/v1")];
+        let mut rag = RagSettings::default();
+
+        // Not whichever backend is selected for chat
+        assert_eq!(embedding_backend(&rag, &backends).unwrap(), LlmBackend::default());
+
+        rag.embedding_backend = Some("llama.cpp".to_string());
+        assert_eq!(embedding_backend(&rag, &backends).unwrap().name, "llama.cpp");
+
+        rag.embedding_backend = Some("Cloud".to_string());
+        assert!(embedding_backend(&rag, &backends).is_err());
+        rag.allow_remote_embeddings = true;
+        assert_eq!(embedding_backend(&rag, &backends).unwrap().name, "Cloud");
+
+        rag.embedding_backend = Some("Missing".to_string());
+        assert!(embedding_backend(&rag, &backends).is_err());
+    }
+
+    #[test]
+    fn test_local_urls() {
+        assert!(backend("", "http://127.0.0.1:11434").is_local());
+        assert!(backend("", "http://[::1]:8080/v1").is_local());
+        assert!(backend("", "http://user@LOCALHOST/v1").is_local());
+        assert!(!backend("", "https://api.example.com/v1").is_local());
+        assert!(!backend("", "http://192.168.1.5:11434").is_local());
+        assert!(!backend("", "http://localhost.example.com").is_local());
+    }
+}
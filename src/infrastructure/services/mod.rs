@@ -7,5 +7,11 @@ pub mod system_adapter;
 pub mod window_adapter;
 pub mod dictionary_adapter;
 pub mod llm_adapter;
+pub mod openai_adapter;
+pub mod llm_router;
 pub mod file_indexer;
+pub mod ignore_rules;
+pub mod embedding_index;
 pub mod time_adapter;
+pub mod history_store;
+pub mod chat_store;
@@ -0,0 +1,382 @@
+use crate::domain::model::{ChatError, ChatMessage, ChatRole, LlmBackend, ToolCall, ToolSpec};
+use crate::domain::ports::ILLMService;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+/// How often a running generation checks whether it was cancelled.
+const CANCEL_POLL: Duration = Duration::from_millis(50);
+
+/// Talks to servers with an OpenAI-compatible API: llama.cpp server, vLLM,
+/// LM Studio and the like. They serve the models they were started with,
+/// so there is nothing to download or delete.
+pub struct OpenAiAdapter {
+    runtime: Arc<Runtime>,
+    client: reqwest::Client,
+    /// Without a trailing slash, e.g. `http://127.0.0.1:8080/v1`.
+    base_url: String,
+    api_key: Option<String>,
+    model: Mutex<String>,
+}
+
+impl OpenAiAdapter {
+    pub fn new(backend: &LlmBackend, model: &str) -> Self {
+        let runtime = Arc::new(Runtime::new().expect("Failed to create Tokio runtime"));
+        let timeout = Duration::from_secs(backend.timeout_secs);
+        let client = reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .read_timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            runtime,
+            client,
+            base_url: backend.base_url.trim_end_matches('/').to_string(),
+            api_key: backend.api_key.clone().filter(|key| !key.is_empty()),
+            model: Mutex::new(model.to_string()),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}{}", self.base_url, path));
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    /// Sends the request and reads the JSON reply, turning error statuses
+    /// into the server's message.
+    fn send_json(&self, request: reqwest::RequestBuilder) -> Result<Value, String> {
+        let base_url = self.base_url.clone();
+        self.runtime.block_on(async move {
+            let response = request.send().await
+                .map_err(|e| format!("Failed to reach {}: {}", base_url, e))?;
+            let response = check_status(response).await?;
+            response.json::<Value>().await.map_err(|e| format!("Unexpected reply: {}", e))
+        })
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, String> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let (status, message) = failure(response).await;
+    Err(format!("Server error {}: {}", status, message))
+}
+
+/// Status and message of a failed request.
+async fn failure(response: reqwest::Response) -> (reqwest::StatusCode, String) {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    // Servers usually explain in {"error": {"message": ...}}
+    let message = serde_json::from_str::<Value>(&body).ok()
+        .and_then(|v| v["error"]["message"].as_str().or(v["error"].as_str()).map(str::to_string))
+        .unwrap_or(body);
+    (status, message.trim().to_string())
+}
+
+async fn cancelled(cancel: &AtomicBool) {
+    while !cancel.load(Ordering::Relaxed) {
+        tokio::time::sleep(CANCEL_POLL).await;
+    }
+}
+
+/// The event of one server-sent event line of a streamed completion: Null
+/// for lines without data, None once the stream says it is done.
+fn stream_event(line: &str) -> Option<Value> {
+    let Some(data) = line.strip_prefix("data:").map(str::trim) else { return Some(Value::Null) };
+    if data == "[DONE]" {
+        return None;
+    }
+    Some(serde_json::from_str(data).unwrap_or_default())
+}
+
+/// Tool calls arrive in pieces: the id and name first, then the arguments
+/// a few characters at a time, each piece tagged with the call's index.
+fn add_tool_call_deltas(calls: &mut Vec<(String, String, String)>, deltas: &Value) {
+    for delta in deltas.as_array().into_iter().flatten() {
+        let index = delta["index"].as_u64().map_or(calls.len().saturating_sub(1), |i| i as usize);
+        if calls.len() <= index {
+            calls.resize(index + 1, Default::default());
+        }
+        let (id, name, arguments) = &mut calls[index];
+        if let Some(piece) = delta["id"].as_str() {
+            id.push_str(piece);
+        }
+        if let Some(piece) = delta["function"]["name"].as_str() {
+            name.push_str(piece);
+        }
+        if let Some(piece) = delta["function"]["arguments"].as_str() {
+            arguments.push_str(piece);
+        }
+    }
+}
+
+fn message_json(message: &ChatMessage) -> Value {
+    let role = match message.role {
+        ChatRole::System => "system",
+        ChatRole::User => "user",
+        ChatRole::Assistant => "assistant",
+        ChatRole::Tool => "tool",
+    };
+    let mut json = json!({ "role": role, "content": message.content });
+    if !message.tool_calls.is_empty() {
+        // Arguments go back the way they came: as a JSON string
+        json["tool_calls"] = message.tool_calls.iter()
+            .map(|call| json!({
+                "id": call.id,
+                "type": "function",
+                "function": { "name": call.name, "arguments": call.arguments.to_string() },
+            }))
+            .collect();
+    }
+    if let Some(id) = &message.tool_call_id {
+        json["tool_call_id"] = json!(id);
+    }
+    json
+}
+
+impl ILLMService for OpenAiAdapter {
+    fn chat(&self, messages: &[ChatMessage], tools: &[ToolSpec], on_token: &mut dyn FnMut(&str), cancel: &AtomicBool) -> Result<ChatMessage, ChatError> {
+        let messages: Vec<Value> = messages.iter().map(message_json).collect();
+        let mut body = json!({ "model": *self.model.lock().unwrap(), "messages": messages, "stream": true });
+        if !tools.is_empty() {
+            body["tools"] = tools.iter()
+                .map(|tool| json!({
+                    "type": "function",
+                    "function": { "name": tool.name, "description": tool.description, "parameters": tool.parameters },
+                }))
+                .collect();
+        }
+        let with_tools = !tools.is_empty();
+        let request = self.request(reqwest::Method::POST, "/chat/completions").json(&body);
+        let base_url = self.base_url.clone();
+
+        self.runtime.block_on(async move {
+            use tokio_stream::StreamExt;
+            let response = request.send().await
+                .map_err(|e| format!("Failed to reach {}: {}", base_url, e))?;
+            if !response.status().is_success() {
+                let (status, message) = failure(response).await;
+                let error = format!("Server error {}: {}", status, message);
+                // e.g. llama.cpp without --jinja, vLLM without --enable-auto-tool-choice
+                let rejects_tools = with_tools && status.is_client_error() && message.to_lowercase().contains("tool");
+                return Err(if rejects_tools { ChatError::ToolsUnsupported(error) } else { ChatError::Other(error) });
+            }
+            let mut stream = response.bytes_stream();
+
+            let mut answer = String::new();
+            let mut calls = Vec::new();
+            // Events can be split across chunks; complete lines are taken off the front
+            let mut pending = Vec::new();
+            'stream: loop {
+                // Dropping the stream closes the connection, which stops the server generating
+                let next = tokio::select! {
+                    next = stream.next() => next,
+                    _ = cancelled(cancel) => break,
+                };
+                match next {
+                    Some(Ok(bytes)) => pending.extend_from_slice(&bytes),
+                    Some(Err(e)) => return Err(format!("The answer stream broke off: {}", e).into()),
+                    None => break,
+                }
+                while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    let Some(event) = stream_event(String::from_utf8_lossy(&line).trim()) else { break 'stream };
+                    let delta = &event["choices"][0]["delta"];
+                    if let Some(token) = delta["content"].as_str().filter(|token| !token.is_empty()) {
+                        on_token(token);
+                        answer.push_str(token);
+                    }
+                    add_tool_call_deltas(&mut calls, &delta["tool_calls"]);
+                }
+            }
+
+            let tool_calls = calls.into_iter().enumerate()
+                .map(|(i, (id, name, arguments))| ToolCall {
+                    id: if id.is_empty() { format!("call_{}", i) } else { id },
+                    name,
+                    arguments: serde_json::from_str(&arguments).unwrap_or_else(|_| json!({})),
+                })
+                .collect();
+            Ok(ChatMessage { tool_calls, ..ChatMessage::new(ChatRole::Assistant, answer) })
+        })
+    }
+
+    fn list_models(&self) -> Result<Vec<String>, String> {
+        let reply = self.send_json(self.request(reqwest::Method::GET, "/models"))?;
+        Ok(reply["data"].as_array().into_iter().flatten()
+            .filter_map(|model| model["id"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    fn pull_model(&self, _model: &str, _on_progress: Box<dyn Fn(f64) + Send>) -> Result<(), String> {
+        Err("This server serves the models it was started with; download models there".to_string())
+    }
+
+    fn delete_model(&self, _model: &str) -> Result<(), String> {
+        Err("This server serves the models it was started with; remove models there".to_string())
+    }
+
+    fn set_model(&self, model: &str) {
+        *self.model.lock().unwrap() = model.to_string();
+    }
+
+    fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let body = json!({ "model": model, "input": texts });
+        let reply = self.send_json(self.request(reqwest::Method::POST, "/embeddings").json(&body))?;
+        let mut data: Vec<&Value> = reply["data"].as_array().into_iter().flatten().collect();
+        // Entries carry their input's index; don't rely on their order
+        data.sort_by_key(|entry| entry["index"].as_u64().unwrap_or(0));
+        let vectors: Vec<Vec<f32>> = data.iter()
+            .map(|entry| {
+                entry["embedding"].as_array().into_iter().flatten()
+                    .filter_map(|v| v.as_f64().map(|v| v as f32))
+                    .collect()
+            })
+            .collect();
+        if vectors.len() != texts.len() {
+            return Err(format!("Expected {} embeddings, got {}", texts.len(), vectors.len()));
+        }
+        Ok(vectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// Serves one request with `response` and hands back what was received.
+    fn serve_once(status: &str, content_type: &str, body: &str) -> (LlmBackend, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status, content_type, body.len(), body
+        );
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8_lossy(&body));
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            let _ = sender.send(request);
+        });
+
+        let backend = LlmBackend {
+            name: "llama.cpp".to_string(),
+            protocol: crate::domain::model::LlmProtocol::OpenAi,
+            base_url: format!("http://127.0.0.1:{}/v1/", port),
+            api_key: Some("secret".to_string()),
+            timeout_secs: 5,
+        };
+        (backend, receiver)
+    }
+
+    #[test]
+    fn test_streams_chat_completion() {
+        let events = [
+            r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"Hel"}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"lo"}}]}"#,
+            "data: [DONE]",
+        ];
+        let (backend, requests) = serve_once("200 OK", "text/event-stream", &(events.join("\n\n") + "\n\n"));
+        let adapter = OpenAiAdapter::new(&backend, "qwen2.5");
+
+        let mut tokens = vec![];
+        let messages = [ChatMessage::new(ChatRole::User, "hi")];
+        let answer = adapter.chat(&messages, &[], &mut |t| tokens.push(t.to_string()), &AtomicBool::new(false)).unwrap();
+        assert_eq!(answer, ChatMessage::new(ChatRole::Assistant, "Hello"));
+        assert_eq!(tokens, ["Hel", "lo"]);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request.to_lowercase().contains("authorization: bearer secret"));
+        let body: Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["model"], "qwen2.5");
+        assert_eq!(body["messages"], json!([{ "role": "user", "content": "hi" }]));
+        assert_eq!(body.get("tools"), None);
+    }
+
+    #[test]
+    fn test_collects_streamed_tool_calls() {
+        let events = [
+            r#"data: {"choices":[{"delta":{"role":"assistant","tool_calls":[{"index":0,"id":"call_7","type":"function","function":{"name":"calculate","arguments":""}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"expression\":"}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"2*21\"}"}}]}}]}"#,
+            "data: [DONE]",
+        ];
+        let (backend, requests) = serve_once("200 OK", "text/event-stream", &(events.join("\n\n") + "\n\n"));
+        let adapter = OpenAiAdapter::new(&backend, "qwen2.5");
+
+        let call = ToolCall { id: "call_1".to_string(), name: "define".to_string(), arguments: json!({ "term": "rust" }) };
+        let messages = [
+            ChatMessage::new(ChatRole::User, "what is rust and 2*21?"),
+            ChatMessage { tool_calls: vec![call], ..ChatMessage::new(ChatRole::Assistant, "") },
+            ChatMessage { tool_call_id: Some("call_1".to_string()), ..ChatMessage::new(ChatRole::Tool, "a language") },
+        ];
+        let tools = [ToolSpec {
+            name: "calculate".to_string(),
+            description: "Evaluates arithmetic".to_string(),
+            parameters: json!({ "type": "object", "properties": { "expression": { "type": "string" } } }),
+        }];
+        let reply = adapter.chat(&messages, &tools, &mut |_| {}, &AtomicBool::new(false)).unwrap();
+        assert_eq!(reply.content, "");
+        assert_eq!(reply.tool_calls, [ToolCall { id: "call_7".to_string(), name: "calculate".to_string(), arguments: json!({ "expression": "2*21" }) }]);
+
+        let request = requests.recv().unwrap();
+        let body: Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["tools"][0]["function"]["name"], "calculate");
+        assert_eq!(body["messages"][1]["tool_calls"][0]["function"]["arguments"], r#"{"term":"rust"}"#);
+        assert_eq!(body["messages"][2], json!({ "role": "tool", "content": "a language", "tool_call_id": "call_1" }));
+    }
+
+    #[test]
+    fn test_reports_rejected_tools() {
+        let (backend, _requests) = serve_once("400 Bad Request", "application/json", r#"{"error":{"message":"tools param requires --jinja flag"}}"#);
+        let adapter = OpenAiAdapter::new(&backend, "qwen2.5");
+        let tools = [ToolSpec { name: "define".to_string(), description: String::new(), parameters: json!({ "type": "object" }) }];
+        let messages = [ChatMessage::new(ChatRole::User, "hi")];
+        let error = adapter.chat(&messages, &tools, &mut |_| {}, &AtomicBool::new(false)).unwrap_err();
+        assert!(matches!(error, ChatError::ToolsUnsupported(ref message) if message.contains("--jinja")), "{:?}", error);
+    }
+
+    #[test]
+    fn test_lists_models() {
+        let (backend, requests) = serve_once("200 OK", "application/json", r#"{"data":[{"id":"qwen2.5"},{"id":"llama3"}]}"#);
+        let adapter = OpenAiAdapter::new(&backend, "");
+        assert_eq!(adapter.list_models().unwrap(), ["qwen2.5", "llama3"]);
+        assert!(requests.recv().unwrap().starts_with("GET /v1/models "));
+    }
+
+    #[test]
+    fn test_reports_server_errors() {
+        let (backend, _requests) = serve_once("404 Not Found", "application/json", r#"{"error":{"message":"model not found"}}"#);
+        let adapter = OpenAiAdapter::new(&backend, "nope");
+        let error = adapter.embed("nope", &["text".to_string()]).unwrap_err();
+        assert!(error.contains("model not found"), "{}", error);
+    }
+}
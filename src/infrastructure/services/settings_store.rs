@@ -3,7 +3,7 @@ use std::fs;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
-use crate::domain::model::{Macro, MacroAction};
+use crate::domain::model::{FileSort, IndexSettings, LlmBackend, Macro, MacroAction, RagSettings};
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct AppSettings {
@@ -13,6 +13,35 @@ pub struct AppSettings {
     pub ai_model: String,
     #[serde(default)]
     pub favorites: Vec<String>,
+    /// Search provider id -> prefix, overriding the provider's default (e.g. "window": "win ").
+    #[serde(default)]
+    pub prefixes: HashMap<String, String>,
+    /// Blended search source id -> score multiplier (e.g. "files": 0.3).
+    #[serde(default)]
+    pub search_weights: HashMap<String, f64>,
+    /// Terminal command line (e.g. "kitty" or "wezterm start"); detected when unset.
+    #[serde(default)]
+    pub terminal: Option<String>,
+    /// Keep the terminal open after a command run in it exits.
+    #[serde(default)]
+    pub terminal_hold: bool,
+    /// List dotfiles in the file browser.
+    #[serde(default)]
+    pub show_hidden_files: bool,
+    #[serde(default)]
+    pub file_sort: FileSort,
+    /// Roots and ignore globs of the file index, read at startup.
+    #[serde(default)]
+    pub index: IndexSettings,
+    /// What the assistant embeds and retrieves from the indexed files.
+    #[serde(default)]
+    pub rag: RagSettings,
+    /// LLM servers to choose from; a local Ollama when empty.
+    #[serde(default)]
+    pub backends: Vec<LlmBackend>,
+    /// Name of the backend in use; the first one when unset.
+    #[serde(default)]
+    pub backend: Option<String>,
 }
 
 fn default_model() -> String {
@@ -135,4 +164,104 @@ impl SettingsStore {
         drop(data);
         self.save().map(|_| is_fav)
     }
+
+    pub fn get_prefix_overrides(&self) -> HashMap<String, String> {
+        self.cache.lock().unwrap().prefixes.clone()
+    }
+
+    pub fn set_prefix(&self, provider_id: &str, prefix: String) -> Result<(), String> {
+        self.cache.lock().unwrap().prefixes.insert(provider_id.to_string(), prefix);
+        self.save()
+    }
+
+    pub fn reset_prefix(&self, provider_id: &str) -> Result<(), String> {
+        self.cache.lock().unwrap().prefixes.remove(provider_id);
+        self.save()
+    }
+
+    pub fn get_search_weights(&self) -> HashMap<String, f64> {
+        self.cache.lock().unwrap().search_weights.clone()
+    }
+
+    pub fn set_search_weight(&self, source: &str, weight: f64) -> Result<(), String> {
+        self.cache.lock().unwrap().search_weights.insert(source.to_string(), weight);
+        self.save()
+    }
+
+    pub fn get_terminal(&self) -> Option<String> {
+        self.cache.lock().unwrap().terminal.clone()
+    }
+
+    /// `None` (or an empty value) goes back to detecting the terminal.
+    pub fn set_terminal(&self, terminal: Option<String>) -> Result<(), String> {
+        self.cache.lock().unwrap().terminal = terminal.filter(|t| !t.trim().is_empty());
+        self.save()
+    }
+
+    pub fn get_terminal_hold(&self) -> bool {
+        self.cache.lock().unwrap().terminal_hold
+    }
+
+    pub fn set_terminal_hold(&self, hold: bool) -> Result<(), String> {
+        self.cache.lock().unwrap().terminal_hold = hold;
+        self.save()
+    }
+
+    pub fn get_show_hidden_files(&self) -> bool {
+        self.cache.lock().unwrap().show_hidden_files
+    }
+
+    pub fn set_show_hidden_files(&self, show: bool) -> Result<(), String> {
+        self.cache.lock().unwrap().show_hidden_files = show;
+        self.save()
+    }
+
+    pub fn get_file_sort(&self) -> FileSort {
+        self.cache.lock().unwrap().file_sort
+    }
+
+    pub fn set_file_sort(&self, sort: FileSort) -> Result<(), String> {
+        self.cache.lock().unwrap().file_sort = sort;
+        self.save()
+    }
+
+    pub fn get_index_settings(&self) -> IndexSettings {
+        self.cache.lock().unwrap().index.clone()
+    }
+
+    pub fn get_rag_settings(&self) -> RagSettings {
+        self.cache.lock().unwrap().rag.clone()
+    }
+
+    pub fn get_llm_backends(&self) -> Vec<LlmBackend> {
+        let backends = self.cache.lock().unwrap().backends.clone();
+        if backends.is_empty() { vec![LlmBackend::default()] } else { backends }
+    }
+
+    /// The selected backend, or the first if the selection no longer exists.
+    pub fn get_llm_backend(&self) -> LlmBackend {
+        let selected = self.cache.lock().unwrap().backend.clone();
+        let backends = self.get_llm_backends();
+        backends.iter()
+            .find(|b| Some(&b.name) == selected.as_ref())
+            .unwrap_or(&backends[0])
+            .clone()
+    }
+
+    pub fn set_llm_backend(&self, name: &str) -> Result<(), String> {
+        self.cache.lock().unwrap().backend = Some(name.to_string());
+        self.save()
+    }
+
+    /// Replaces the backend with the same name, adding it if there is none
+    /// (e.g. the default Ollama before any backend was configured).
+    pub fn update_llm_backend(&self, backend: LlmBackend) -> Result<(), String> {
+        let mut data = self.cache.lock().unwrap();
+        match data.backends.iter_mut().find(|b| b.name == backend.name) {
+            Some(existing) => *existing = backend,
+            None => data.backends.push(backend),
+        }
+        drop(data);
+        self.save()
+    }
 }
@@ -1,53 +1,73 @@
-use std::process::Command;
+use crate::infrastructure::system::process_watcher::{ProcessWatcher, stderr_file};
+use std::process::{Command, Stdio};
 
-pub struct SystemAdapter;
+pub struct SystemAdapter {
+    watcher: ProcessWatcher,
+}
 
 impl SystemAdapter {
     pub fn new() -> Self {
-        Self
+        Self::with_watcher(ProcessWatcher::new())
+    }
+
+    /// Reports commands that exit with an error through a shared watcher.
+    pub fn with_watcher(watcher: ProcessWatcher) -> Self {
+        Self { watcher }
     }
 
-    fn run_cmd(cmd: &str, args: &[&str]) {
-        let _ = Command::new(cmd).args(args).spawn();
+    fn run_cmd(&self, cmd: &str, args: &[&str]) -> Result<(), String> {
+        let mut command = Command::new(cmd);
+        command.args(args);
+        let stderr = stderr_file().ok();
+        if let Some(file) = stderr.as_ref().and_then(|file| file.try_clone().ok()) {
+            command.stderr(Stdio::from(file));
+        }
+        let child = command.spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => format!("{} is not installed", cmd),
+                _ => format!("Failed to run {}: {}", cmd, e),
+            })?;
+        self.watcher.watch(child, stderr, &format!("{} {}", cmd, args.join(" ")));
+        Ok(())
     }
 
-    pub fn shutdown(&self) {
-        Self::run_cmd("systemctl", &["poweroff"]);
+    pub fn shutdown(&self) -> Result<(), String> {
+        self.run_cmd("systemctl", &["poweroff"])
     }
 
-    pub fn reboot(&self) {
-        Self::run_cmd("systemctl", &["reboot"]);
+    pub fn reboot(&self) -> Result<(), String> {
+        self.run_cmd("systemctl", &["reboot"])
     }
 
-    pub fn suspend(&self) {
-        Self::run_cmd("systemctl", &["suspend"]);
+    pub fn suspend(&self) -> Result<(), String> {
+        self.run_cmd("systemctl", &["suspend"])
     }
 
-    pub fn hibernate(&self) {
-        Self::run_cmd("systemctl", &["hibernate"]);
+    pub fn hibernate(&self) -> Result<(), String> {
+        self.run_cmd("systemctl", &["hibernate"])
     }
 
-    pub fn lock(&self) {
+    pub fn lock(&self) -> Result<(), String> {
         // Try loginctl first, commonplace on systemd systems
-        Self::run_cmd("loginctl", &["lock-session"]);
+        self.run_cmd("loginctl", &["lock-session"])
     }
 
-    pub fn mute_audio(&self) {
+    pub fn mute_audio(&self) -> Result<(), String> {
         // Toggle default sink mute
-        Self::run_cmd("wpctl", &["set-mute", "@DEFAULT_AUDIO_SINK@", "toggle"]);
+        self.run_cmd("wpctl", &["set-mute", "@DEFAULT_AUDIO_SINK@", "toggle"])
     }
 
-    pub fn mute_mic(&self) {
+    pub fn mute_mic(&self) -> Result<(), String> {
         // Toggle default source mute
-        Self::run_cmd("wpctl", &["set-mute", "@DEFAULT_AUDIO_SOURCE@", "toggle"]);
+        self.run_cmd("wpctl", &["set-mute", "@DEFAULT_AUDIO_SOURCE@", "toggle"])
     }
 
-    pub fn mute_all(&self) {
-        self.mute_audio();
-        self.mute_mic();
+    pub fn mute_all(&self) -> Result<(), String> {
+        self.mute_audio()?;
+        self.mute_mic()
     }
 
-    pub fn toggle_night_light(&self) {
+    pub fn toggle_night_light(&self) -> Result<(), String> {
         // We need to check current state to toggle? Or is there a toggle?
         // gsettings doesn't have a toggle. We need to read then write.
         // For simplicity in this "fire and forget" context, we might implement a small script logic
@@ -62,10 +82,10 @@ impl SystemAdapter {
                 gsettings set $schema $key true
             fi
         ";
-        Self::run_cmd("sh", &["-c", script]);
+        self.run_cmd("sh", &["-c", script])
     }
 
-    pub fn toggle_dark_mode(&self) {
+    pub fn toggle_dark_mode(&self) -> Result<(), String> {
         let script = "
             schema='org.gnome.desktop.interface'
             key='color-scheme'
@@ -76,10 +96,10 @@ impl SystemAdapter {
                 gsettings set $schema $key 'prefer-dark'
             fi
         ";
-        Self::run_cmd("sh", &["-c", script]);
+        self.run_cmd("sh", &["-c", script])
     }
 
-    pub fn toggle_dnd(&self) {
+    pub fn toggle_dnd(&self) -> Result<(), String> {
         // 'show-banners' -> false means DND is ON (notifications hidden)
         // 'show-banners' -> true means DND is OFF (notifications shown)
         let script = "
@@ -92,25 +112,31 @@ impl SystemAdapter {
                 gsettings set $schema $key true
             fi
         ";
-        Self::run_cmd("sh", &["-c", script]);
+        self.run_cmd("sh", &["-c", script])
     }
 }
 use crate::domain::ports::ISystemPower;
 
+impl Default for SystemAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ISystemPower for SystemAdapter {
     fn execute(&self, action: &str) -> Result<(), String> {
         match action {
-            "shutdown" | "poweroff" => { self.shutdown(); Ok(()) },
-            "reboot" => { self.reboot(); Ok(()) },
-            "suspend" => { self.suspend(); Ok(()) },
-            "hibernate" => { self.hibernate(); Ok(()) },
-            "lock" => { self.lock(); Ok(()) },
-            "mute" | "mute_audio" => { self.mute_audio(); Ok(()) },
-            "mute_mic" => { self.mute_mic(); Ok(()) },
-            "mute_all" => { self.mute_all(); Ok(()) },
-            "toggle_night_light" => { self.toggle_night_light(); Ok(()) },
-            "toggle_dark_mode" => { self.toggle_dark_mode(); Ok(()) },
-            "toggle_dnd" => { self.toggle_dnd(); Ok(()) },
+            "shutdown" | "poweroff" => self.shutdown(),
+            "reboot" => self.reboot(),
+            "suspend" => self.suspend(),
+            "hibernate" => self.hibernate(),
+            "lock" => self.lock(),
+            "mute" | "mute_audio" => self.mute_audio(),
+            "mute_mic" => self.mute_mic(),
+            "mute_all" => self.mute_all(),
+            "toggle_night_light" => self.toggle_night_light(),
+            "toggle_dark_mode" => self.toggle_dark_mode(),
+            "toggle_dnd" => self.toggle_dnd(),
             _ => Err(format!("Unknown system action: {}", action))
         }
     }
@@ -133,12 +133,17 @@ impl SystemWindowAdapter {
     }
     
     fn run_wmctrl_focus(id: &str) -> Result<(), String> {
+        Self::run_wmctrl(&["-i", "-a", id])
+    }
+
+    fn run_wmctrl(args: &[&str]) -> Result<(), String> {
         let status = Command::new("wmctrl")
-            .arg("-i")
-            .arg("-a")
-            .arg(id)
+            .args(args)
             .status()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => "wmctrl is not installed".to_string(),
+                _ => e.to_string(),
+            })?;
             
         if status.success() {
             Ok(())
@@ -164,4 +169,12 @@ impl IWindowRepository for SystemWindowAdapter {
     fn focus_window(&self, id: &str) -> Result<(), String> {
          Self::run_wmctrl_focus(id)
     }
+
+    fn close_window(&self, id: &str) -> Result<(), String> {
+        Self::run_wmctrl(&["-i", "-c", id])
+    }
+
+    fn move_window_to_workspace(&self, id: &str, workspace: i32) -> Result<(), String> {
+        Self::run_wmctrl(&["-i", "-r", id, "-t", &workspace.to_string()])
+    }
 }
@@ -1,19 +1,381 @@
+use crate::domain::model::{CommandSpec, LaunchFailure};
 use crate::domain::ports::ICommandExecutor;
-use std::process::Command;
+use crate::infrastructure::system::process_watcher::{ProcessWatcher, stderr_file};
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::os::unix::fs::FileExt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-pub struct SystemCommandExecutorAdapter;
+/// How long `systemd-run` may take to set up a scope before the launch
+/// counts as started anyway.
+const SCOPE_SETUP_TIMEOUT: Duration = Duration::from_secs(1);
+const SCOPE_SETUP_POLL: Duration = Duration::from_millis(5);
+
+/// How launched commands are detached from the launcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchMode {
+    /// Each command runs in its own transient `app-launch-<id>-<n>.scope`,
+    /// created by `systemd-run` through the user manager's `StartTransientUnit`.
+    /// Apps then get their own cgroup and survive the launcher being killed.
+    /// If the scope can't be created the command is started directly.
+    SystemdScope,
+    /// Plain child process in its own session.
+    Direct,
+}
+
+pub struct SystemCommandExecutorAdapter {
+    mode: LaunchMode,
+    launches: AtomicU32,
+    watcher: ProcessWatcher,
+}
 
 impl SystemCommandExecutorAdapter {
     pub fn new() -> Self {
-        Self
+        Self::with_watcher(ProcessWatcher::new())
+    }
+
+    /// Reports failures through a watcher shared with other adapters. Uses
+    /// systemd scopes when a systemd user manager is running.
+    pub fn with_watcher(watcher: ProcessWatcher) -> Self {
+        let mode = if user_manager_running() { LaunchMode::SystemdScope } else { LaunchMode::Direct };
+        Self::with_mode(mode, watcher)
+    }
+
+    pub fn with_mode(mode: LaunchMode, watcher: ProcessWatcher) -> Self {
+        Self { mode, launches: AtomicU32::new(0), watcher }
+    }
+
+    /// Starts `spec`, naming its scope after `name`. `label` is how the
+    /// command appears in failure reports. A scope is waited for on a
+    /// thread, which starts the command directly if the scope failed.
+    fn start(&self, spec: &CommandSpec, name: &str, label: &str) -> Result<(), String> {
+        let n = self.launches.fetch_add(1, Ordering::Relaxed);
+
+        if self.mode == LaunchMode::SystemdScope {
+            let unit = scope_unit_name(name, std::process::id(), n);
+            // systemd-run missing: start it directly
+            if let Ok((mut child, stderr)) = spawn_command(scope_command(spec, &unit, name), n) {
+                // Spawning can return before exec renames the child, which
+                // until then has the name of this thread
+                let spawner = std::fs::read_to_string("/proc/thread-self/comm").unwrap_or_default();
+                let (spec, label, watcher) = (spec.clone(), label.to_string(), self.watcher.clone());
+                thread::spawn(move || {
+                    if scope_started(&mut child, &spawner) {
+                        return watch(&watcher, &spec, &label, child, stderr);
+                    }
+                    // No user bus, unit refused, ...: the app never ran
+                    println!("systemd-run could not start '{}', launching it directly: {}", label, first_line(stderr.as_ref()));
+                    match spawn_command(direct_command(&spec), n) {
+                        Ok((child, stderr)) => watch(&watcher, &spec, &label, child, stderr),
+                        Err(e) => watcher.report(LaunchFailure { message: launch_error(&spec, &label, e), command: label }),
+                    }
+                });
+                return Ok(());
+            }
+        }
+
+        let (child, stderr) = spawn_command(direct_command(spec), n).map_err(|e| launch_error(spec, label, e))?;
+        watch(&self.watcher, spec, label, child, stderr);
+        Ok(())
+    }
+
+    /// Scope name and report label for `spec`: a plain `sh -c` script is
+    /// shown as the script itself.
+    fn describe(spec: &CommandSpec, app_id: Option<&str>) -> (String, String) {
+        let label = match spec.args.as_slice() {
+            [flag, script, ..] if spec.program == "sh" && flag == "-c" && spec.args.len() == 3 => script.clone(),
+            _ => spec.command_line(),
+        };
+        let name = app_id.map(str::to_string)
+            .or_else(|| program_name(&label))
+            .unwrap_or_else(|| "command".to_string());
+        (name, label)
+    }
+}
+
+impl Default for SystemCommandExecutorAdapter {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl ICommandExecutor for SystemCommandExecutorAdapter {
-    fn execute(&self, cmd: &str) {
-        let _ = Command::new("sh")
-            .arg("-c")
-            .arg(cmd)
-            .spawn();
+    fn execute(&self, cmd: &str) -> Result<(), String> {
+        self.spawn(&CommandSpec::shell(cmd))
+    }
+
+    fn spawn(&self, spec: &CommandSpec) -> Result<(), String> {
+        self.launch_app(spec, None)
+    }
+
+    /// `spec` runs directly and its output is discarded; a thread waits for
+    /// it and starts `fallback` if it failed.
+    fn spawn_or(&self, spec: &CommandSpec, fallback: &CommandSpec) -> Result<(), String> {
+        let mut command = direct_command(spec);
+        command.stdout(Stdio::null()).stderr(Stdio::null());
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(_) => return self.spawn(fallback),
+        };
+        if let Some(input) = &spec.stdin {
+            write_stdin(&mut child, input);
+        }
+
+        let (_, label) = Self::describe(fallback, None);
+        let fallback = fallback.clone();
+        let watcher = self.watcher.clone();
+        let n = self.launches.fetch_add(1, Ordering::Relaxed);
+        thread::spawn(move || {
+            if child.wait().is_ok_and(|status| status.success()) {
+                return;
+            }
+            match spawn_command(direct_command(&fallback), n) {
+                Ok((child, stderr)) => watch(&watcher, &fallback, &label, child, stderr),
+                Err(e) => watcher.report(LaunchFailure { message: launch_error(&fallback, &label, e), command: label }),
+            }
+        });
+        Ok(())
+    }
+
+    fn launch_app(&self, spec: &CommandSpec, app_id: Option<&str>) -> Result<(), String> {
+        let (name, label) = Self::describe(spec, app_id);
+        self.start(spec, &name, &label)
+    }
+
+    fn take_failures(&self) -> Vec<LaunchFailure> {
+        self.watcher.take_failures()
+    }
+}
+
+/// The user manager's private socket exists only while it runs.
+fn user_manager_running() -> bool {
+    std::env::var("XDG_RUNTIME_DIR")
+        .is_ok_and(|dir| Path::new(&dir).join("systemd/private").exists())
+}
+
+/// `systemd-run --scope` execs the program itself, so the working directory
+/// and environment set on it carry over.
+fn scope_command(spec: &CommandSpec, unit: &str, name: &str) -> Command {
+    let mut command = Command::new("systemd-run");
+    command.args(["--user", "--scope", "--quiet", "--collect"])
+        .arg(format!("--unit={}", unit))
+        .arg(format!("--description={}", name))
+        .arg("--")
+        .arg(&spec.program)
+        .args(&spec.args);
+    apply_context(&mut command, spec);
+    command
+}
+
+/// Waits until `systemd-run` has created the scope and exec'd the program.
+/// False if it exited before that, i.e. it couldn't create the scope.
+/// `spawner` is the name of the thread that spawned it.
+fn scope_started(child: &mut Child, spawner: &str) -> bool {
+    let comm = format!("/proc/{}/comm", child.id());
+    let deadline = Instant::now() + SCOPE_SETUP_TIMEOUT;
+    while Instant::now() < deadline {
+        // Still readable once the program exited, until it is reaped
+        if std::fs::read_to_string(&comm).is_ok_and(|name| name != spawner && name.trim_end() != "systemd-run") {
+            return true;
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => return status.success(),
+            Ok(None) => thread::sleep(SCOPE_SETUP_POLL),
+            Err(_) => return true,
+        }
+    }
+    true
+}
+
+/// Hands the started command its input and watches it for failures.
+fn watch(watcher: &ProcessWatcher, spec: &CommandSpec, label: &str, mut child: Child, stderr: Option<File>) {
+    if let Some(input) = &spec.stdin {
+        write_stdin(&mut child, input);
+    }
+    watcher.watch(child, stderr, label);
+}
+
+fn launch_error(spec: &CommandSpec, label: &str, e: std::io::Error) -> String {
+    match e.kind() {
+        ErrorKind::NotFound => format!("{} is not installed", spec.program),
+        _ => format!("Failed to launch '{}': {}", label, e),
+    }
+}
+
+/// Feeds `input` to the child from a thread, so a program that reads
+/// slowly doesn't block the caller.
+fn write_stdin(child: &mut Child, input: &str) {
+    let Some(mut stdin) = child.stdin.take() else { return };
+    let input = input.to_string();
+    thread::spawn(move || {
+        if let Err(e) = stdin.write_all(input.as_bytes()) {
+            println!("Failed to write to stdin: {}", e);
+        }
+    });
+}
+
+fn first_line(stderr: Option<&File>) -> String {
+    let mut buf = [0; 256];
+    let n = stderr.and_then(|file| file.read_at(&mut buf, 0).ok()).unwrap_or(0);
+    String::from_utf8_lossy(&buf[..n]).lines().next().unwrap_or_default().to_string()
+}
+
+fn direct_command(spec: &CommandSpec) -> Command {
+    let mut command = Command::new(&spec.program);
+    command.args(&spec.args);
+    apply_context(&mut command, spec);
+    command
+}
+
+fn apply_context(command: &mut Command, spec: &CommandSpec) {
+    if let Some(cwd) = &spec.cwd {
+        command.current_dir(cwd);
+    }
+    command.envs(spec.env.iter().map(|(k, v)| (k, v)));
+    if spec.stdin.is_some() {
+        command.stdin(Stdio::piped());
+    }
+}
+
+/// Starts the command with a fresh startup notification ID, in its own process
+/// group so signals aimed at the launcher (e.g. Ctrl+C in a terminal) don't reach it.
+/// Stderr goes to a file so the watcher can report why it failed.
+fn spawn_command(mut command: Command, n: u32) -> std::io::Result<(Child, Option<File>)> {
+    let startup_id = startup_id(std::process::id(), n);
+    let stderr = stderr_file()
+        .map_err(|e| println!("Not capturing stderr: {}", e))
+        .ok();
+    if let Some(file) = &stderr {
+        command.stderr(Stdio::from(file.try_clone()?));
+    }
+    let child = command.process_group(0)
+        // GLib sets these for the launcher itself; they must not leak into apps
+        .env_remove("GIO_LAUNCHED_DESKTOP_FILE")
+        .env_remove("GIO_LAUNCHED_DESKTOP_FILE_PID")
+        .env("DESKTOP_STARTUP_ID", &startup_id)
+        .env("XDG_ACTIVATION_TOKEN", &startup_id)
+        .spawn()?;
+    Ok((child, stderr))
+}
+
+/// Startup notification ID in the `<unique>_TIME<timestamp>` form of the
+/// startup-notification spec, also accepted as an activation token.
+fn startup_id(pid: u32, n: u32) -> String {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u32);
+    format!("launch-{}-{}_TIME{}", pid, n, millis)
+}
+
+/// `app-launch-<id>-<n>.scope`, with the ID escaped like systemd unit names
+/// (`-` becomes `\x2d`) so the ID can be recovered from the cgroup path.
+fn scope_unit_name(app_id: &str, pid: u32, n: u32) -> String {
+    let mut escaped = String::new();
+    for c in app_id.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == ':' {
+            escaped.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                escaped.push_str(&format!("\\x{:02x}", byte));
+            }
+        }
+    }
+    format!("app-launch-{}-{}_{}.scope", escaped, pid, n)
+}
+
+/// Basename of the first word of a shell command line.
+fn program_name(cmd: &str) -> Option<String> {
+    let first = cmd.split_whitespace().next()?;
+    Path::new(first).file_name().map(|n| n.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_unit_name_escapes_app_id() {
+        assert_eq!(scope_unit_name("org.mozilla.firefox", 42, 0), "app-launch-org.mozilla.firefox-42_0.scope");
+        assert_eq!(scope_unit_name("gnome-system-monitor", 42, 3), "app-launch-gnome\\x2dsystem\\x2dmonitor-42_3.scope");
+        assert_eq!(scope_unit_name("a b", 1, 1), "app-launch-a\\x20b-1_1.scope");
+        assert_eq!(program_name("/usr/bin/firefox --new-window").as_deref(), Some("firefox"));
+    }
+
+    #[test]
+    fn test_direct_launch_gets_startup_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("env");
+        let cmd = format!("echo \"$DESKTOP_STARTUP_ID\" > {}", out.display());
+        let (mut child, _) = spawn_command(direct_command(&CommandSpec::shell(&cmd)), 7).unwrap();
+        assert!(child.wait().unwrap().success());
+        let startup_id = std::fs::read_to_string(out).unwrap();
+        assert!(startup_id.starts_with(&format!("launch-{}-7_TIME", std::process::id())));
+    }
+
+    /// A stand-in for `systemd-run` running `script`.
+    fn fake_systemd_run(dir: &Path, script: &str) -> Child {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("systemd-run");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        Command::new(path).spawn().unwrap()
+    }
+
+    #[test]
+    fn test_detects_scope_setup_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let spawner = std::fs::read_to_string("/proc/thread-self/comm").unwrap();
+        let mut failed = fake_systemd_run(dir.path(), "echo 'Failed to connect to bus' >&2; exit 1");
+        assert!(!scope_started(&mut failed, &spawner));
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut started = fake_systemd_run(dir.path(), "sleep 0.1; exec sleep 1");
+        assert!(scope_started(&mut started, &spawner));
+        assert!(started.try_wait().unwrap().is_none());
+        started.kill().unwrap();
+        started.wait().unwrap();
+    }
+
+    #[test]
+    fn test_spec_arguments_reach_the_program_verbatim() {
+        let dir = tempfile::tempdir().unwrap();
+        let hostile = "it's \"here\" $(touch pwned)";
+        let spec = CommandSpec::script("printf '%s' \"$1\" > out", [hostile]).with_cwd(&dir.path().to_string_lossy());
+        let (mut child, _) = spawn_command(direct_command(&spec), 0).unwrap();
+        assert!(child.wait().unwrap().success());
+        assert_eq!(std::fs::read_to_string(dir.path().join("out")).unwrap(), hostile);
+        assert!(!dir.path().join("pwned").exists());
+    }
+
+    fn wait_for_file(path: &Path) -> String {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !path.exists() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        // Give the writer time to finish
+        thread::sleep(Duration::from_millis(50));
+        std::fs::read_to_string(path).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_stdin_and_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().to_string_lossy().to_string();
+        let executor = SystemCommandExecutorAdapter::with_mode(LaunchMode::Direct, ProcessWatcher::new());
+
+        let copy = CommandSpec::script("cat > copied.tmp && mv copied.tmp copied", [] as [&str; 0]).with_cwd(&cwd).with_stdin("it's $HOME");
+        executor.spawn(&copy).unwrap();
+        assert_eq!(wait_for_file(&dir.path().join("copied")), "it's $HOME");
+
+        let fallback = CommandSpec::new("touch", ["fallback"]).with_cwd(&cwd);
+        executor.spawn_or(&CommandSpec::new("true", [] as [&str; 0]), &fallback).unwrap();
+        executor.spawn_or(&CommandSpec::new("false", [] as [&str; 0]).with_cwd(&cwd), &CommandSpec::new("touch", ["used"]).with_cwd(&cwd)).unwrap();
+        wait_for_file(&dir.path().join("used"));
+        assert!(dir.path().join("used").exists());
+        assert!(!dir.path().join("fallback").exists());
     }
 }
@@ -1,2 +1,3 @@
 pub mod command_executor_adapter;
-
+pub mod process_watcher;
+pub mod terminal_adapter;
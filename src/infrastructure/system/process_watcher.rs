@@ -0,0 +1,144 @@
+use crate::domain::model::LaunchFailure;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::FileExt;
+use std::process::Child;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Exits within this long after launch are reported as failures; later ones
+/// are just the user closing the app.
+const WATCH_WINDOW: Duration = Duration::from_secs(3);
+/// How much of a failed command's stderr is kept for the report.
+const MAX_STDERR: usize = 2048;
+
+static STDERR_FILES: AtomicU32 = AtomicU32::new(0);
+
+/// An unlinked temporary file for a launched command's stderr. Unlike a
+/// pipe it never breaks when the launcher exits, so apps aren't killed by
+/// SIGPIPE, and nothing has to keep reading it.
+pub fn stderr_file() -> std::io::Result<File> {
+    let n = STDERR_FILES.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("launch-stderr-{}-{}", std::process::id(), n));
+    let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+    std::fs::remove_file(&path)?;
+    Ok(file)
+}
+
+struct Watched {
+    child: Child,
+    command: String,
+    started: Instant,
+    /// Dropped once the command outlived the watch window.
+    stderr: Option<File>,
+}
+
+/// Collects launched processes that failed right after starting, and reaps
+/// the others once they exit. Both happen when failures are taken, which the
+/// UI does regularly, so no thread waits on the children. Clones share the
+/// same processes and failures.
+#[derive(Clone, Default)]
+pub struct ProcessWatcher {
+    children: Arc<Mutex<Vec<Watched>>>,
+    failures: Arc<Mutex<Vec<LaunchFailure>>>,
+}
+
+impl ProcessWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watches `child`, whose stderr went to `stderr` (see [`stderr_file`]),
+    /// so an early failure can be reported with its message.
+    pub fn watch(&self, child: Child, stderr: Option<File>, command: &str) {
+        self.children.lock().unwrap().push(Watched {
+            child,
+            command: command.to_string(),
+            started: Instant::now(),
+            stderr,
+        });
+    }
+
+    /// Reports a failure that happened outside a watched process, e.g. a
+    /// fallback that couldn't be started.
+    pub fn report(&self, failure: LaunchFailure) {
+        self.failures.lock().unwrap().push(failure);
+    }
+
+    /// Failures since the last call. A command counts as failed if it had
+    /// exited with an error by the first check after its watch window.
+    pub fn take_failures(&self) -> Vec<LaunchFailure> {
+        self.check_children();
+        std::mem::take(&mut *self.failures.lock().unwrap())
+    }
+
+    fn check_children(&self) {
+        let mut failures = Vec::new();
+        self.children.lock().unwrap().retain_mut(|watched| {
+            let status = match watched.child.try_wait() {
+                Ok(Some(status)) => status,
+                Ok(None) => {
+                    if watched.started.elapsed() > WATCH_WINDOW {
+                        watched.stderr = None;
+                    }
+                    return true;
+                },
+                Err(e) => {
+                    println!("Failed to wait for '{}': {}", watched.command, e);
+                    return false;
+                },
+            };
+            if status.success() {
+                return false;
+            }
+            let Some(file) = &watched.stderr else {
+                println!("'{}' exited with {}", watched.command, status);
+                return false;
+            };
+            let mut buf = vec![0; MAX_STDERR];
+            let n = file.read_at(&mut buf, 0).unwrap_or(0);
+            let stderr = String::from_utf8_lossy(&buf[..n]).trim().to_string();
+            let message = if stderr.is_empty() { format!("exited with {}", status) } else { stderr };
+            failures.push(LaunchFailure { command: watched.command.clone(), message });
+            false
+        });
+        self.failures.lock().unwrap().extend(failures);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+    use std::thread;
+
+    fn spawn(watcher: &ProcessWatcher, script: &str, command: &str) {
+        let stderr = stderr_file().unwrap();
+        let child = Command::new("sh").arg("-c").arg(script)
+            .stderr(Stdio::from(stderr.try_clone().unwrap()))
+            .spawn()
+            .unwrap();
+        watcher.watch(child, Some(stderr), command);
+    }
+
+    #[test]
+    fn test_reports_early_failure_with_stderr() {
+        let watcher = ProcessWatcher::new();
+        spawn(&watcher, "true", "true");
+        spawn(&watcher, "echo 'no such thing' >&2; exit 3", "broken");
+        spawn(&watcher, "exit 2", "silent");
+        spawn(&watcher, "sleep 1", "running");
+
+        thread::sleep(Duration::from_millis(300));
+        let mut failures = watcher.take_failures();
+        failures.sort_by(|a, b| a.command.cmp(&b.command));
+
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0], LaunchFailure { command: "broken".to_string(), message: "no such thing".to_string() });
+        assert_eq!(failures[1].command, "silent");
+        assert!(failures[1].message.contains('2'));
+        assert!(watcher.take_failures().is_empty());
+        // Exited commands are reaped; the running one is still watched
+        assert_eq!(watcher.children.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::domain::model::CommandSpec;
+use crate::domain::ports::ITerminalLauncher;
+use crate::infrastructure::services::settings_store::SettingsStore;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How a terminal emulator is told what to run and where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalStyle {
+    pub name: &'static str,
+    /// Arguments that always follow the program (`wezterm start`).
+    pub base_args: &'static [&'static str],
+    /// Goes before the command: `--`, `-e`, `-x`, or nothing when the
+    /// command is simply appended.
+    pub exec_flag: Option<&'static str>,
+    /// Keeps the window open after the command exits.
+    pub hold_flag: Option<&'static str>,
+    /// Prefix of the working directory option, joined with the path.
+    pub cwd_flag: Option<&'static str>,
+}
+
+/// Terminals we know the argument conventions of, in detection order.
+pub const KNOWN_TERMINALS: &[TerminalStyle] = &[
+    TerminalStyle { name: "gnome-terminal", base_args: &[], exec_flag: Some("--"), hold_flag: None, cwd_flag: Some("--working-directory=") },
+    TerminalStyle { name: "ptyxis", base_args: &["--standalone"], exec_flag: Some("--"), hold_flag: None, cwd_flag: Some("--working-directory=") },
+    TerminalStyle { name: "kgx", base_args: &[], exec_flag: Some("--"), hold_flag: None, cwd_flag: Some("--working-directory=") },
+    TerminalStyle { name: "konsole", base_args: &[], exec_flag: Some("-e"), hold_flag: Some("--hold"), cwd_flag: Some("--workdir=") },
+    TerminalStyle { name: "xfce4-terminal", base_args: &[], exec_flag: Some("-x"), hold_flag: Some("--hold"), cwd_flag: Some("--working-directory=") },
+    TerminalStyle { name: "kitty", base_args: &[], exec_flag: None, hold_flag: Some("--hold"), cwd_flag: Some("--directory=") },
+    TerminalStyle { name: "alacritty", base_args: &[], exec_flag: Some("-e"), hold_flag: Some("--hold"), cwd_flag: Some("--working-directory=") },
+    TerminalStyle { name: "wezterm", base_args: &["start"], exec_flag: Some("--"), hold_flag: None, cwd_flag: Some("--cwd=") },
+    TerminalStyle { name: "foot", base_args: &[], exec_flag: None, hold_flag: Some("--hold"), cwd_flag: Some("--working-directory=") },
+    TerminalStyle { name: "xterm", base_args: &[], exec_flag: Some("-e"), hold_flag: Some("-hold"), cwd_flag: None },
+    TerminalStyle { name: "x-terminal-emulator", base_args: &[], exec_flag: Some("-e"), hold_flag: None, cwd_flag: None },
+];
+
+/// Runs the default terminal per the xdg-terminal-exec proposal; takes the
+/// command as plain arguments.
+const XDG_TERMINAL_EXEC: TerminalStyle =
+    TerminalStyle { name: "xdg-terminal-exec", base_args: &[], exec_flag: None, hold_flag: None, cwd_flag: None };
+
+/// `$TERMINAL` and custom commands follow the common `-e` convention.
+const GENERIC_TERMINAL: TerminalStyle =
+    TerminalStyle { name: "", base_args: &[], exec_flag: Some("-e"), hold_flag: None, cwd_flag: None };
+
+/// Runs `"$@"`, then waits for Enter, for terminals without a hold option.
+const HOLD_SCRIPT: &str = r#""$@"; status=$?; printf '\n[Exited with status %d. Press Enter to close.]' "$status"; read -r _"#;
+
+/// A terminal picked for launching: its program, extra arguments and style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Terminal {
+    pub program: String,
+    pub args: Vec<String>,
+    pub style: TerminalStyle,
+}
+
+impl Terminal {
+    /// Parses a command line such as `wezterm start --always-new-process`,
+    /// split into words like a shell would. Arguments given by the user
+    /// follow the style's base arguments unless they already start with them.
+    fn from_command_line(command_line: &str) -> Option<Self> {
+        let mut words = shlex::split(command_line)?.into_iter();
+        let program = words.next()?;
+        let user_args: Vec<String> = words.collect();
+        let name = program.rsplit('/').next().unwrap_or(&program);
+        let style = KNOWN_TERMINALS.iter()
+            .chain(std::iter::once(&XDG_TERMINAL_EXEC))
+            .find(|t| t.name == name)
+            .copied()
+            .unwrap_or(GENERIC_TERMINAL);
+        let mut args: Vec<String> = style.base_args.iter().map(|a| a.to_string()).collect();
+        if user_args.starts_with(&args) {
+            args.clear();
+        }
+        args.extend(user_args);
+        Some(Self { program, args, style })
+    }
+
+    /// The terminal running `command` (or a shell) in `cwd`.
+    pub fn command(&self, command: Option<&CommandSpec>, cwd: Option<&str>, hold: bool) -> CommandSpec {
+        let cwd = command.and_then(|c| c.cwd.as_deref()).or(cwd);
+        let mut args = self.args.clone();
+
+        if let (Some(flag), Some(cwd)) = (self.style.cwd_flag, cwd) {
+            args.push(format!("{}{}", flag, cwd));
+        }
+        if let Some(command) = command {
+            let mut argv = command.argv();
+            if hold {
+                match self.style.hold_flag {
+                    Some(flag) => args.push(flag.to_string()),
+                    None => argv = CommandSpec::script(HOLD_SCRIPT, argv).argv(),
+                }
+            }
+            if let Some(flag) = self.style.exec_flag {
+                args.push(flag.to_string());
+            }
+            args.extend(argv);
+        }
+
+        let mut spec = CommandSpec::new(&self.program, args);
+        // Terminals without a directory option start where they are started
+        spec.cwd = cwd.map(str::to_string);
+        spec.env = command.map(|c| c.env.clone()).unwrap_or_default();
+        spec
+    }
+}
+
+/// Picks the terminal: the configured one, then `$TERMINAL`, then
+/// `xdg-terminal-exec`, then the first known terminal that is installed.
+pub fn resolve_terminal(configured: Option<&str>, env_terminal: Option<&str>, is_installed: impl Fn(&str) -> bool) -> Terminal {
+    if let Some(terminal) = configured.and_then(Terminal::from_command_line) {
+        return terminal;
+    }
+    let from_env = env_terminal
+        .and_then(Terminal::from_command_line)
+        .filter(|t| is_installed(&t.program));
+    if let Some(terminal) = from_env {
+        return terminal;
+    }
+    std::iter::once(&XDG_TERMINAL_EXEC)
+        .chain(KNOWN_TERMINALS)
+        .find(|t| is_installed(t.name))
+        .or(KNOWN_TERMINALS.last())
+        .and_then(|t| Terminal::from_command_line(t.name))
+        .expect("x-terminal-emulator is always a fallback")
+}
+
+/// The terminal that would be used without a configured one.
+pub fn detected_terminal() -> String {
+    let env_terminal = std::env::var("TERMINAL").ok();
+    resolve_terminal(None, env_terminal.as_deref(), is_installed).program
+}
+
+fn is_installed(program: &str) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    let runnable = |p: &Path| std::fs::metadata(p).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0);
+
+    if program.contains('/') {
+        return runnable(Path::new(program));
+    }
+    std::env::var("PATH")
+        .unwrap_or_default()
+        .split(':')
+        .any(|dir| runnable(&Path::new(dir).join(program)))
+}
+
+/// Launches terminals per the user's settings. Detection runs on every use,
+/// so installing a terminal or changing the setting takes effect immediately.
+pub struct TerminalAdapter {
+    settings: Arc<SettingsStore>,
+}
+
+impl TerminalAdapter {
+    pub fn new(settings: Arc<SettingsStore>) -> Self {
+        Self { settings }
+    }
+}
+
+impl ITerminalLauncher for TerminalAdapter {
+    fn terminal_command(&self, command: Option<&CommandSpec>, cwd: Option<&str>) -> CommandSpec {
+        let env_terminal = std::env::var("TERMINAL").ok();
+        let terminal = resolve_terminal(self.settings.get_terminal().as_deref(), env_terminal.as_deref(), is_installed);
+        terminal.command(command, cwd, self.settings.get_terminal_hold())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terminal(name: &str) -> Terminal {
+        Terminal::from_command_line(name).unwrap()
+    }
+
+    #[test]
+    fn test_detection_order() {
+        let installed = |names: &'static [&'static str]| move |p: &str| names.contains(&p);
+
+        assert_eq!(resolve_terminal(Some("foot"), Some("kitty"), installed(&["kitty"])).program, "foot");
+        assert_eq!(resolve_terminal(None, Some("kitty"), installed(&["kitty", "xdg-terminal-exec"])).program, "kitty");
+        // A $TERMINAL that isn't installed is ignored
+        assert_eq!(resolve_terminal(None, Some("urxvt"), installed(&["alacritty"])).program, "alacritty");
+        assert_eq!(resolve_terminal(None, None, installed(&["alacritty", "xdg-terminal-exec"])).program, "xdg-terminal-exec");
+        assert_eq!(resolve_terminal(None, None, installed(&["konsole", "kitty"])).program, "konsole");
+        assert_eq!(resolve_terminal(None, None, installed(&[])).program, "x-terminal-emulator");
+    }
+
+    #[test]
+    fn test_argument_styles() {
+        let htop = CommandSpec::new("htop", ["-d", "10"]);
+        let argv = |name: &str| terminal(name).command(Some(&htop), None, false).argv();
+
+        assert_eq!(argv("gnome-terminal"), ["gnome-terminal", "--", "htop", "-d", "10"]);
+        assert_eq!(argv("kitty"), ["kitty", "htop", "-d", "10"]);
+        assert_eq!(argv("alacritty"), ["alacritty", "-e", "htop", "-d", "10"]);
+        assert_eq!(argv("wezterm"), ["wezterm", "start", "--", "htop", "-d", "10"]);
+        assert_eq!(argv("foot"), ["foot", "htop", "-d", "10"]);
+        assert_eq!(argv("konsole"), ["konsole", "-e", "htop", "-d", "10"]);
+        assert_eq!(argv("/opt/bin/my-term --single"), ["/opt/bin/my-term", "--single", "-e", "htop", "-d", "10"]);
+        // User arguments come after the ones the terminal needs
+        assert_eq!(argv("wezterm --config-file 'my config.lua'"), ["wezterm", "start", "--config-file", "my config.lua", "--", "htop", "-d", "10"]);
+        assert_eq!(argv("wezterm start --always-new-process")[..3], ["wezterm", "start", "--always-new-process"]);
+        assert_eq!(argv("ptyxis --tab")[..3], ["ptyxis", "--standalone", "--tab"]);
+        assert_eq!(argv("'/opt/My Term/term'")[..2], ["/opt/My Term/term", "-e"]);
+    }
+
+    #[test]
+    fn test_working_directory_and_hold() {
+        let open = terminal("wezterm").command(None, Some("/tmp/a b"), false);
+        assert_eq!(open.argv(), ["wezterm", "start", "--cwd=/tmp/a b"]);
+        assert_eq!(open.cwd.as_deref(), Some("/tmp/a b"));
+
+        let make = CommandSpec::shell("make");
+        assert_eq!(terminal("kitty").command(Some(&make), None, true).argv(), ["kitty", "--hold", "sh", "-c", "make"]);
+
+        // No hold option: wait for Enter in a wrapper script
+        let held = terminal("gnome-terminal").command(Some(&make), None, true).argv();
+        assert_eq!(held[..4], ["gnome-terminal", "--", "sh", "-c"]);
+        assert_eq!(held[5..], ["sh", "sh", "-c", "make"]);
+    }
+}
@@ -6,9 +6,27 @@ use gtk4::{
     ComboBoxText, FlowBox, SelectionMode
 };
 use std::sync::Arc;
-use crate::application::use_cases::omnibar::Omnibar;
+use crate::application::use_cases::omnibar::{ChatEnter, Omnibar};
 use crate::application::use_cases::execute_command::ExecuteCommand;
 use crate::infrastructure::services::settings_store::SettingsStore;
+use crate::infrastructure::system::terminal_adapter::detected_terminal;
+use crate::application::providers::file_provider::FileBrowserProvider;
+use crate::application::providers::macro_provider::MacroProvider;
+use crate::application::providers::shortcut_provider::ShortcutProvider;
+use crate::domain::model::{Action, AiAnswer, ChatRole, Citation, InternalCommand, LaunchFailure, LlmBackend, LlmProtocol, SearchResult};
+use crate::infrastructure::services::llm_router::adapter_for;
+use crate::infrastructure::ui::markdown::markdown_to_pango;
+
+/// How long an error toast stays under the entry.
+const TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(5);
+/// Bytes of a file read for the text preview.
+const PREVIEW_BYTES: usize = 8192;
+/// Lines of a file shown in the text preview.
+const PREVIEW_LINES: usize = 40;
+const PREVIEW_WIDTH: i32 = 260;
+
+/// Results shown in the list, in row order; rows are activated by index.
+type Results = std::rc::Rc<std::cell::RefCell<Vec<SearchResult>>>;
 
 // UI Dependencies wrapper
 #[derive(Clone)]
@@ -93,6 +111,19 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
         .grid-item:hover {
             background-color: rgba(255, 255, 255, 0.15);
         }
+        .toast {
+            color: #ffb4a9;
+            font-size: 13px;
+            margin: 0 12px 6px 12px;
+        }
+        .preview {
+            margin-left: 10px;
+        }
+        .preview-text {
+            font-family: monospace;
+            font-size: 11px;
+            color: #d0d0d0;
+        }
         .time-status {
             color: #ff6161ff; /* Pastel Orange/Coral */
             font-weight: bold;
@@ -124,9 +155,37 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
     time_label.add_css_class("time-status");
     entry_box.append(&time_label);
 
+    // Error messages when something fails to launch
+    let toast = Label::new(None);
+    toast.set_widget_name("toast");
+    toast.add_css_class("toast");
+    toast.set_halign(gtk4::Align::Start);
+    toast.set_wrap(true);
+    toast.set_visible(false);
+    main_box.append(&toast);
+
     let list_box = ListBox::new();
     list_box.set_visible(false); // Hidden initially
-    main_box.append(&list_box);
+
+    // Scrolls once a directory listing outgrows the window
+    let results_scroll = ScrolledWindow::builder()
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(480)
+        .hexpand(true)
+        .child(&list_box)
+        .build();
+
+    // Preview of the selected file in the f mode
+    let preview = gtk4::Box::new(Orientation::Vertical, 0);
+    preview.add_css_class("preview");
+    preview.set_size_request(PREVIEW_WIDTH, -1);
+    preview.set_visible(false);
+
+    let results_box = gtk4::Box::new(Orientation::Horizontal, 0);
+    results_box.append(&results_scroll);
+    results_box.append(&preview);
+    main_box.append(&results_box);
 
     // Overview Grid (Zero State)
     let overview_scroll = ScrolledWindow::builder()
@@ -141,25 +200,42 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
     overview_scroll.set_child(Some(&overview_box));
     main_box.append(&overview_scroll);
 
-    let current_cmds = std::rc::Rc::new(std::cell::RefCell::new(Vec::<String>::new()));
+    let current_results: Results = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    // Results to return to when the Ctrl+Enter sub-action list is open
+    let parent_results = std::rc::Rc::new(std::cell::RefCell::new(None::<Vec<SearchResult>>));
 
     let ctx_clone = ctx.clone();
     let list_box_clone = list_box.clone();
     let window_clone = window.clone();
-    let cmds_clone = current_cmds.clone();
+    let results_clone = current_results.clone();
+    let parent_clone = parent_results.clone();
     let overview_scroll_clone = overview_scroll.clone();
 
     // On text change -> search
     entry.connect_changed(move |e| {
         let query_text = e.text();
         let query = query_text.as_str();
+
+        // In a chat the list keeps the conversation while the next question
+        // is typed; leaving AI mode closes the chat
+        if ctx_clone.omnibar.chat_open() {
+            let ai_prefix = ctx_clone.omnibar.prefix_for("ai").unwrap_or_else(|| "? ".to_string());
+            if query.starts_with(&ai_prefix) {
+                overview_scroll_clone.set_visible(false);
+                // Typing a question takes precedence over a selected source
+                list_box_clone.unselect_all();
+                return;
+            }
+            ctx_clone.omnibar.end_chat();
+        }
         
         // Remove all children
         while let Some(child) = list_box_clone.first_child() {
             list_box_clone.remove(&child);
         }
         
-        cmds_clone.borrow_mut().clear();
+        results_clone.borrow_mut().clear();
+        parent_clone.borrow_mut().take(); // Typing leaves the sub-action list
         overview_scroll_clone.set_visible(false); // Hide overview on type
 
         if query.is_empty() {
@@ -168,185 +244,93 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
             return;
         }
 
-        let results = ctx_clone.omnibar.search(query);
+        // Directory listings scroll; elsewhere show the top 9 to match Ctrl+1-9
+        let files_prefix = ctx_clone.omnibar.prefix_for("files").unwrap_or_else(|| "f ".to_string());
+        let limit = if query.starts_with(&files_prefix) { usize::MAX } else { 9 };
+        let results: Vec<SearchResult> = ctx_clone.omnibar.search(query)
+            .into_iter()
+            .take(limit)
+            .collect();
         
         if results.is_empty() {
              list_box_clone.set_visible(false);
         } else {
              list_box_clone.set_visible(true);
-             let mut cmds = cmds_clone.borrow_mut();
-             
-             for app in results.iter().take(9) { // Show top 9 to match Ctrl+1-9
-                 let row = ListBoxRow::new();
-                 let row_box = gtk4::Box::new(Orientation::Horizontal, 10);
-                 
-                 // Icon
-                 if let Some(icon_str) = &app.icon {
-                    let image = gtk4::Image::new();
-                    // Check if it's a path or icon name
-                    if std::path::Path::new(icon_str).exists() {
-                        image.set_from_file(Some(icon_str));
-                    } else {
-                        image.set_icon_name(Some(icon_str));
-                    }
-                    image.set_pixel_size(24);
-                    row_box.append(&image);
-                 }
-
-                 let label = Label::new(Some(&app.name));
-                 label.set_halign(gtk4::Align::Start);
-                 label.set_margin_top(5);
-                 label.set_margin_bottom(5);
-                 label.set_wrap(true);
-                 label.set_wrap_mode(gtk4::pango::WrapMode::WordChar);
-                 label.set_max_width_chars(50); // Prevent limitless expansion
-                 label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
-                 label.set_lines(5); // Show up to 5 lines of definition
-                 
-                 if app.is_running {
-                    label.add_css_class("running-app");
-                    
-                    let dot = gtk4::Box::new(Orientation::Horizontal, 0);
-                    dot.add_css_class("running-dot");
-                    dot.set_valign(gtk4::Align::Center);
-                    row_box.append(&dot);
-                 }
-
-                 row_box.append(&label);
-                 row.set_child(Some(&row_box));
-                 
-                 list_box_clone.append(&row);
-                 cmds.push(app.exec_path.clone());
-             }
-             
-             // Select first row strictly
-             if let Some(row) = list_box_clone.row_at_index(0) {
-                 list_box_clone.select_row(Some(&row));
-             }
+             render_results(&list_box_clone, &results);
         }
+        *results_clone.borrow_mut() = results;
+    });
+
+    // Preview the selected file and keep it scrolled into view
+    let ctx_preview = ctx.clone();
+    let results_preview = current_results.clone();
+    let entry_preview = entry.clone();
+    let window_preview = window.clone();
+    list_box.connect_row_selected(move |list_box, row| {
+        let Some(row) = row else {
+            show_preview(&preview, None, &window_preview);
+            return;
+        };
+        scroll_to_row(list_box, row);
+        let files_prefix = ctx_preview.omnibar.prefix_for("files").unwrap_or_else(|| "f ".to_string());
+        let path = results_preview.borrow().get(row.index() as usize)
+            .filter(|_| entry_preview.text().starts_with(&files_prefix))
+            .and_then(|r| match &r.action {
+                Action::OpenPath(path) if std::path::Path::new(path).is_file() => Some(path.clone()),
+                _ => None,
+            });
+        let mime = path.as_ref().map(|p| ctx_preview.omnibar.mime.mime_type(p));
+        show_preview(&preview, path.as_deref().zip(mime.as_deref()), &window_preview);
     });
 
     let ctx_clone_exec = ctx.clone();
     let list_box_exec = list_box.clone();
     let window_exec = window.clone();
-    let cmds_exec = current_cmds.clone();
+    let results_exec = current_results.clone();
     
     // On Enter -> Execute Selected
     entry.connect_activate(move |e| {
+        // In a chat, Enter opens a selected source or sends the next question
+        if ctx_clone_exec.omnibar.chat_open() {
+            let selected = list_box_exec.selected_row()
+                .and_then(|row| results_exec.borrow().get(row.index() as usize).cloned());
+            match ctx_clone_exec.omnibar.chat_enter(e.text().as_str(), selected.as_ref()) {
+                ChatEnter::Activate(action) => activate_action(&action, &ctx_clone_exec, &window_exec, e, &list_box_exec, &results_exec),
+                ChatEnter::Ask(question) => run_ai_query(&question, &ctx_clone_exec, e, &list_box_exec, &results_exec),
+                ChatEnter::Nothing => {},
+            }
+            return;
+        }
         if let Some(row) = list_box_exec.selected_row() {
             let idx = row.index() as usize;
             
-            // Clone the command to drop the borrow immediately
-            let cmd_opt = cmds_exec.borrow().get(idx).cloned();
-            
-            if let Some(cmd) = cmd_opt {
-                if cmd.starts_with("internal:") {
-                    if cmd == "internal:quit" {
-                        if let Some(app) = window_exec.application() { app.quit(); }
-                    } else if cmd == "internal:about" {
-                        show_about_dialog(&window_exec);
-                    } else if cmd == "internal:settings" {
-                        show_settings_dialog(&window_exec, &ctx_clone_exec);
-                    } else if let Some(prompt) = cmd.strip_prefix("internal:ai:") {
-                         // 1. Clear list to show we are doing something, distinct from search results
-                         while let Some(row) = list_box_exec.row_at_index(0) {
-                             list_box_exec.remove(&row);
-                         }
-                         
-                         // 2. Add Thinking row
-                         let row = ListBoxRow::new();
-                         let box_ = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
-                         box_.set_margin_top(12);
-                         box_.set_margin_bottom(12);
-                         box_.set_margin_start(12);
-                         box_.set_margin_end(12);
-                         
-                         let spinner = gtk4::Spinner::new();
-                         spinner.start();
-                         box_.append(&spinner);
-                         
-                         let label = Label::new(Some("Thinking..."));
-                         box_.append(&label);
-                         
-                         row.set_child(Some(&box_));
-                         row.set_activatable(false);
-                         list_box_exec.append(&row);
-                         
-                         let ctx_ai_exec = ctx_clone_exec.clone();
-                         let prompt_str = prompt.to_string();
-                         let list_box_weak = list_box_exec.downgrade();
-                         
-                         let (sender, receiver) = std::sync::mpsc::channel();
-                         std::thread::spawn(move || {
-                             let response = match ctx_ai_exec.omnibar.query_ai(&prompt_str) {
-                                 Ok(r) => r,
-                                 Err(e) => format!("Error: {}", e),
-                             };
-                             let _ = sender.send(response);
-                         });
+            // Clone the action to drop the borrow immediately
+            let action_opt = results_exec.borrow().get(idx).map(|r| r.action.clone());
 
-                         glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-                             if let Ok(response) = receiver.try_recv() {
-                                 if let Some(lb) = list_box_weak.upgrade() {
-                                     // Clear "Thinking" (or everything to be safe)
-                                     while let Some(r) = lb.row_at_index(0) {
-                                         lb.remove(&r);
-                                     }
-                                     
-                                     // Add Result
-                                     let row = ListBoxRow::new();
-                                     let box_ = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
-                                     box_.set_margin_top(12);
-                                     box_.set_margin_bottom(12);
-                                     box_.set_margin_start(12);
-                                     box_.set_margin_end(12);
-                                     // Top align for long text
-                                     box_.set_valign(gtk4::Align::Start);
-                                     
-                                     let icon = gtk4::Image::from_icon_name("dialog-information");
-                                     icon.set_pixel_size(24);
-                                     icon.set_valign(gtk4::Align::Start);
-                                     box_.append(&icon);
-                                     
-                                     let label = Label::new(Some(&response));
-                                     label.set_wrap(true);
-                                     label.set_wrap_mode(gtk4::pango::WrapMode::Word);
-                                     label.set_xalign(0.0);
-                                     label.set_valign(gtk4::Align::Start);
-                                     label.set_hexpand(true);
-                                     // Allow selecting text if necessary, but ListBoxRow steals clicks usually. 
-                                     // label.set_selectable(true); 
-                                     
-                                     box_.append(&label);
-                                     row.set_child(Some(&box_));
-                                     row.set_activatable(false); 
-                                     
-                                     lb.append(&row);
-                                 }
-                                 return glib::ControlFlow::Break;
-                             }
-                             glib::ControlFlow::Continue
-                         });
-                    } else {
-                        // Delegate other internal commands (time, window, system, macro) to executor
-                        ctx_clone_exec.execute_command.execute(&cmd);
-                        e.set_text("");
-                        window_exec.set_visible(false);
-                    }
-                } else {
-                    ctx_clone_exec.execute_command.execute(&cmd);
-                    e.set_text("");
-                    window_exec.set_visible(false); // Hide on launch
-                }
+            if let Some(action) = action_opt {
+                activate_action(&action, &ctx_clone_exec, &window_exec, e, &list_box_exec, &results_exec);
             }
         }
     });
+
+    // On click -> Execute the clicked row (chat messages aren't activatable)
+    let ctx_row = ctx.clone();
+    let window_row = window.clone();
+    let entry_row = entry.clone();
+    let results_row = current_results.clone();
+    list_box.connect_row_activated(move |list_box, row| {
+        let action = results_row.borrow().get(row.index() as usize).map(|r| r.action.clone());
+        if let Some(action) = action.filter(|a| *a != Action::None) {
+            activate_action(&action, &ctx_row, &window_row, &entry_row, list_box, &results_row);
+        }
+    });
     
     // Key Controller on Entry to handle navigation and shortcuts
     let controller = gtk4::EventControllerKey::new();
     controller.set_propagation_phase(gtk4::PropagationPhase::Capture);
     let list_box_key = list_box.clone();
-    let cmds_key = current_cmds.clone();
+    let results_key = current_results.clone();
+    let parent_key = parent_results.clone();
     let entry_key = entry.clone();
     let overview_box_key = overview_box.clone();
     let overview_scroll_key = overview_scroll.clone();
@@ -355,7 +339,74 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
 
     controller.connect_key_pressed(move |_, key, _keycode, state| {
         if key == gtk4::gdk::Key::Escape {
-             win_key.set_visible(false);
+             // Stop an answer being written first, then close the chat
+             if ctx_key_exec.omnibar.cancel_ai() {
+                 return gtk4::glib::Propagation::Stop;
+             }
+             if ctx_key_exec.omnibar.chat_open() {
+                 ctx_key_exec.omnibar.end_chat();
+                 let ai_prefix = ctx_key_exec.omnibar.prefix_for("ai").unwrap_or_else(|| "? ".to_string());
+                 entry_key.set_text(&ai_prefix);
+                 entry_key.set_position(-1);
+                 return gtk4::glib::Propagation::Stop;
+             }
+             // Close the sub-action list first, then the launcher
+             let parent = parent_key.borrow_mut().take();
+             if let Some(results) = parent {
+                 render_results(&list_box_key, &results);
+                 *results_key.borrow_mut() = results;
+             } else {
+                 win_key.set_visible(false);
+             }
+             return gtk4::glib::Propagation::Stop;
+        }
+
+        // Shift+Enter -> start a new instance even if the app already has a window
+        if (key == gtk4::gdk::Key::Return || key == gtk4::gdk::Key::KP_Enter)
+            && state.contains(gtk4::gdk::ModifierType::SHIFT_MASK)
+        {
+             let action = list_box_key.selected_row()
+                 .and_then(|row| results_key.borrow().get(row.index() as usize).map(|r| r.action.clone()));
+             if let Some(action @ Action::Launch(_)) = action {
+                 match ctx_key_exec.execute_command.execute_new_instance(&action, entry_key.text().as_str()) {
+                     Ok(()) => {
+                         entry_key.set_text("");
+                         win_key.set_visible(false);
+                     },
+                     Err(e) => show_toast(&win_key, &e),
+                 }
+                 return gtk4::glib::Propagation::Stop;
+             }
+        }
+
+        // Tab -> complete with the selected completion, or the first one
+        if key == gtk4::gdk::Key::Tab {
+             let selected = list_box_key.selected_row()
+                 .and_then(|row| results_key.borrow().get(row.index() as usize).map(|r| r.action.clone()));
+             let completion = selected
+                 .filter(|action| matches!(action, Action::Complete(_)))
+                 .or_else(|| results_key.borrow().iter().map(|r| r.action.clone()).find(|a| matches!(a, Action::Complete(_))));
+             if let Some(Action::Complete(text)) = completion {
+                 complete_entry(&entry_key, &ctx_key_exec, &text);
+                 return gtk4::glib::Propagation::Stop;
+             }
+        }
+
+        // Ctrl+Enter -> list the selected result's sub-actions
+        if (key == gtk4::gdk::Key::Return || key == gtk4::gdk::Key::KP_Enter)
+            && state.contains(gtk4::gdk::ModifierType::CONTROL_MASK)
+        {
+             let selected = list_box_key.selected_row()
+                 .and_then(|row| results_key.borrow().get(row.index() as usize).cloned());
+             let sub_actions = selected
+                 .filter(|_| parent_key.borrow().is_none())
+                 .map(|result| ctx_key_exec.omnibar.sub_actions(&result))
+                 .unwrap_or_default();
+             if !sub_actions.is_empty() {
+                 render_results(&list_box_key, &sub_actions);
+                 let parent = std::mem::replace(&mut *results_key.borrow_mut(), sub_actions);
+                 *parent_key.borrow_mut() = Some(parent);
+             }
              return gtk4::glib::Propagation::Stop;
         }
         
@@ -380,7 +431,7 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
                  }
              } else if entry_key.text().is_empty() {
                  // Show Overview Grid
-                show_overview_grid(&overview_box_key, &ctx_key_exec, &win_key, &entry_key);
+                show_overview_grid(&overview_box_key, &ctx_key_exec, &win_key, &entry_key, &list_box_key, &results_key);
                  overview_scroll_key.set_visible(true);
                  
                  // Focus first item with small delay to ensure widgets are realized
@@ -419,16 +470,43 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
 
         // Left/Right for File Browser Navigation
         let current_text = entry_key.text().to_string();
-        if current_text.starts_with("f ") {
+        let files_prefix = ctx_key_exec.omnibar.prefix_for("files").unwrap_or_else(|| "f ".to_string());
+        if current_text.starts_with(&files_prefix) {
+            // Ctrl+H shows hidden files, Ctrl+S changes the sort order
+            if state.contains(gtk4::gdk::ModifierType::CONTROL_MASK) {
+                let settings = &ctx_key_exec.settings;
+                let changed = match key.to_lower() {
+                    gtk4::gdk::Key::h => {
+                        let show = !settings.get_show_hidden_files();
+                        settings.set_show_hidden_files(show)
+                            .map(|_| if show { "Showing hidden files" } else { "Hiding hidden files" }.to_string())
+                    },
+                    gtk4::gdk::Key::s => {
+                        let sort = settings.get_file_sort().next();
+                        settings.set_file_sort(sort).map(|_| format!("Sorted by {}", sort.label()))
+                    },
+                    _ => Err(String::new()),
+                };
+                match changed {
+                    Ok(message) => {
+                        show_toast(&win_key, &message);
+                        entry_key.emit_by_name::<()>("changed", &[]);
+                        return gtk4::glib::Propagation::Stop;
+                    },
+                    Err(e) if !e.is_empty() => {
+                        show_toast(&win_key, &e);
+                        return gtk4::glib::Propagation::Stop;
+                    },
+                    Err(_) => {},
+                }
+            }
             if key == gtk4::gdk::Key::Right {
                 if let Some(row) = list_box_key.selected_row() {
                     let idx = row.index() as usize;
-                    let path_opt = cmds_key.borrow().get(idx).cloned();
-                    if let Some(path) = path_opt {
-                        // Check if it's a directory (we exec nautilus "path")
-                        if path.starts_with("nautilus \"") && path.ends_with("\"") {
-                            let clean_path = &path[10..path.len()-1]; // Strip 'nautilus "' and '"'
-                            entry_key.set_text(&format!("f {}/", clean_path));
+                    let action_opt = results_key.borrow().get(idx).map(|r| r.action.clone());
+                    if let Some(Action::OpenPath(path)) = action_opt {
+                        if std::path::Path::new(&path).is_dir() {
+                            entry_key.set_text(&format!("{}{}/", files_prefix, path));
                             entry_key.set_position(-1); // Move cursor to end
                             return gtk4::glib::Propagation::Stop;
                         }
@@ -436,14 +514,14 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
                 }
             } else if key == gtk4::gdk::Key::Left {
                 // Go up one directory
-                let path_part = current_text[2..].trim();
+                let path_part = current_text[files_prefix.len()..].trim();
                 let path = std::path::Path::new(path_part);
                 if let Some(parent) = path.parent() {
                      // Don't go above /
                      if parent == std::path::Path::new("") {
-                         entry_key.set_text("f /");
+                         entry_key.set_text(&format!("{}/", files_prefix));
                      } else {
-                         entry_key.set_text(&format!("f {}/", parent.display()));
+                         entry_key.set_text(&format!("{}{}/", files_prefix, parent.display()));
                      }
                      entry_key.set_position(-1);
                      return gtk4::glib::Propagation::Stop;
@@ -456,24 +534,14 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
              let key_val = key.to_unicode(); // char
              if let Some(c) = key_val {
                  if let Some(digit) = c.to_digit(10) {
-                     if digit >= 1 && digit <= 9 {
+                     if (1..=9).contains(&digit) {
                          let idx = (digit - 1) as usize;
                          
-                         // Clone cmd to drop borrow, preventing panic on set_text
-                         let cmd_opt = cmds_key.borrow().get(idx).cloned();
+                         // Clone action to drop borrow, preventing panic on set_text
+                         let action_opt = results_key.borrow().get(idx).map(|r| r.action.clone());
                          
-                         if let Some(cmd) = cmd_opt {
-                             if cmd.starts_with("internal:") {
-                                if cmd == "internal:quit" {
-                                    if let Some(app) = win_key.application() { app.quit(); }
-                                } else if cmd == "internal:about" {
-                                    show_about_dialog(&win_key);
-                                }
-                             } else {
-                                 ctx_key_exec.execute_command.execute(&cmd);
-                                 entry_key.set_text("");
-                                 win_key.set_visible(false); // Hide on launch
-                             }
+                         if let Some(action) = action_opt {
+                             activate_action(&action, &ctx_key_exec, &win_key, &entry_key, &list_box_key, &results_key);
                              return gtk4::glib::Propagation::Stop;
                          }
                      }
@@ -515,17 +583,690 @@ pub fn build_ui(app: &Application, ctx: AppContext) {
         gtk4::glib::ControlFlow::Continue
     });
 
+    // Launch Failure Poller: toast while open, desktop notification while hidden
+    let window_failures = window.clone();
+    let ctx_failures = ctx.clone();
+    gtk4::glib::timeout_add_local(std::time::Duration::from_millis(500), move || {
+        for failure in ctx_failures.execute_command.take_failures() {
+            if window_failures.is_visible() {
+                show_toast(&window_failures, &failure_text(&failure));
+            } else if let Some(app) = window_failures.application() {
+                let notification = gtk4::gio::Notification::new("Launch failed");
+                notification.set_body(Some(&failure_text(&failure)));
+                app.send_notification(None, &notification);
+            }
+        }
+        gtk4::glib::ControlFlow::Continue
+    });
+
     window.present();
 
     let scroll_win = overview_scroll.clone();
     let entry_win = entry.clone();
+    let toast_win = toast.clone();
     window.connect_show(move |_| {
          scroll_win.set_visible(false);
+         toast_win.set_visible(false);
          entry_win.set_text("");
          entry_win.grab_focus();
     });
 }
 
+/// Shows an icon file, or a themed icon. MIME type icons missing from the
+/// theme fall back to the generic one of their media type (`image-x-generic`).
+fn set_icon(image: &gtk4::Image, icon: &str) {
+    if std::path::Path::new(icon).exists() {
+        image.set_from_file(Some(icon));
+        return;
+    }
+    const MEDIA_TYPES: &[&str] = &["application", "audio", "font", "image", "inode", "model", "text", "video"];
+    let generic = icon.split_once('-')
+        .filter(|(media, _)| MEDIA_TYPES.contains(media))
+        .map(|(media, _)| format!("{}-x-generic", media));
+    match generic {
+        Some(generic) => image.set_from_gicon(&gtk4::gio::ThemedIcon::from_names(&[icon, &generic])),
+        None => image.set_icon_name(Some(icon)),
+    }
+}
+
+/// Fills the result list with one row per result and selects the first.
+fn render_results(list_box: &ListBox, results: &[SearchResult]) {
+    while let Some(child) = list_box.first_child() {
+        list_box.remove(&child);
+    }
+
+    for app in results {
+        list_box.append(&result_row(app));
+    }
+
+    // Select first row strictly
+    if let Some(row) = list_box.row_at_index(0) {
+        list_box.select_row(Some(&row));
+    }
+}
+
+fn result_row(app: &SearchResult) -> ListBoxRow {
+    let row = ListBoxRow::new();
+    let row_box = gtk4::Box::new(Orientation::Horizontal, 10);
+    
+    // Icon
+    if let Some(icon_str) = &app.icon {
+        let image = gtk4::Image::new();
+        set_icon(&image, icon_str);
+        image.set_pixel_size(24);
+        row_box.append(&image);
+    }
+
+    let label = Label::new(Some(&app.name));
+    label.set_halign(gtk4::Align::Start);
+    label.set_margin_top(5);
+    label.set_margin_bottom(5);
+    label.set_wrap(true);
+    label.set_wrap_mode(gtk4::pango::WrapMode::WordChar);
+    label.set_max_width_chars(50); // Prevent limitless expansion
+    label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+    label.set_lines(5); // Show up to 5 lines of definition
+    
+    if app.is_running {
+        label.add_css_class("running-app");
+       
+        let dot = gtk4::Box::new(Orientation::Horizontal, 0);
+        dot.add_css_class("running-dot");
+        dot.set_valign(gtk4::Align::Center);
+        row_box.append(&dot);
+    }
+
+    row_box.append(&label);
+
+    if let Some(detail) = &app.detail {
+        let detail_label = Label::new(Some(detail));
+        detail_label.add_css_class("dim-label");
+        detail_label.set_hexpand(true);
+        detail_label.set_halign(gtk4::Align::End);
+        detail_label.set_margin_end(5);
+        row_box.append(&detail_label);
+    }
+
+    if let Some(category) = app.category {
+        let category_label = Label::new(Some(category.label()));
+        category_label.add_css_class("dim-label");
+        category_label.set_hexpand(true);
+        category_label.set_halign(gtk4::Align::End);
+        category_label.set_margin_end(5);
+        row_box.append(&category_label);
+    }
+
+    row.set_child(Some(&row_box));
+    row
+}
+
+/// Runs the action behind a selected result or grid item.
+fn activate_action(action: &Action, ctx: &AppContext, window: &ApplicationWindow, entry: &Entry, list_box: &ListBox, results: &Results) {
+    match action {
+        Action::Internal(InternalCommand::Quit) => {
+            if let Some(app) = window.application() { app.quit(); }
+        },
+        Action::Internal(InternalCommand::About) => show_about_dialog(window),
+        Action::Internal(InternalCommand::Settings) => show_settings_dialog(window, ctx),
+        Action::Internal(InternalCommand::EditShortcut(key)) => {
+            if let Some(cmd) = ctx.omnibar.shortcuts.get(key) {
+                manage_shortcut_dialog(window.upcast_ref(), ctx, Some((key.clone(), cmd)), || {});
+            }
+        },
+        Action::Internal(InternalCommand::EditMacro(name)) => {
+            if let Some(mac) = ctx.omnibar.macros.get(name) {
+                manage_macro_dialog(window.upcast_ref(), ctx, Some(mac), || {});
+            }
+        },
+        Action::Internal(InternalCommand::RenameFile(path)) => {
+            let name = std::path::Path::new(path).file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let path = path.clone();
+            file_name_dialog(window, ctx, entry, "Rename", &name, move |new_name| FileBrowserProvider::rename_action(&path, new_name));
+        },
+        Action::Internal(InternalCommand::NewFolder(dir)) => {
+            let dir = dir.clone();
+            file_name_dialog(window, ctx, entry, "New Folder", "", move |name| FileBrowserProvider::new_folder_action(&dir, name));
+        },
+        Action::Internal(InternalCommand::DeleteShortcut(key)) => {
+            let message = format!("Delete the shortcut “{}”? This can't be undone.", key);
+            confirm_dialog(window, ctx, entry, "Delete Shortcut", &message, ShortcutProvider::delete_action(key));
+        },
+        Action::Internal(InternalCommand::DeleteMacro(name)) => {
+            let message = format!("Delete the macro “{}”? This can't be undone.", name);
+            confirm_dialog(window, ctx, entry, "Delete Macro", &message, MacroProvider::delete_action(name));
+        },
+        // File operations keep the launcher open on the refreshed listing
+        Action::Provider { id, .. } if id == "files" => match ctx.execute_command.execute(action, entry.text().as_str()) {
+            Ok(()) => entry.emit_by_name::<()>("changed", &[]),
+            Err(e) => show_toast(window, &e),
+        },
+        Action::AskAi(prompt) if prompt.is_empty() => {
+            // Switch the entry to AI mode so the user can type the question of a new chat
+            ctx.omnibar.end_chat();
+            let ai_prefix = ctx.omnibar.prefix_for("ai").unwrap_or_else(|| "? ".to_string());
+            entry.grab_focus();
+            entry.set_text(&ai_prefix);
+            entry.set_position(-1);
+        },
+        Action::AskAi(prompt) => run_ai_query(prompt, ctx, entry, list_box, results),
+        Action::ResumeChat(id) => show_chat(id, ctx, entry, list_box, results),
+        Action::Complete(text) => complete_entry(entry, ctx, text),
+        Action::None => {},
+        _ => match ctx.execute_command.execute(action, entry.text().as_str()) {
+            Ok(()) => {
+                entry.set_text("");
+                window.set_visible(false); // Hide on launch
+            },
+            // Stay open so the user sees what went wrong
+            Err(e) => show_toast(window, &e),
+        }
+    }
+}
+
+/// Asks for a file or folder name and runs the file operation built from it.
+fn file_name_dialog(
+    window: &ApplicationWindow,
+    ctx: &AppContext,
+    entry: &Entry,
+    title: &str,
+    initial: &str,
+    make_action: impl Fn(&str) -> Action + 'static,
+) {
+    let dialog = Window::builder()
+        .transient_for(window)
+        .modal(true)
+        .title(title)
+        .default_width(300)
+        .build();
+
+    let vbox = gtk4::Box::new(Orientation::Vertical, 10);
+    vbox.set_margin_top(10);
+    vbox.set_margin_bottom(10);
+    vbox.set_margin_start(10);
+    vbox.set_margin_end(10);
+
+    let name_entry = Entry::new();
+    name_entry.set_text(initial);
+    // Select the name without its extension, like file managers do
+    let stem = initial.rfind('.').filter(|&i| i > 0).unwrap_or(initial.len());
+    name_entry.select_region(0, initial[..stem].chars().count() as i32);
+
+    let btn_box = gtk4::Box::new(Orientation::Horizontal, 10);
+    let save_btn = Button::with_label(if initial.is_empty() { "Create" } else { "Rename" });
+    let cancel_btn = Button::with_label("Cancel");
+    btn_box.append(&save_btn);
+    btn_box.append(&cancel_btn);
+
+    vbox.append(&Label::new(Some("Name:")));
+    vbox.append(&name_entry);
+    vbox.append(&btn_box);
+    dialog.set_child(Some(&vbox));
+
+    let dialog_weak = dialog.downgrade();
+    cancel_btn.connect_clicked(move |_| {
+        if let Some(d) = dialog_weak.upgrade() { d.close(); }
+    });
+
+    let ctx_clone = ctx.clone();
+    let entry_clone = entry.clone();
+    let dialog_weak_save = dialog.downgrade();
+    let save = move |name_entry: &Entry| {
+        let action = make_action(name_entry.text().as_str());
+        match ctx_clone.execute_command.execute(&action, "") {
+            Ok(()) => {
+                entry_clone.emit_by_name::<()>("changed", &[]);
+                if let Some(d) = dialog_weak_save.upgrade() { d.close(); }
+            },
+            Err(e) => {
+                if let Some(d) = dialog_weak_save.upgrade() { show_error_dialog(&d, &e); }
+            },
+        }
+    };
+    let save = std::rc::Rc::new(save);
+    let save_click = save.clone();
+    let name_entry_click = name_entry.clone();
+    save_btn.connect_clicked(move |_| save_click(&name_entry_click));
+    name_entry.connect_activate(move |e| save(e));
+
+    dialog.present();
+}
+
+/// Asks whether to go ahead with a destructive action and runs it if so.
+fn confirm_dialog(window: &ApplicationWindow, ctx: &AppContext, entry: &Entry, title: &str, message: &str, action: Action) {
+    let dialog = Window::builder()
+        .transient_for(window)
+        .modal(true)
+        .title(title)
+        .default_width(300)
+        .build();
+
+    let vbox = gtk4::Box::new(Orientation::Vertical, 10);
+    vbox.set_margin_top(10);
+    vbox.set_margin_bottom(10);
+    vbox.set_margin_start(10);
+    vbox.set_margin_end(10);
+
+    let label = Label::new(Some(message));
+    label.set_wrap(true);
+    let btn_box = gtk4::Box::new(Orientation::Horizontal, 10);
+    let delete_btn = Button::with_label("Delete");
+    delete_btn.add_css_class("destructive-action");
+    let cancel_btn = Button::with_label("Cancel");
+    btn_box.append(&delete_btn);
+    btn_box.append(&cancel_btn);
+
+    vbox.append(&label);
+    vbox.append(&btn_box);
+    dialog.set_child(Some(&vbox));
+
+    let dialog_weak = dialog.downgrade();
+    cancel_btn.connect_clicked(move |_| {
+        if let Some(d) = dialog_weak.upgrade() { d.close(); }
+    });
+
+    let ctx_clone = ctx.clone();
+    let entry_clone = entry.clone();
+    let dialog_weak_delete = dialog.downgrade();
+    delete_btn.connect_clicked(move |_| {
+        match ctx_clone.execute_command.execute(&action, "") {
+            Ok(()) => {
+                entry_clone.emit_by_name::<()>("changed", &[]);
+                if let Some(d) = dialog_weak_delete.upgrade() { d.close(); }
+            },
+            Err(e) => {
+                if let Some(d) = dialog_weak_delete.upgrade() { show_error_dialog(&d, &e); }
+            },
+        }
+    });
+
+    // Cancel is the safe default for Enter
+    dialog.set_default_widget(Some(&cancel_btn));
+    cancel_btn.grab_focus();
+    dialog.present();
+}
+
+/// Scrolls the result list so the selected row is visible.
+fn scroll_to_row(list_box: &ListBox, row: &ListBoxRow) {
+    let Some(scroll) = list_box.ancestor(ScrolledWindow::static_type()).and_downcast::<ScrolledWindow>() else { return };
+    if let Some(bounds) = row.compute_bounds(list_box) {
+        let top = bounds.y() as f64;
+        scroll.vadjustment().clamp_page(top, top + bounds.height() as f64);
+    }
+}
+
+/// Shows an image or the first lines of a text file beside the results;
+/// hides the pane for anything else.
+fn show_preview(preview: &gtk4::Box, file: Option<(&str, &str)>, window: &ApplicationWindow) {
+    while let Some(child) = preview.first_child() {
+        preview.remove(&child);
+    }
+    let widget: Option<gtk4::Widget> = file.and_then(|(path, mime)| {
+        if mime.starts_with("image/") {
+            let picture = gtk4::Picture::for_filename(path);
+            picture.set_can_shrink(true);
+            picture.set_size_request(PREVIEW_WIDTH, PREVIEW_WIDTH);
+            return Some(picture.upcast());
+        }
+        let text = preview_text(path)?;
+        let label = Label::new(Some(&text));
+        label.add_css_class("preview-text");
+        label.set_halign(gtk4::Align::Start);
+        label.set_valign(gtk4::Align::Start);
+        label.set_xalign(0.0);
+        label.set_max_width_chars(36);
+        label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+        Some(label.upcast())
+    });
+
+    match widget {
+        Some(widget) => {
+            preview.append(&widget);
+            preview.set_visible(true);
+        },
+        None if preview.is_visible() => {
+            preview.set_visible(false);
+            // Let the window shrink back to its usual width
+            window.set_default_size(630, -1);
+        },
+        None => {},
+    }
+}
+
+/// The start of a file, if it looks like text.
+fn preview_text(path: &str) -> Option<String> {
+    use std::io::Read;
+    let mut bytes = Vec::with_capacity(PREVIEW_BYTES);
+    std::fs::File::open(path).ok()?
+        .take(PREVIEW_BYTES as u64)
+        .read_to_end(&mut bytes).ok()?;
+    // NUL bytes mean a binary file
+    if bytes.contains(&0) {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    Some(text.lines().take(PREVIEW_LINES).collect::<Vec<_>>().join("\n"))
+}
+
+/// Replaces the query after the mode prefix, keeping the prefix.
+fn complete_entry(entry: &Entry, ctx: &AppContext, text: &str) {
+    let current = entry.text().to_string();
+    let prefix = ctx.omnibar.mode_prefix(&current).to_string();
+    entry.grab_focus();
+    entry.set_text(&format!("{}{}", prefix, text));
+    entry.set_position(-1);
+}
+
+/// Shows a message under the entry for a few seconds.
+fn show_toast(window: &ApplicationWindow, message: &str) {
+    let Some(toast) = find_toast(window) else { return };
+    toast.set_text(message);
+    toast.set_visible(true);
+
+    let toast_weak = toast.downgrade();
+    let shown = message.to_string();
+    gtk4::glib::timeout_add_local_once(TOAST_DURATION, move || {
+        // A newer message keeps its own full duration
+        if let Some(toast) = toast_weak.upgrade().filter(|t| t.text() == shown) {
+            toast.set_visible(false);
+        }
+    });
+}
+
+fn find_toast(window: &ApplicationWindow) -> Option<Label> {
+    let main_box = window.child()?;
+    let mut child = main_box.first_child();
+    while let Some(widget) = child {
+        if widget.widget_name() == "toast" {
+            return widget.downcast().ok();
+        }
+        child = widget.next_sibling();
+    }
+    None
+}
+
+/// "firefox: error message", using the program name and the first line of its stderr.
+fn failure_text(failure: &LaunchFailure) -> String {
+    let program = failure.command.split_whitespace().next()
+        .and_then(|p| p.rsplit('/').next())
+        .unwrap_or(&failure.command);
+    let message = failure.message.lines().next().unwrap_or_default();
+    format!("{}: {}", program, message)
+}
+
+/// A chat message row: an icon beside wrapping text. Returns the row, its
+/// box and the text label.
+fn chat_row(icon: &impl IsA<gtk4::Widget>, text: &str) -> (ListBoxRow, gtk4::Box, Label) {
+    let row = ListBoxRow::new();
+    let box_ = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    box_.set_margin_top(12);
+    box_.set_margin_bottom(12);
+    box_.set_margin_start(12);
+    box_.set_margin_end(12);
+    // Top align for long text
+    box_.set_valign(gtk4::Align::Start);
+
+    icon.set_valign(gtk4::Align::Start);
+    box_.append(icon);
+
+    let label = Label::new(Some(text));
+    label.set_wrap(true);
+    label.set_wrap_mode(gtk4::pango::WrapMode::Word);
+    label.set_xalign(0.0);
+    label.set_valign(gtk4::Align::Start);
+    label.set_hexpand(true);
+    box_.append(&label);
+
+    row.set_child(Some(&box_));
+    row.set_activatable(false);
+    (row, box_, label)
+}
+
+fn chat_icon(name: &str) -> gtk4::Image {
+    let icon = gtk4::Image::from_icon_name(name);
+    icon.set_pixel_size(24);
+    icon
+}
+
+/// Appends a question and its answer to the chat, plus the answer's sources.
+fn append_chat_turn(ctx: &AppContext, list_box: &ListBox, results: &Results, question: &str, answer: &str, citations: &[Citation]) {
+    list_box.append(&chat_row(&chat_icon("avatar-default"), question).0);
+    let (row, _, label) = chat_row(&chat_icon("dialog-information"), "");
+    label.set_markup(&markdown_to_pango(answer));
+    list_box.append(&row);
+
+    let sources = ctx.omnibar.citation_results(citations);
+    for source in &sources {
+        list_box.append(&result_row(source));
+    }
+    let mut results = results.borrow_mut();
+    results.push(SearchResult::new(question, "avatar-default", Action::None));
+    results.push(SearchResult::new(answer, "dialog-information", Action::None));
+    results.extend(sources);
+}
+
+/// Scrolls the list to its end once the rows just added are laid out.
+fn scroll_to_end(list_box: &ListBox) {
+    let Some(scroll) = list_box.ancestor(ScrolledWindow::static_type()).and_downcast::<ScrolledWindow>() else { return };
+    glib::idle_add_local_once(move || {
+        let adjustment = scroll.vadjustment();
+        adjustment.set_value(adjustment.upper() - adjustment.page_size());
+    });
+}
+
+/// Reopens a saved chat: the list shows the conversation and the entry
+/// takes the next question.
+fn show_chat(id: &str, ctx: &AppContext, entry: &Entry, list_box: &ListBox, results: &Results) {
+    let Some(session) = ctx.omnibar.resume_chat(id) else { return };
+    let ai_prefix = ctx.omnibar.prefix_for("ai").unwrap_or_else(|| "? ".to_string());
+    entry.grab_focus();
+    entry.set_text(&ai_prefix);
+    entry.set_position(-1);
+
+    while let Some(row) = list_box.row_at_index(0) {
+        list_box.remove(&row);
+    }
+    results.borrow_mut().clear();
+    list_box.set_visible(true);
+
+    let mut question = "";
+    for message in &session.messages {
+        match message.role {
+            ChatRole::User => question = &message.content,
+            ChatRole::Assistant => append_chat_turn(ctx, list_box, results, question, &message.content, &message.citations),
+            ChatRole::System | ChatRole::Tool => {},
+        }
+    }
+    scroll_to_end(list_box);
+}
+
+/// Progress of an answer, sent from the thread generating it.
+enum AiUpdate {
+    Token(String),
+    Done(Result<AiAnswer, String>),
+}
+
+/// Asks the next question of the open chat, or of a new one, and streams
+/// the answer into the list below the earlier turns.
+fn run_ai_query(prompt: &str, ctx: &AppContext, entry: &Entry, list_box: &ListBox, results: &Results) {
+    // 1. A new chat replaces the search results
+    if !ctx.omnibar.chat_open() {
+        while let Some(row) = list_box.row_at_index(0) {
+            list_box.remove(&row);
+        }
+        results.borrow_mut().clear();
+        ctx.omnibar.open_chat();
+    }
+    list_box.set_visible(true);
+
+    // The entry takes the follow-up question
+    let ai_prefix = ctx.omnibar.prefix_for("ai").unwrap_or_else(|| "? ".to_string());
+    entry.set_text(&ai_prefix);
+    entry.set_position(-1);
+
+    // 2. Add the question and the answer row, thinking until the first token arrives
+    list_box.append(&chat_row(&chat_icon("avatar-default"), prompt).0);
+    let spinner = gtk4::Spinner::new();
+    spinner.set_size_request(24, 24);
+    spinner.start();
+    let (row, box_, label) = chat_row(&spinner, "Thinking...");
+    list_box.append(&row);
+    scroll_to_end(list_box);
+
+    let answer_index = {
+        let mut results = results.borrow_mut();
+        results.push(SearchResult::new(prompt, "avatar-default", Action::None));
+        results.push(SearchResult::new("", "dialog-information", Action::None));
+        results.len() - 1
+    };
+
+    let ctx_ai_exec = ctx.clone();
+    let prompt_str = prompt.to_string();
+    let list_box_weak = list_box.downgrade();
+    let results = results.clone();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let tokens = sender.clone();
+        let answer = ctx_ai_exec.omnibar.query_ai(&prompt_str, &mut |token| {
+            let _ = tokens.send(AiUpdate::Token(token.to_string()));
+        });
+        let _ = sender.send(AiUpdate::Done(answer));
+    });
+
+    let ctx = ctx.clone();
+    let mut response = String::new();
+    glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+        // Leaving the chat removed the answer
+        let Some(lb) = list_box_weak.upgrade().filter(|_| row.parent().is_some()) else {
+            return glib::ControlFlow::Break;
+        };
+
+        let mut done = None;
+        let mut changed = false;
+        while let Ok(update) = receiver.try_recv() {
+            match update {
+                AiUpdate::Token(token) => {
+                    response.push_str(&token);
+                    changed = true;
+                },
+                AiUpdate::Done(answer) => {
+                    done = Some(answer);
+                    break;
+                },
+            }
+        }
+        if changed {
+            label.set_markup(&markdown_to_pango(&response));
+            scroll_to_end(&lb);
+        }
+        let Some(answer) = done else {
+            return glib::ControlFlow::Continue;
+        };
+
+        box_.remove(&spinner);
+        let icon = chat_icon("dialog-information");
+        icon.set_valign(gtk4::Align::Start);
+        box_.prepend(&icon);
+
+        let extra_rows = match answer {
+            Ok(answer) => {
+                label.set_markup(&markdown_to_pango(&answer.text));
+                response = answer.text;
+                let mut rows = ctx.omnibar.citation_results(&answer.citations);
+                rows.extend(answer.proposals);
+                rows
+            },
+            Err(e) => {
+                response = format!("Error: {}", e);
+                label.set_text(&response);
+                vec![]
+            },
+        };
+
+        // Sources and suggested actions follow the answer. Like any result,
+        // an action only runs when the user picks it.
+        for extra in &extra_rows {
+            lb.append(&result_row(extra));
+        }
+        let mut results = results.borrow_mut();
+        if let Some(result) = results.get_mut(answer_index) {
+            result.name = std::mem::take(&mut response);
+        }
+        results.extend(extra_rows);
+        scroll_to_end(&lb);
+        glib::ControlFlow::Break
+    });
+}
+
+/// Models offered by Ollama's library.
+const OLLAMA_MODELS: &[(&str, &str)] = &[
+    ("llama3", "Llama 3 (Meta)"),
+    ("mistral", "Mistral"),
+    ("gemma", "Gemma (Google)"),
+    ("phi", "Phi (Microsoft)"),
+    ("tinyllama", "TinyLlama (1.1GB - Fast)"),
+];
+
+/// Lists the models to pick for a backend: Ollama's library, or those an
+/// OpenAI-compatible server reports, fetched in the background.
+/// Fills `combo` with the models of `backend`. `fills` counts the fills, so
+/// a slow listing doesn't overwrite the models of a backend picked since.
+fn fill_model_combo(combo: &ComboBoxText, fills: &std::rc::Rc<std::cell::Cell<u64>>, backend: &LlmBackend, current_model: &str) {
+    let fill = fills.get() + 1;
+    fills.set(fill);
+    combo.remove_all();
+    if backend.protocol == LlmProtocol::Ollama {
+        for (id, label) in OLLAMA_MODELS {
+            combo.append(Some(id), label);
+        }
+        if !combo.set_active_id(Some(current_model)) {
+            combo.set_active_id(Some("llama3"));
+        }
+        return;
+    }
+
+    combo.append(None, "Loading models...");
+    combo.set_active(Some(0));
+    let backend = backend.clone();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(adapter_for(&backend, "").list_models());
+    });
+    let combo_weak = combo.downgrade();
+    let fills = fills.clone();
+    let current_model = current_model.to_string();
+    glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+        let models = match receiver.try_recv() {
+            Ok(models) => models,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+            // The listing thread panicked
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+        };
+        let Some(combo) = combo_weak.upgrade() else { return glib::ControlFlow::Break };
+        if fills.get() != fill {
+            return glib::ControlFlow::Break;
+        }
+        combo.remove_all();
+        match models {
+            Ok(models) => {
+                for model in &models {
+                    combo.append(Some(model), model);
+                }
+                if !combo.set_active_id(Some(&current_model)) {
+                    combo.set_active(Some(0));
+                }
+            },
+            Err(e) => {
+                combo.append(None, &e);
+                combo.set_active(Some(0));
+            },
+        }
+        glib::ControlFlow::Break
+    });
+}
+
 fn show_error_dialog(parent: &gtk4::Window, message: &str) {
     let dialog = MessageDialog::new(
         Some(parent),
@@ -1189,22 +1930,45 @@ fn show_settings_dialog(window: &ApplicationWindow, ctx: &AppContext) {
     ai_box.set_margin_start(10);
     ai_box.set_margin_end(10);
 
-    let model_label = Label::new(Some("AI Model (Ollama)"));
+    let backend_label = Label::new(Some("Backend"));
+    backend_label.set_halign(gtk4::Align::Start);
+    backend_label.add_css_class("heading");
+
+    let backends = ctx.settings.get_llm_backends();
+    let backend_combo = ComboBoxText::new();
+    for backend in &backends {
+        backend_combo.append(Some(&backend.name), &format!("{} ({})", backend.name, backend.base_url));
+    }
+    backend_combo.set_active_id(Some(&ctx.settings.get_llm_backend().name));
+
+    // Connection of the selected backend
+    let url_entry = Entry::new();
+    url_entry.set_placeholder_text(Some("Address, e.g. http://127.0.0.1:8080/v1"));
+    let key_entry = gtk4::PasswordEntry::new();
+    key_entry.set_show_peek_icon(true);
+    key_entry.set_placeholder_text(Some("API key (optional)"));
+    let timeout_box = gtk4::Box::new(Orientation::Horizontal, 10);
+    let timeout_spin = gtk4::SpinButton::with_range(1.0, 3600.0, 10.0);
+    timeout_box.append(&Label::new(Some("Timeout (seconds)")));
+    timeout_box.append(&timeout_spin);
+    let show_backend = {
+        let (url_entry, key_entry, timeout_spin) = (url_entry.clone(), key_entry.clone(), timeout_spin.clone());
+        move |backend: &LlmBackend| {
+            url_entry.set_text(&backend.base_url);
+            key_entry.set_text(backend.api_key.as_deref().unwrap_or_default());
+            timeout_spin.set_value(backend.timeout_secs as f64);
+        }
+    };
+    show_backend(&ctx.settings.get_llm_backend());
+
+    let model_label = Label::new(Some("AI Model"));
     model_label.set_halign(gtk4::Align::Start);
     model_label.add_css_class("heading");
 
     let model_combo = ComboBoxText::new();
-    model_combo.append(Some("llama3"), "Llama 3 (Meta)");
-    model_combo.append(Some("mistral"), "Mistral");
-    model_combo.append(Some("gemma"), "Gemma (Google)");
-    model_combo.append(Some("phi"), "Phi (Microsoft)");
-    model_combo.append(Some("tinyllama"), "TinyLlama (1.1GB - Fast)");
-    
-    // Set current
     let current_model = ctx.settings.get_ai_model();
-    if !model_combo.set_active_id(Some(&current_model)) {
-        model_combo.set_active_id(Some("llama3"));
-    }
+    let model_fills = std::rc::Rc::new(std::cell::Cell::new(0));
+    fill_model_combo(&model_combo, &model_fills, &ctx.settings.get_llm_backend(), &current_model);
 
     let warning_label = Label::new(Some("⚠ Switching models will download the new model and delete the old one."));
     warning_label.set_wrap(true);
@@ -1216,23 +1980,61 @@ fn show_settings_dialog(window: &ApplicationWindow, ctx: &AppContext) {
     progress_bar.set_visible(false);
     progress_bar.set_show_text(true);
 
+    ai_box.append(&backend_label);
+    ai_box.append(&backend_combo);
+    ai_box.append(&url_entry);
+    ai_box.append(&key_entry);
+    ai_box.append(&timeout_box);
     ai_box.append(&model_label);
     ai_box.append(&model_combo);
     ai_box.append(&warning_label);
     ai_box.append(&progress_bar);
     ai_box.append(&save_ai_btn);
     
+    // Models come with the backend: Ollama downloads them, other servers list what they serve
+    let backends_changed = backends.clone();
+    let model_combo_backend = model_combo.clone();
+    let warning_backend = warning_label.clone();
+    warning_label.set_visible(ctx.settings.get_llm_backend().protocol == LlmProtocol::Ollama);
+    backend_combo.connect_changed(move |combo| {
+        let Some(backend) = combo.active_id().and_then(|id| backends_changed.iter().find(|b| b.name == id.as_str())) else { return };
+        warning_backend.set_visible(backend.protocol == LlmProtocol::Ollama);
+        show_backend(backend);
+        fill_model_combo(&model_combo_backend, &model_fills, backend, &current_model);
+    });
+
     let ctx_ai = ctx.clone();
     let combo_ai = model_combo.clone();
+    let backend_combo_ai = backend_combo.clone();
     let dialog_weak_ai = dialog.downgrade();
     let pb_weak = progress_bar.downgrade();
     
     save_ai_btn.connect_clicked(move |btn| {
+        if let Some(name) = backend_combo_ai.active_id() {
+            if let Some(backend) = backends.iter().find(|b| b.name == name.as_str()) {
+                let key = key_entry.text().trim().to_string();
+                let edited = LlmBackend {
+                    base_url: url_entry.text().trim().to_string(),
+                    api_key: (!key.is_empty()).then_some(key),
+                    timeout_secs: timeout_spin.value() as u64,
+                    ..backend.clone()
+                };
+                if edited != *backend && let Err(e) = ctx_ai.settings.update_llm_backend(edited) {
+                    println!("Failed to save the backend: {}", e);
+                }
+            }
+            let _ = ctx_ai.settings.set_llm_backend(&name);
+        }
         if let Some(new_model) = combo_ai.active_id() {
              let new_model_str = new_model.to_string();
              let old_model_str = ctx_ai.settings.get_ai_model();
              
-             if new_model_str != old_model_str {
+             if ctx_ai.settings.get_llm_backend().protocol != LlmProtocol::Ollama {
+                 // Nothing to download; the server already has its models
+                 let _ = ctx_ai.settings.set_ai_model(new_model_str.clone());
+                 ctx_ai.omnibar.llm.set_model(&new_model_str);
+                 if let Some(d) = dialog_weak_ai.upgrade() { d.close(); }
+             } else if new_model_str != old_model_str {
                  // Disable button
                  btn.set_sensitive(false);
                  btn.set_label("Downloading Model... Please Wait");
@@ -1301,16 +2103,138 @@ fn show_settings_dialog(window: &ApplicationWindow, ctx: &AppContext) {
 
     notebook.append_page(&ai_box, Some(&Label::new(Some("AI"))));
 
+    // TAB 4: PREFIXES
+    let prefix_box = gtk4::Box::new(Orientation::Vertical, 10);
+    prefix_box.set_margin_top(10);
+    prefix_box.set_margin_bottom(10);
+    prefix_box.set_margin_start(10);
+    prefix_box.set_margin_end(10);
+
+    let prefix_hint = Label::new(Some("Prefix typed before a query to switch mode. Leave empty to restore the default."));
+    prefix_hint.set_wrap(true);
+    prefix_hint.add_css_class("dim-label");
+    prefix_box.append(&prefix_hint);
+
+    let prefix_list = ListBox::new();
+    prefix_list.set_selection_mode(gtk4::SelectionMode::None);
+    prefix_list.add_css_class("boxed-list");
+
+    let mut prefix_entries = Vec::new();
+    let mut providers = ctx.omnibar.provider_prefixes();
+    providers.sort();
+    for (id, prefix) in providers {
+        let row = ListBoxRow::new();
+        let hbox = gtk4::Box::new(Orientation::Horizontal, 10);
+        hbox.set_margin_start(10);
+        hbox.set_margin_end(10);
+        hbox.set_margin_top(5);
+        hbox.set_margin_bottom(5);
+
+        let id_lbl = Label::new(Some(&id));
+        id_lbl.set_hexpand(true);
+        id_lbl.set_halign(gtk4::Align::Start);
+
+        let prefix_entry = Entry::new();
+        prefix_entry.set_text(prefix.trim_end());
+        prefix_entry.set_width_chars(6);
+
+        hbox.append(&id_lbl);
+        hbox.append(&prefix_entry);
+        row.set_child(Some(&hbox));
+        prefix_list.append(&row);
+
+        prefix_entries.push((id, prefix, prefix_entry));
+    }
+
+    let prefix_scroll = ScrolledWindow::builder()
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .min_content_height(300)
+        .vexpand(true)
+        .child(&prefix_list)
+        .build();
+    prefix_box.append(&prefix_scroll);
+
+    let save_prefix_btn = Button::with_label("Save");
+    prefix_box.append(&save_prefix_btn);
+
+    let ctx_prefix = ctx.clone();
+    let dialog_weak_prefix = dialog.downgrade();
+    save_prefix_btn.connect_clicked(move |_| {
+        for (id, current, entry) in &prefix_entries {
+            let text = entry.text().trim().to_string();
+            let result = if text.is_empty() {
+                ctx_prefix.settings.reset_prefix(id)
+            } else if format!("{} ", text) != *current {
+                ctx_prefix.settings.set_prefix(id, format!("{} ", text))
+            } else {
+                Ok(())
+            };
+            if let Err(e) = result {
+                if let Some(d) = dialog_weak_prefix.upgrade() {
+                    show_error_dialog(&d, &format!("Failed to save prefix: {}", e));
+                }
+                return;
+            }
+        }
+        if let Some(d) = dialog_weak_prefix.upgrade() { d.close(); }
+    });
+
+    notebook.append_page(&prefix_box, Some(&Label::new(Some("Prefixes"))));
+
+    // TAB 5: TERMINAL
+    let terminal_box = gtk4::Box::new(Orientation::Vertical, 10);
+    terminal_box.set_margin_top(10);
+    terminal_box.set_margin_bottom(10);
+    terminal_box.set_margin_start(10);
+    terminal_box.set_margin_end(10);
+
+    terminal_box.append(&Label::new(Some("Terminal command (e.g. kitty, wezterm start)")));
+    let terminal_entry = Entry::new();
+    terminal_entry.set_text(&ctx.settings.get_terminal().unwrap_or_default());
+    terminal_entry.set_placeholder_text(Some(&format!("Automatic ({})", detected_terminal())));
+    terminal_box.append(&terminal_entry);
+
+    let terminal_hint = Label::new(Some("Used for the x prefix, Open Terminal Here and apps marked Terminal=true. Leave empty to use $TERMINAL, xdg-terminal-exec or the first installed terminal."));
+    terminal_hint.set_wrap(true);
+    terminal_hint.add_css_class("dim-label");
+    terminal_box.append(&terminal_hint);
+
+    let hold_check = gtk4::CheckButton::with_label("Keep the terminal open after the command exits");
+    hold_check.set_active(ctx.settings.get_terminal_hold());
+    terminal_box.append(&hold_check);
+
+    let save_terminal_btn = Button::with_label("Save");
+    terminal_box.append(&save_terminal_btn);
+
+    let ctx_terminal = ctx.clone();
+    let dialog_weak_terminal = dialog.downgrade();
+    save_terminal_btn.connect_clicked(move |_| {
+        let terminal = terminal_entry.text().trim().to_string();
+        let result = ctx_terminal.settings.set_terminal(Some(terminal))
+            .and_then(|_| ctx_terminal.settings.set_terminal_hold(hold_check.is_active()));
+        if let Some(d) = dialog_weak_terminal.upgrade() {
+            match result {
+                Ok(_) => d.close(),
+                Err(e) => show_error_dialog(&d, &format!("Failed to save terminal: {}", e)),
+            }
+        }
+    });
+
+    notebook.append_page(&terminal_box, Some(&Label::new(Some("Terminal"))));
+
     dialog.set_child(Some(&notebook));
     dialog.present();
 }
 
+#[allow(clippy::too_many_arguments)]
 fn add_section_items(
     row_box: &gtk4::Box, 
-    items: Vec<crate::domain::model::App>, 
+    items: Vec<SearchResult>, 
     ctx: &AppContext, 
     window: &ApplicationWindow,
     entry: &Entry,
+    list_box: &ListBox,
+    results: &Results,
     parent_container: &gtk4::Box
 ) {
     for item in items {
@@ -1322,11 +2246,7 @@ fn add_section_items(
         let vbox = gtk4::Box::new(Orientation::Vertical, 5);
         if let Some(icon_name) = &item.icon {
             let img = gtk4::Image::new();
-            if std::path::Path::new(icon_name).exists() {
-                img.set_from_file(Some(icon_name));
-            } else {
-                img.set_icon_name(Some(icon_name));
-            }
+            set_icon(&img, icon_name);
             img.set_pixel_size(48); // Slightly larger icon for grid
             vbox.append(&img);
         }
@@ -1349,22 +2269,15 @@ fn add_section_items(
         
         btn.set_child(Some(&vbox));
         
-        let exec_cmd = item.exec_path.clone();
+        let action = item.action.clone();
         let ctx_clone = ctx.clone();
         let win_clone = window.clone();
+        let entry_clone = entry.clone();
+        let list_box_clone = list_box.clone();
+        let results_clone = results.clone();
         
         btn.connect_clicked(move |_| {
-            if exec_cmd.starts_with("internal:") {
-                 // Simplified handling for now, ideally match full logic
-                 if exec_cmd == "internal:quit" {
-                     if let Some(app) = win_clone.application() { app.quit(); }
-                 } else if exec_cmd == "internal:settings" {
-                     show_settings_dialog(&win_clone, &ctx_clone);
-                 }
-            } else {
-                ctx_clone.execute_command.execute(&exec_cmd);
-                win_clone.set_visible(false);
-            }
+            activate_action(&action, &ctx_clone, &win_clone, &entry_clone, &list_box_clone, &results_clone);
         });
         
         // Favorites Controller
@@ -1372,6 +2285,8 @@ fn add_section_items(
         let ctx_fav = ctx.clone();
         let win_fav = window.clone();
         let entry_fav = entry.clone();
+        let list_box_fav = list_box.clone();
+        let results_fav = results.clone();
         let container_fav = parent_container.clone();
         let name_fav = item.name.clone();
 
@@ -1379,7 +2294,7 @@ fn add_section_items(
             if key == gtk4::gdk::Key::space {
                 let _ = ctx_fav.settings.toggle_favorite(&name_fav);
                 // Refresh grid
-                show_overview_grid(&container_fav, &ctx_fav, &win_fav, &entry_fav);
+                show_overview_grid(&container_fav, &ctx_fav, &win_fav, &entry_fav, &list_box_fav, &results_fav);
                 return gtk4::glib::Propagation::Stop;
             }
             gtk4::glib::Propagation::Proceed
@@ -1394,7 +2309,9 @@ fn show_overview_grid(
     container: &gtk4::Box, 
     ctx: &AppContext, 
     window: &ApplicationWindow, 
-    entry: &Entry
+    entry: &Entry,
+    list_box: &ListBox,
+    results: &Results
 ) {
     // Clear existing
     while let Some(child) = container.first_child() {
@@ -1407,7 +2324,7 @@ fn show_overview_grid(
     // We need to pass entry to the closure to capture it for the first section
     let entry_weak = entry.downgrade();
     
-    let create_section = move |title: &str, items: Vec<crate::domain::model::App>| {
+    let create_section = move |title: &str, items: Vec<SearchResult>| {
         if items.is_empty() { return; }
         
         let label = Label::new(Some(title));
@@ -1426,7 +2343,7 @@ fn show_overview_grid(
         row_box.set_margin_bottom(10);
         
         // Add items
-        add_section_items(&row_box, items, ctx, window, entry, container);
+        add_section_items(&row_box, items, ctx, window, entry, list_box, results, container);
         
         scroll.set_child(Some(&row_box));
         
@@ -0,0 +1,128 @@
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// Converts Markdown, as written by the assistant, to Pango markup for a
+/// Label. Unfinished input renders too, so it can be called on every token.
+pub fn markdown_to_pango(text: &str) -> String {
+    let mut out = String::new();
+    // Next item number of each open list; None for bullet lists
+    let mut lists: Vec<Option<u64>> = Vec::new();
+
+    for event in Parser::new_ext(text, Options::ENABLE_STRIKETHROUGH) {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Heading { level, .. } => {
+                    let size = if level == HeadingLevel::H1 { "x-large" } else { "large" };
+                    out.push_str(&format!("<span size=\"{}\" weight=\"bold\">", size));
+                },
+                Tag::CodeBlock(_) => out.push_str("<tt>"),
+                Tag::BlockQuote(_) => out.push_str("<i>"),
+                Tag::Strong => out.push_str("<b>"),
+                Tag::Emphasis => out.push_str("<i>"),
+                Tag::Strikethrough => out.push_str("<s>"),
+                Tag::Link { .. } => out.push_str("<u>"),
+                Tag::List(start) => {
+                    end_line(&mut out);
+                    lists.push(start);
+                },
+                Tag::Item => {
+                    out.push_str(&"    ".repeat(lists.len().saturating_sub(1)));
+                    match lists.last_mut() {
+                        Some(Some(number)) => {
+                            out.push_str(&format!("{}. ", number));
+                            *number += 1;
+                        },
+                        _ => out.push_str("• "),
+                    }
+                },
+                _ => {},
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Heading(_) => {
+                    out.push_str("</span>");
+                    end_block(&mut out);
+                },
+                TagEnd::CodeBlock => {
+                    // The block's text ends with its last newline
+                    if out.ends_with('\n') {
+                        out.pop();
+                    }
+                    out.push_str("</tt>");
+                    end_block(&mut out);
+                },
+                TagEnd::BlockQuote(_) => {
+                    let trimmed = out.trim_end().len();
+                    out.truncate(trimmed);
+                    out.push_str("</i>");
+                    end_block(&mut out);
+                },
+                TagEnd::Paragraph => {
+                    if lists.is_empty() { end_block(&mut out) } else { end_line(&mut out) }
+                },
+                TagEnd::Strong => out.push_str("</b>"),
+                TagEnd::Emphasis => out.push_str("</i>"),
+                TagEnd::Strikethrough => out.push_str("</s>"),
+                TagEnd::Link => out.push_str("</u>"),
+                TagEnd::List(_) => {
+                    lists.pop();
+                    if lists.is_empty() { end_block(&mut out) }
+                },
+                TagEnd::Item => end_line(&mut out),
+                _ => {},
+            },
+            Event::Text(text) => out.push_str(&escape(&text)),
+            Event::Code(code) => out.push_str(&format!("<tt>{}</tt>", escape(&code))),
+            Event::SoftBreak => out.push(' '),
+            Event::HardBreak => out.push('\n'),
+            Event::Rule => {
+                out.push_str("――――――――");
+                end_block(&mut out);
+            },
+            // Markup in the answer is shown as written
+            Event::Html(html) | Event::InlineHtml(html) => out.push_str(&escape(&html)),
+            _ => {},
+        }
+    }
+    out.trim_end().to_string()
+}
+
+fn end_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Separates blocks by an empty line.
+fn end_block(out: &mut String) {
+    end_line(out);
+    if !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_blocks_and_inline_markup() {
+        let markdown = "# Steps\nRun **this** with `cargo`:\n\n```sh\ncargo build && ls <dir>\n```\n\n1. first\n2. *second*\n\n- a\n  - b";
+        assert_eq!(
+            markdown_to_pango(markdown),
+            "<span size=\"x-large\" weight=\"bold\">Steps</span>\n\n\
+             Run <b>this</b> with <tt>cargo</tt>:\n\n\
+             <tt>cargo build &amp;&amp; ls &lt;dir&gt;</tt>\n\n\
+             1. first\n2. <i>second</i>\n\n\
+             • a\n    • b"
+        );
+    }
+
+    #[test]
+    fn test_renders_unfinished_answers() {
+        assert_eq!(markdown_to_pango("Use **bo"), "Use **bo");
+        assert_eq!(markdown_to_pango("```\nlet x = 1;"), "<tt>let x = 1;</tt>");
+    }
+}
@@ -1 +1,2 @@
 pub mod app_window;
+pub mod markdown;
@@ -7,8 +7,9 @@ use launch::*;
 
 fn main() {
     // 1. Instantiate Adapters
+    let window_adapter: Arc<dyn IWindowRepository + Send + Sync> = Arc::new(SystemWindowAdapter::new());
     let app_repo_inner = Arc::new(LinuxAppRepoAdapter::new());
-    let process_monitor_inner = Arc::new(ProcFsMonitorAdapter::new());
+    let process_monitor_inner = Arc::new(ProcFsMonitorAdapter::with_windows(window_adapter.clone()));
     
     // Cached Service
     let app_cache = Arc::new(AppCacheService::new(app_repo_inner, process_monitor_inner));
@@ -17,19 +18,24 @@ fn main() {
     let app_repo = app_cache.clone();
     let process_monitor = app_cache.clone();
 
-    let command_executor = Arc::new(SystemCommandExecutorAdapter::new());
+    // Collects commands that fail right after starting, for the UI to report
+    let process_watcher = ProcessWatcher::new();
+    let command_executor = Arc::new(SystemCommandExecutorAdapter::with_watcher(process_watcher.clone()));
     let fs_adapter = Arc::new(LocalFileSystemAdapter::new());
-    let power_adapter: Arc<dyn ISystemPower + Send + Sync> = Arc::new(SystemAdapter::new());
-    let window_adapter: Arc<dyn IWindowRepository + Send + Sync> = Arc::new(SystemWindowAdapter::new());
+    let mime_adapter = Arc::new(XdgMimeAdapter::new());
+    let power_adapter: Arc<dyn ISystemPower + Send + Sync> = Arc::new(SystemAdapter::with_watcher(process_watcher));
     let calculator_adapter = Arc::new(MevalCalculatorAdapter::new());
     let dictionary_adapter = Arc::new(SmartDictionaryAdapter::new());
-    let llm_adapter = Arc::new(OllamaAdapter::new("llama3")); // Default model
-    let file_indexer = Arc::new(FileIndexerAdapter::new());
-    file_indexer.index_home();
     let time_adapter: Arc<dyn ITimeService + Send + Sync> = Arc::new(TimeAdapter::new());
     
     // Persistence
     let settings_store = Arc::new(SettingsStore::new());
+    // Uses the backend and model chosen in the settings
+    let llm_adapter = Arc::new(LlmRouter::new(settings_store.clone()));
+    let file_indexer = Arc::new(FileIndexerAdapter::new(settings_store.get_index_settings()));
+    file_indexer.start();
+    let passage_index = Arc::new(EmbeddingIndexAdapter::new(llm_adapter.clone(), file_indexer.clone(), settings_store.get_rag_settings()));
+    passage_index.resume();
     let shortcut_adapter = Arc::new(JsonShortcutAdapter::new(settings_store.clone()));
     let macro_adapter = Arc::new(JsonMacroAdapter::new(settings_store.clone()));
 
@@ -38,18 +44,21 @@ fn main() {
         app_repo,
         process_monitor,
         fs_adapter,
+        mime_adapter,
         shortcut_adapter.clone(),
         macro_adapter.clone(),
-        window_adapter.clone(),
+        window_adapter,
         power_adapter.clone(),
         calculator_adapter,
         dictionary_adapter,
-        llm_adapter,
+        AiServices { llm: llm_adapter, passages: passage_index, chats: Arc::new(ChatStore::new()) },
         file_indexer,
-        time_adapter.clone(),
+        time_adapter,
         settings_store.clone(),
+        Arc::new(HistoryStore::new()),
     ));
-    let execute_command = Arc::new(ExecuteCommand::new(command_executor, macro_adapter, omnibar.clone(), power_adapter.clone(), window_adapter, time_adapter));
+    let terminal_adapter = Arc::new(TerminalAdapter::new(settings_store.clone()));
+    let execute_command = Arc::new(ExecuteCommand::new(command_executor, macro_adapter, omnibar.clone(), power_adapter.clone(), terminal_adapter));
 
     // 3. Create Context
     let ctx = AppContext {
@@ -64,6 +73,15 @@ fn main() {
         .flags(gtk4::gio::ApplicationFlags::HANDLES_COMMAND_LINE) // Enable CLI handling
         .build();
 
+    // Refresh running status only while the launcher window is on screen
+    let app_cache_visibility = app_cache.clone();
+    app.connect_window_added(move |_, window| {
+        if window.is::<gtk4::ApplicationWindow>() {
+            let cache = app_cache_visibility.clone();
+            window.connect_visible_notify(move |w| cache.set_visible(w.is_visible()));
+        }
+    });
+
     // Keep app alive even when windows are hidden
     let hold_guard = std::rc::Rc::new(std::cell::RefCell::new(None));
 
@@ -112,4 +130,5 @@ fn main() {
     });
 
     app.run();
+    app_cache.stop();
 }
@@ -1,13 +1,29 @@
-use crate::domain::model::{App, Macro, MacroAction, Window};
+use crate::domain::model::{App, ChatError, ChatMessage, ChatRole, ChatSession, CommandSpec, ContentMatch, FileMatch, FileQuery, IndexedFile, DesktopAction, Macro, MacroAction, MimeHandler, Passage, ToolSpec, UsageRecord, Window};
 use crate::domain::ports::*;
-use crate::application::use_cases::omnibar::Omnibar;
+use crate::application::use_cases::omnibar::{AiServices, Omnibar};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 // Mocks
 pub struct MockAppRepo;
 impl IAppRepository for MockAppRepo {
-    fn find_apps(&self) -> Vec<App> { vec![] }
+    fn find_apps(&self) -> Vec<App> {
+        vec![App {
+            name: "Firefox".to_string(),
+            exec_path: "firefox".to_string(),
+            command: CommandSpec::new("firefox", Vec::<String>::new()),
+            icon: Some("firefox".to_string()),
+            desktop_id: Some("firefox.desktop".to_string()),
+            actions: vec![DesktopAction {
+                id: "new-private-window".to_string(),
+                name: "New Private Window".to_string(),
+                icon: None,
+                exec_path: "firefox --private-window".to_string(),
+                command: CommandSpec::new("firefox", ["--private-window"]),
+            }],
+            ..Default::default()
+        }]
+    }
 }
 
 pub struct MockProcessMonitor;
@@ -21,6 +37,42 @@ impl IFileSystem for MockFS {
     fn list_dir(&self, _path: &str) -> Vec<String> { vec![] }
     fn is_dir(&self, _path: &str) -> bool { false }
     fn exists(&self, _path: &str) -> bool { false }
+    fn trash(&self, _path: &str) -> Result<(), String> { Ok(()) }
+    fn rename(&self, _path: &str, _new_path: &str) -> Result<(), String> { Ok(()) }
+    fn create_dir(&self, _path: &str) -> Result<(), String> { Ok(()) }
+}
+
+/// Text files open in the text editor or Vim, directories in Files.
+pub struct MockMime;
+impl IMimeDatabase for MockMime {
+    fn mime_type(&self, path: &str) -> String {
+        if path.ends_with(".txt") || path.ends_with(".md") {
+            "text/plain".to_string()
+        } else if path.ends_with('/') {
+            "inode/directory".to_string()
+        } else {
+            "application/octet-stream".to_string()
+        }
+    }
+    fn icon_name(&self, mime_type: &str) -> String { mime_type.replace('/', "-") }
+    fn handlers(&self, mime_type: &str) -> Vec<MimeHandler> {
+        let handler = |id: &str, name: &str, terminal: bool| MimeHandler {
+            desktop_id: format!("{}.desktop", id), name: name.to_string(), icon: None, terminal,
+        };
+        match mime_type {
+            "text/plain" => vec![handler("org.gnome.TextEditor", "Text Editor", false), handler("vim", "Vim", true)],
+            "inode/directory" => vec![handler("org.gnome.Nautilus", "Files", false)],
+            _ => vec![],
+        }
+    }
+    fn open_command(&self, handler: &MimeHandler, path: &str) -> Option<CommandSpec> {
+        let program = match handler.desktop_id.as_str() {
+            "org.gnome.TextEditor.desktop" => "gnome-text-editor",
+            "vim.desktop" => "vim",
+            _ => "nautilus",
+        };
+        Some(CommandSpec::new(program, [path]))
+    }
 }
 
 pub struct MockShortcuts;
@@ -28,7 +80,9 @@ impl IShortcutRepository for MockShortcuts {
     fn get(&self, key: &str) -> Option<String> {
         if key == "term" { Some("gnome-terminal".to_string()) } else { None }
     }
-    fn get_all(&self) -> HashMap<String, String> { HashMap::new() }
+    fn get_all(&self) -> HashMap<String, String> {
+        HashMap::from([("term".to_string(), "gnome-terminal".to_string())])
+    }
     fn add(&self, _key: String, _cmd: String) -> Result<(), String> { Ok(()) }
     fn remove(&self, _key: &str) -> Result<(), String> { Ok(()) }
 }
@@ -38,6 +92,20 @@ impl ISystemPower for MockPower {
     fn execute(&self, _action: &str) -> Result<(), String> { Ok(()) }
 }
 
+pub struct MockTerminal;
+impl ITerminalLauncher for MockTerminal {
+    fn terminal_command(&self, command: Option<&CommandSpec>, cwd: Option<&str>) -> CommandSpec {
+        let mut args = vec![];
+        if let Some(command) = command {
+            args.push("-e".to_string());
+            args.extend(command.argv());
+        }
+        let mut spec = CommandSpec::new("term", args);
+        spec.cwd = cwd.map(str::to_string);
+        spec
+    }
+}
+
 pub struct MockWindowRepo;
 impl IWindowRepository for MockWindowRepo {
     fn get_open_windows(&self) -> Vec<Window> {
@@ -47,6 +115,8 @@ impl IWindowRepository for MockWindowRepo {
             ]
     }
     fn focus_window(&self, _id: &str) -> Result<(), String> { Ok(()) }
+    fn close_window(&self, _id: &str) -> Result<(), String> { Ok(()) }
+    fn move_window_to_workspace(&self, _id: &str, _workspace: i32) -> Result<(), String> { Ok(()) }
 }
 
 pub struct MockCalculator;
@@ -65,17 +135,77 @@ impl IDictionaryService for MockDictionary {
 
 pub struct MockLLM;
 impl ILLMService for MockLLM {
-    fn query(&self, _prompt: &str, _context: Option<String>) -> Result<String, String> { Ok("AI response".to_string()) }
+    fn chat(&self, _messages: &[ChatMessage], _tools: &[ToolSpec], on_token: &mut dyn FnMut(&str), _cancel: &std::sync::atomic::AtomicBool) -> Result<ChatMessage, ChatError> {
+        on_token("AI ");
+        on_token("response");
+        Ok(ChatMessage::new(ChatRole::Assistant, "AI response"))
+    }
     fn list_models(&self) -> Result<Vec<String>, String> { Ok(vec![]) }
     fn pull_model(&self, _model: &str, _on_progress: Box<dyn Fn(f64) + Send>) -> Result<(), String> { Ok(()) }
     fn delete_model(&self, _model: &str) -> Result<(), String> { Ok(()) }
     fn set_model(&self, _model: &str) {}
+    fn embed(&self, _model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        Ok(texts.iter().map(|_| vec![1.0]).collect())
+    }
 }
 
 pub struct MockIndexer;
 impl IFileIndexer for MockIndexer {
-    fn search(&self, _term: &str) -> Vec<String> { vec![] }
-    fn index_home(&self) {}
+    fn search(&self, query: &FileQuery, _limit: usize) -> Vec<FileMatch> {
+        let path = "/home/user/notes/todo.md".to_string();
+        let md = query.extensions.is_empty() || query.extensions.iter().any(|e| e == "md");
+        if md && "todo.md".contains(query.text.as_str()) { vec![FileMatch { path, score: 100.0 }] } else { vec![] }
+    }
+    fn search_content(&self, term: &str, limit: usize) -> Vec<ContentMatch> {
+        let line = "- [ ] buy oat milk";
+        if line.contains(term) {
+            vec![ContentMatch { path: "/home/user/notes/todo.md".to_string(), line_number: 3, line: line.to_string() }]
+                .into_iter().take(limit).collect()
+        } else {
+            vec![]
+        }
+    }
+    fn text_files(&self) -> Vec<IndexedFile> {
+        vec![IndexedFile { path: "/home/user/notes/todo.md".to_string(), modified: 0 }]
+    }
+    fn start(&self) {}
+}
+
+#[derive(Default)]
+pub struct MockChats {
+    pub sessions: Mutex<Vec<ChatSession>>,
+}
+impl MockChats {
+    pub fn with_session(title: &str, question: &str) -> Self {
+        let session = ChatSession {
+            id: "1".to_string(),
+            title: title.to_string(),
+            updated: 0,
+            messages: vec![ChatMessage::new(ChatRole::User, question), ChatMessage::new(ChatRole::Assistant, "AI response")],
+        };
+        Self { sessions: Mutex::new(vec![session]) }
+    }
+}
+impl IChatHistory for MockChats {
+    fn sessions(&self) -> Vec<ChatSession> { self.sessions.lock().unwrap().clone() }
+    fn get(&self, id: &str) -> Option<ChatSession> {
+        self.sessions.lock().unwrap().iter().find(|s| s.id == id).cloned()
+    }
+    fn save(&self, session: &ChatSession) -> Result<(), String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|s| s.id != session.id);
+        sessions.insert(0, session.clone());
+        Ok(())
+    }
+}
+
+pub struct MockPassages;
+impl IPassageIndex for MockPassages {
+    fn retrieve(&self, _question: &str, limit: usize) -> Result<Vec<Passage>, String> {
+        let text = "# Todo\n- [ ] call mum\n- [ ] buy oat milk".to_string();
+        Ok(vec![Passage { path: "/home/user/notes/todo.md".to_string(), start_line: 1, end_line: 3, text }]
+            .into_iter().take(limit).collect())
+    }
 }
 
 pub struct MockTimeService;
@@ -101,20 +231,54 @@ impl IMacroRepository for MockMacro {
     fn remove(&self, _name: &str) -> Result<(), String> { Ok(()) }
 }
 
+/// In-memory history so tests never touch the real one.
+#[derive(Default)]
+pub struct MockHistory {
+    records: Mutex<Vec<UsageRecord>>,
+}
+impl IUsageHistory for MockHistory {
+    fn record(&self, key: &str, query: &str) {
+        self.records.lock().unwrap().push(UsageRecord { key: key.to_string(), query: query.to_string(), timestamp: 0 });
+    }
+    fn records(&self) -> Vec<UsageRecord> { self.records.lock().unwrap().clone() }
+}
+
 pub fn create_omnibar() -> Omnibar {
+    create_omnibar_with_windows(Arc::new(MockWindowRepo))
+}
+
+pub fn create_omnibar_with_windows(window_repo: Arc<dyn IWindowRepository + Send + Sync>) -> Omnibar {
+    build_omnibar(window_repo, Arc::new(MockLLM), Arc::new(MockIndexer))
+}
+
+pub fn create_omnibar_with_llm(llm: Arc<dyn ILLMService + Send + Sync>) -> Omnibar {
+    build_omnibar(Arc::new(MockWindowRepo), llm, Arc::new(MockIndexer))
+}
+
+pub fn create_omnibar_with_indexer(indexer: Arc<dyn IFileIndexer + Send + Sync>) -> Omnibar {
+    build_omnibar(Arc::new(MockWindowRepo), Arc::new(MockLLM), indexer)
+}
+
+fn build_omnibar(
+    window_repo: Arc<dyn IWindowRepository + Send + Sync>,
+    llm: Arc<dyn ILLMService + Send + Sync>,
+    indexer: Arc<dyn IFileIndexer + Send + Sync>,
+) -> Omnibar {
     Omnibar::new(
         Arc::new(MockAppRepo),
         Arc::new(MockProcessMonitor),
         Arc::new(MockFS),
+        Arc::new(MockMime),
         Arc::new(MockShortcuts),
         Arc::new(MockMacro),
-        Arc::new(MockWindowRepo),
+        window_repo,
         Arc::new(MockPower),
         Arc::new(MockCalculator),
         Arc::new(MockDictionary),
-        Arc::new(MockLLM),
-        Arc::new(MockIndexer),
+        AiServices { llm, passages: Arc::new(MockPassages), chats: Arc::new(MockChats::default()) },
+        indexer,
         Arc::new(MockTimeService),
         Arc::new(crate::infrastructure::services::settings_store::SettingsStore::new()),
+        Arc::new(MockHistory::default()),
     )
 }
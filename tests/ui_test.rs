@@ -17,15 +17,13 @@ fn test_ui_search_flow() {
     let settings_store = Arc::new(SettingsStore::new());
     let macro_repo = Arc::new(MockMacro);
     let power = Arc::new(MockPower);
-    let win_repo = Arc::new(MockWindowRepo);
     
     let execute_command = Arc::new(ExecuteCommand::new(
          command_executor, 
          macro_repo, 
          omnibar.clone(),
          power,
-         win_repo,
-         Arc::new(MockTimeService),
+         Arc::new(MockTerminal),
     ));
 
     let ctx = AppContext {